@@ -1,8 +1,12 @@
 use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
 
 use anyhow::*;
 use fs_extra::copy_items;
 use fs_extra::dir::CopyOptions;
+use glob::glob;
 
 fn main() -> Result<()> {
     // This tells cargo to rerun this script if something in /res/ changes.
@@ -15,7 +19,35 @@ fn main() -> Result<()> {
 
     let paths_to_copy = vec!["res/"];
 
-    copy_items(&paths_to_copy, out_dir, &copy_options)?;
+    copy_items(&paths_to_copy, &out_dir, &copy_options)?;
+
+    if env::var_os("CARGO_FEATURE_EMBED_RES").is_some() {
+        embed_res(Path::new(&out_dir))?;
+    }
+
+    Ok(())
+}
+
+// Lists every file under res with include_bytes!, keyed by its path relative to res like the loaders expect
+fn embed_res(out_dir: &Path) -> Result<()> {
+    let res = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("res");
+    let mut files = String::from("&[\n");
+
+    for path in glob(&format!("{}/**/*", res.display()))? {
+        let path = path?;
+
+        if !path.is_file() {
+            continue;
+        }
+
+        let file_name = path.strip_prefix(&res)?.to_string_lossy().replace('\\', "/");
+
+        writeln!(files, "    ({file_name:?}, include_bytes!({:?})),", path.display().to_string())?;
+    }
+
+    files.push_str("]\n");
+
+    fs::write(out_dir.join("embedded_res.rs"), files)?;
 
     Ok(())
 }
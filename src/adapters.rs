@@ -0,0 +1,87 @@
+use std::fmt::Write;
+
+use wgpu::{Adapter, Backends, Features, Instance, Surface};
+
+use crate::error::InitError;
+use crate::options::AdapterSelector;
+
+// Prints what --adapter can choose from, in the order indices refer to
+pub fn list_adapters(backends: Backends) {
+    let instance = Instance::new(backends);
+    let listing = describe_adapters(&instance, backends);
+
+    if listing.is_empty() {
+        println!("no adapters found for {backends:?}");
+    } else {
+        print!("{listing}");
+    }
+}
+
+// The first adapter the selector matches that can also present to the surface, if there is one.
+// Option::is_none_or would read better, it needs rust 1.82
+#[allow(clippy::unnecessary_map_or)]
+pub fn select_adapter(
+    instance: &Instance,
+    backends: Backends,
+    surface: Option<&Surface>,
+    selector: &AdapterSelector,
+) -> Result<Adapter, InitError> {
+    let mut matched = instance
+        .enumerate_adapters(backends)
+        .enumerate()
+        .filter(|(index, adapter)| selector.matches(*index, &adapter.get_info()))
+        .peekable();
+
+    if matched.peek().is_none() {
+        return Err(InitError::NoAdapter(format!(
+            "no adapter matches {selector:?}, these are available:\n{}",
+            describe_adapters(instance, backends),
+        )));
+    }
+
+    let presentable = matched.find(|(_, adapter)| surface.map_or(true, |surface| adapter.is_surface_supported(surface)));
+
+    let Some((_, adapter)) = presentable else {
+        return Err(InitError::NoAdapter(format!("no adapter that matches {selector:?} can present to the window")));
+    };
+
+    Ok(adapter)
+}
+
+fn describe_adapters(instance: &Instance, backends: Backends) -> String {
+    instance
+        .enumerate_adapters(backends)
+        .enumerate()
+        .fold(String::new(), |mut listing, (index, adapter)| {
+            let info = adapter.get_info();
+            let limits = adapter.limits();
+            let features = adapter.features();
+
+            let _ = writeln!(listing, "{index}: {}", info.name);
+            let _ = writeln!(
+                listing,
+                "   backend {:?}, type {:?}, vendor {:#06x}, device {:#06x}",
+                info.backend, info.device_type, info.vendor, info.device,
+            );
+            let _ = writeln!(
+                listing,
+                "   max texture {}, bind groups {}, uniform binding {}, storage binding {}, vertex buffers {}",
+                limits.max_texture_dimension_2d,
+                limits.max_bind_groups,
+                limits.max_uniform_buffer_binding_size,
+                limits.max_storage_buffer_binding_size,
+                limits.max_vertex_buffers,
+            );
+            let _ = writeln!(
+                listing,
+                "   wireframe {}, timestamps {}, compressed textures bc {}, etc2 {}, astc {}",
+                features.contains(Features::POLYGON_MODE_LINE),
+                features.contains(Features::TIMESTAMP_QUERY),
+                features.contains(Features::TEXTURE_COMPRESSION_BC),
+                features.contains(Features::TEXTURE_COMPRESSION_ETC2),
+                features.contains(Features::TEXTURE_COMPRESSION_ASTC_LDR),
+            );
+
+            listing
+        })
+}
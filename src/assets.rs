@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+use wgpu::{Device, Sampler};
+
+use crate::models::{Material, Model, SamplerSettings, Texture};
+
+// Everything loaded from res that lives on the gpu, shared by the file it came from
+#[derive(Default)]
+pub struct Assets {
+    pub materials: AssetStore<Material>,
+    pub models: AssetStore<Model>,
+    pub textures: AssetStore<Texture>,
+    pub samplers: SamplerCache,
+}
+
+impl Assets {
+    // Materials go with the last model that uses them, and textures with the last material
+    pub fn release_model(&mut self, handle: Handle<Model>) {
+        for material in self.models.release_with(handle, &mut self.materials) {
+            for texture in material.held() {
+                self.textures.release(texture);
+            }
+        }
+    }
+}
+
+// Assets that keep others in another store loaded, see AssetStore::release_with
+pub trait HoldsAssets<T> {
+    fn held(&self) -> Vec<Handle<T>>;
+}
+
+impl HoldsAssets<Material> for Model {
+    fn held(&self) -> Vec<Handle<Material>> {
+        self.materials.clone()
+    }
+}
+
+impl HoldsAssets<Texture> for Material {
+    fn held(&self) -> Vec<Handle<Texture>> {
+        vec![self.diffuse_texture, self.normal_texture]
+    }
+}
+
+// Textures and materials sampled the same way share one sampler. There's only ever a handful,
+// so they're kept until the device goes away rather than counted like the assets
+#[derive(Default)]
+pub struct SamplerCache {
+    samplers: Vec<(SamplerSettings, Arc<Sampler>)>,
+}
+
+impl SamplerCache {
+    pub fn get(&mut self, device: &Device, settings: SamplerSettings) -> Arc<Sampler> {
+        if let Some((_, sampler)) = self.samplers.iter().find(|(cached, _)| *cached == settings) {
+            return Arc::clone(sampler);
+        }
+
+        let sampler = Arc::new(Texture::create_sampler(device, &settings));
+
+        self.samplers.push((settings, Arc::clone(&sampler)));
+
+        sampler
+    }
+
+    // The settings a cached sampler was created with
+    pub fn settings(&self, sampler: &Arc<Sampler>) -> Option<SamplerSettings> {
+        self.samplers.iter().find(|(_, cached)| Arc::ptr_eq(cached, sampler)).map(|(settings, _)| *settings)
+    }
+}
+
+// Refers to an asset in its store. The generation tells a released slot apart from what reused it
+pub struct Handle<T> {
+    index: usize,
+    generation: u32,
+    marker: PhantomData<fn() -> T>,
+}
+
+// Derives would require T to implement these too
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> Debug for Handle<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Handle({}v{})", self.index, self.generation)
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    entry: Option<Entry<T>>,
+}
+
+struct Entry<T> {
+    // Usually the file name, materials add their name to the model's
+    key: String,
+    asset: T,
+    references: u32,
+}
+
+pub struct AssetStore<T> {
+    slots: Vec<Slot<T>>,
+    keys: HashMap<String, Handle<T>>,
+}
+
+impl<T> Default for AssetStore<T> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            keys: HashMap::new(),
+        }
+    }
+}
+
+impl<T> AssetStore<T> {
+    // Another reference to what's already loaded from the key, every one of them has to be released
+    pub fn acquire(&mut self, key: &str) -> Option<Handle<T>> {
+        let handle = *self.keys.get(key)?;
+
+        if let Some(entry) = self.entry_mut(handle) {
+            entry.references += 1;
+        }
+
+        Some(handle)
+    }
+
+    // Looks the key up without taking a reference
+    pub fn handle(&self, key: &str) -> Option<Handle<T>> {
+        self.keys.get(key).copied()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.entry.as_ref()).map(|entry| &entry.asset)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|slot| slot.entry.as_mut()).map(|entry| &mut entry.asset)
+    }
+
+    // The asset starts with a single reference, call acquire first to share an existing one.
+    // A key that's already there refers to the new asset from then on
+    pub fn insert(&mut self, key: &str, asset: T) -> Handle<T> {
+        let entry = Entry { key: key.to_owned(), asset, references: 1 };
+
+        let index = if let Some(index) = self.slots.iter().position(|slot| slot.entry.is_none()) {
+            self.slots[index].entry = Some(entry);
+            index
+        } else {
+            self.slots.push(Slot { generation: 0, entry: Some(entry) });
+            self.slots.len() - 1
+        };
+
+        let handle = Handle { index, generation: self.slots[index].generation, marker: PhantomData };
+
+        self.keys.insert(key.to_owned(), handle);
+
+        handle
+    }
+
+    // Gives the asset back once its last reference is gone, the handle is stale from then on
+    pub fn release(&mut self, handle: Handle<T>) -> Option<T> {
+        let entry = self.entry_mut(handle)?;
+
+        entry.references -= 1;
+
+        if entry.references > 0 {
+            return None;
+        }
+
+        let slot = &mut self.slots[handle.index];
+        let entry = slot.entry.take()?;
+
+        slot.generation = slot.generation.wrapping_add(1);
+
+        if self.keys.get(&entry.key) == Some(&handle) {
+            self.keys.remove(&entry.key);
+        }
+
+        Some(entry.asset)
+    }
+
+    // Like release, and once the asset is gone what it held is released too.
+    // Gives back the held assets that went with it, which may hold others in turn
+    pub fn release_with<U>(&mut self, handle: Handle<T>, held: &mut AssetStore<U>) -> Vec<U>
+        where T: HoldsAssets<U>
+    {
+        self.release(handle)
+            .map(|asset| asset.held().into_iter().filter_map(|handle| held.release(handle)).collect())
+            .unwrap_or_default()
+    }
+
+    fn entry(&self, handle: Handle<T>) -> Option<&Entry<T>> {
+        self.slots
+            .get(handle.index)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.entry.as_ref())
+    }
+
+    fn entry_mut(&mut self, handle: Handle<T>) -> Option<&mut Entry<T>> {
+        self.slots
+            .get_mut(handle.index)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.entry.as_mut())
+    }
+}
+
+// Like a map, indexing with a released handle panics
+impl<T> Index<Handle<T>> for AssetStore<T> {
+    type Output = T;
+
+    fn index(&self, handle: Handle<T>) -> &T {
+        &self.entry(handle).expect("asset handle was released").asset
+    }
+}
+
+impl<T> IndexMut<Handle<T>> for AssetStore<T> {
+    fn index_mut(&mut self, handle: Handle<T>) -> &mut T {
+        &mut self.entry_mut(handle).expect("asset handle was released").asset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stands in for a model or material, the real ones need a device
+    struct Holder(Vec<Handle<&'static str>>);
+
+    impl HoldsAssets<&'static str> for Holder {
+        fn held(&self) -> Vec<Handle<&'static str>> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn acquire_shares_by_key() {
+        let mut store = AssetStore::default();
+        let handle = store.insert("a.png", "a");
+
+        assert_eq!(store.acquire("a.png"), Some(handle));
+        assert_eq!(store.acquire("b.png"), None);
+        assert_eq!(store.handle("a.png"), Some(handle));
+    }
+
+    #[test]
+    fn release_counts_references() {
+        let mut store = AssetStore::default();
+        let handle = store.insert("a.png", "a");
+
+        store.acquire("a.png");
+
+        assert_eq!(store.release(handle), None);
+        assert_eq!(store[handle], "a");
+        assert_eq!(store.release(handle), Some("a"));
+        assert_eq!(store.handle("a.png"), None);
+        assert_eq!(store.acquire("a.png"), None);
+    }
+
+    #[test]
+    fn reused_slots_leave_handles_stale() {
+        let mut store = AssetStore::default();
+        let old = store.insert("a.png", "a");
+
+        store.release(old);
+
+        let new = store.insert("b.png", "b");
+
+        assert_eq!(old.index, new.index);
+        assert_ne!(old, new);
+        assert_eq!(store.release(old), None);
+        assert_eq!(store[new], "b");
+    }
+
+    #[test]
+    #[should_panic(expected = "asset handle was released")]
+    fn indexing_stale_handles_panics() {
+        let mut store = AssetStore::default();
+        let handle = store.insert("a.png", "a");
+
+        store.release(handle);
+        let _ = store[handle];
+    }
+
+    #[test]
+    fn insert_takes_keys_over() {
+        let mut store = AssetStore::default();
+        let old = store.insert("a.png", "old");
+        let new = store.insert("a.png", "new");
+
+        assert_eq!(store.handle("a.png"), Some(new));
+
+        // Releasing the old one leaves the key with the new one
+        assert_eq!(store.release(old), Some("old"));
+        assert_eq!(store.handle("a.png"), Some(new));
+    }
+
+    #[test]
+    fn release_with_cascades() {
+        let mut textures = AssetStore::default();
+        let mut materials = AssetStore::default();
+
+        let shared = textures.insert("shared.png", "shared");
+        let own = textures.insert("own.png", "own");
+
+        textures.acquire("shared.png");
+
+        let first = materials.insert("a.obj#first", Holder(vec![shared, own]));
+        let second = materials.insert("b.obj#second", Holder(vec![shared]));
+
+        // Only what nothing else holds goes with the first one
+        assert_eq!(materials.release_with(first, &mut textures), vec!["own"]);
+        assert_eq!(textures.handle("shared.png"), Some(shared));
+        assert_eq!(textures.handle("own.png"), None);
+
+        assert_eq!(materials.release_with(second, &mut textures), vec!["shared"]);
+        assert_eq!(textures.handle("shared.png"), None);
+
+        // Stale handles release nothing
+        assert!(materials.release_with(first, &mut textures).is_empty());
+    }
+
+    #[test]
+    fn release_with_keeps_what_is_still_held() {
+        let mut textures = AssetStore::default();
+        let mut materials = AssetStore::default();
+
+        let texture = textures.insert("a.png", "a");
+        let material = materials.insert("a.obj#a", Holder(vec![texture]));
+
+        materials.acquire("a.obj#a");
+
+        assert!(materials.release_with(material, &mut textures).is_empty());
+        assert_eq!(textures[texture], "a");
+    }
+}
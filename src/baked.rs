@@ -0,0 +1,461 @@
+use std::mem::size_of;
+
+use anyhow::{bail, ensure, Context};
+use bytemuck::{cast_slice, pod_collect_to_vec};
+use cgmath::Point3;
+use image::{DynamicImage, RgbaImage};
+use wgpu::{AddressMode, FilterMode};
+
+use crate::models::{Aabb, ModelVertex, SamplerSettings};
+use crate::resources::{MeshData, ModelData};
+
+// What the pack binary writes, loading it is copying bytes around. Everything is little endian,
+// the version goes up whenever the layout changes and older files have to be packed again.
+//
+// A mesh file is the header, then each material as its name and texture file names (a u32 length and utf8)
+// and how each texture is sampled, then each mesh as its material, index size, vertex and index counts and bounds, followed by
+// its ModelVertex data and its indices, padded to 4 bytes.
+// A texture file is the header, then the format, width and height, followed by the pixels
+pub const MESH_EXTENSION: &str = "mesh";
+pub const TEXTURE_EXTENSION: &str = "tex";
+
+const MESH_MAGIC: [u8; 4] = *b"LWMS";
+const TEXTURE_MAGIC: [u8; 4] = *b"LWTX";
+const VERSION: u32 = 2;
+
+// The only texture format so far, what Texture::from_image uploads
+const FORMAT_RGBA8: u32 = 0;
+
+// Sampler settings store modes by their index in these
+const ADDRESS_MODES: [AddressMode; 4] =
+    [AddressMode::ClampToEdge, AddressMode::Repeat, AddressMode::MirrorRepeat, AddressMode::ClampToBorder];
+const FILTER_MODES: [FilterMode; 2] = [FilterMode::Nearest, FilterMode::Linear];
+
+// Texture file names are relative to res, like the ones in an mtl file
+pub struct BakedMaterial {
+    pub name: String,
+    pub diffuse_texture: String,
+    pub normal_texture: String,
+    pub diffuse_sampler: SamplerSettings,
+    pub normal_sampler: SamplerSettings,
+}
+
+pub struct BakedModel {
+    pub meshes: Vec<MeshData>,
+    pub materials: Vec<BakedMaterial>,
+}
+
+pub fn read_model(data: &[u8]) -> anyhow::Result<BakedModel> {
+    let mut reader = Reader::new(data, MESH_MAGIC)?;
+    let mesh_count = reader.u32()?;
+    let material_count = reader.u32()?;
+
+    let materials = (0..material_count)
+        .map(|_| Ok(BakedMaterial {
+            name: reader.string()?,
+            diffuse_texture: reader.string()?,
+            normal_texture: reader.string()?,
+            diffuse_sampler: reader.sampler()?,
+            normal_sampler: reader.sampler()?,
+        }))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let meshes = (0..mesh_count)
+        .map(|_| reader.mesh(materials.len()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    ensure!(!meshes.is_empty(), "there are no meshes");
+
+    Ok(BakedModel { meshes, materials })
+}
+
+// Materials refer to textures by the file names in them, the pack binary points them at the packed textures
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_model(model: &ModelData) -> Vec<u8> {
+    let mut writer = Writer::new(MESH_MAGIC);
+
+    writer.u32(length(model.meshes.len()));
+    writer.u32(length(model.materials.len()));
+
+    for material in &model.materials {
+        writer.string(&material.name);
+        writer.string(&material.diffuse_texture.file_name);
+        writer.string(&material.normal_texture.file_name);
+        writer.sampler(&material.diffuse_texture.sampler);
+        writer.sampler(&material.normal_texture.sampler);
+    }
+
+    for mesh in &model.meshes {
+        // Most meshes are small enough for 16 bit indices, which halves them
+        let short = mesh.vertices.len() <= usize::from(u16::MAX) + 1;
+
+        writer.u32(length(mesh.material));
+        writer.u32(if short { 2 } else { 4 });
+        writer.u32(length(mesh.vertices.len()));
+        writer.u32(length(mesh.indices.len()));
+
+        for point in [mesh.bounds.min, mesh.bounds.max] {
+            for value in [point.x, point.y, point.z] {
+                writer.f32(value);
+            }
+        }
+
+        writer.bytes(cast_slice(&mesh.vertices));
+
+        if short {
+            #[allow(clippy::cast_possible_truncation)]
+            let indices = mesh.indices.iter().map(|&index| index as u16).collect::<Vec<_>>();
+
+            writer.bytes(cast_slice(&indices));
+        } else {
+            writer.bytes(cast_slice(&mesh.indices));
+        }
+
+        writer.pad();
+    }
+
+    writer.data
+}
+
+#[inline]
+pub fn is_texture(data: &[u8]) -> bool {
+    data.starts_with(&TEXTURE_MAGIC)
+}
+
+pub fn read_texture(data: &[u8]) -> anyhow::Result<DynamicImage> {
+    let mut reader = Reader::new(data, TEXTURE_MAGIC)?;
+    let format = reader.u32()?;
+
+    ensure!(format == FORMAT_RGBA8, "unknown texture format {format}");
+
+    let width = reader.u32()?;
+    let height = reader.u32()?;
+    let pixels = reader.array(width as usize * height as usize, 4)?;
+
+    let image = RgbaImage::from_raw(width, height, pixels.to_vec()).context("the pixels don't fit the size")?;
+
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_texture(image: &DynamicImage) -> Vec<u8> {
+    let image = image.to_rgba8();
+    let mut writer = Writer::new(TEXTURE_MAGIC);
+
+    writer.u32(FORMAT_RGBA8);
+    writer.u32(image.width());
+    writer.u32(image.height());
+    writer.bytes(image.as_raw());
+
+    writer.data
+}
+
+// Counts in the files are u32, nothing the packer reads comes close
+#[cfg(not(target_arch = "wasm32"))]
+fn length(length: usize) -> u32 {
+    u32::try_from(length).expect("too many elements for a packed file")
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], magic: [u8; 4]) -> anyhow::Result<Self> {
+        let mut reader = Self { data, offset: 0 };
+
+        ensure!(reader.bytes(4)? == magic, "not a packed file");
+
+        let version = reader.u32()?;
+
+        ensure!(version == VERSION, "packed with version {version}, this reads version {VERSION}, pack it again");
+
+        Ok(reader)
+    }
+
+    fn bytes(&mut self, length: usize) -> anyhow::Result<&'a [u8]> {
+        let bytes = self.offset
+            .checked_add(length)
+            .and_then(|end| self.data.get(self.offset..end))
+            .context("the file is cut short")?;
+
+        self.offset += length;
+
+        Ok(bytes)
+    }
+
+    // Counts come from the file, so they can't be trusted not to overflow
+    fn array(&mut self, count: usize, size: usize) -> anyhow::Result<&'a [u8]> {
+        self.bytes(count.checked_mul(size).context("the file is cut short")?)
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        let bytes = self.bytes(4)?;
+
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn f32(&mut self) -> anyhow::Result<f32> {
+        self.u32().map(f32::from_bits)
+    }
+
+    fn string(&mut self) -> anyhow::Result<String> {
+        let length = self.u32()? as usize;
+
+        Ok(std::str::from_utf8(self.bytes(length)?)?.to_owned())
+    }
+
+    fn mode<T: Copy>(&mut self, modes: &[T]) -> anyhow::Result<T> {
+        let index = self.u32()?;
+
+        modes.get(index as usize).copied().with_context(|| format!("unknown sampler mode {index}"))
+    }
+
+    fn sampler(&mut self) -> anyhow::Result<SamplerSettings> {
+        let sampler = SamplerSettings {
+            address_mode_u: self.mode(&ADDRESS_MODES)?,
+            address_mode_v: self.mode(&ADDRESS_MODES)?,
+            mag_filter: self.mode(&FILTER_MODES)?,
+            min_filter: self.mode(&FILTER_MODES)?,
+            mipmap_filter: self.mode(&FILTER_MODES)?,
+            anisotropy: u8::try_from(self.u32()?).context("anisotropy is out of range")?,
+            lod_min_clamp: self.f32()?,
+            lod_max_clamp: self.f32()?,
+        };
+
+        // The device would only refuse them once the model's uploaded. Written so nan fails too
+        let (min, max) = (sampler.lod_min_clamp, sampler.lod_max_clamp);
+
+        ensure!(min >= 0.0 && min <= max, "the lod clamps {min} to {max} aren't a range from 0 up");
+
+        Ok(sampler)
+    }
+
+    fn point(&mut self) -> anyhow::Result<Point3<f32>> {
+        Ok(Point3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    fn mesh(&mut self, material_count: usize) -> anyhow::Result<MeshData> {
+        let material = self.u32()? as usize;
+        let index_size = self.u32()? as usize;
+        let vertex_count = self.u32()? as usize;
+        let index_count = self.u32()? as usize;
+        let bounds = Aabb { min: self.point()?, max: self.point()? };
+
+        // Drawing indexes materials by mesh and vertices by index, either would only fail on the gpu
+        ensure!(material < material_count, "a mesh uses material {material}, there are {material_count}");
+
+        // The data is only byte aligned, so it's copied out rather than cast in place
+        let vertices: Vec<ModelVertex> = pod_collect_to_vec(self.array(vertex_count, size_of::<ModelVertex>())?);
+
+        let indices = match index_size {
+            2 => pod_collect_to_vec::<_, u16>(self.array(index_count, 2)?).into_iter().map(u32::from).collect(),
+            4 => pod_collect_to_vec(self.array(index_count, 4)?),
+            _ => bail!("indices can't be {index_size} bytes"),
+        };
+
+        if let Some(index) = indices.iter().find(|&&index| index as usize >= vertex_count) {
+            bail!("index {index} is past the {vertex_count} vertices");
+        }
+
+        self.bytes((4 - self.offset % 4) % 4)?;
+
+        Ok(MeshData { vertices, indices, material, bounds })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Writer {
+    data: Vec<u8>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Writer {
+    fn new(magic: [u8; 4]) -> Self {
+        let mut writer = Self { data: Vec::new() };
+
+        writer.bytes(&magic);
+        writer.u32(VERSION);
+
+        writer
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(length(value.len()));
+        self.bytes(value.as_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn mode<T: PartialEq>(&mut self, modes: &[T], mode: &T) {
+        self.u32(length(modes.iter().position(|known| known == mode).expect("every mode is listed")));
+    }
+
+    fn sampler(&mut self, sampler: &SamplerSettings) {
+        self.mode(&ADDRESS_MODES, &sampler.address_mode_u);
+        self.mode(&ADDRESS_MODES, &sampler.address_mode_v);
+        self.mode(&FILTER_MODES, &sampler.mag_filter);
+        self.mode(&FILTER_MODES, &sampler.min_filter);
+        self.mode(&FILTER_MODES, &sampler.mipmap_filter);
+        self.u32(u32::from(sampler.anisotropy));
+        self.f32(sampler.lod_min_clamp);
+        self.f32(sampler.lod_max_clamp);
+    }
+
+    fn pad(&mut self) {
+        self.data.resize(self.data.len().next_multiple_of(4), 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::Zeroable;
+    use image::Rgba;
+
+    use super::*;
+    use crate::models::ColorSpace;
+    use crate::resources::{MaterialData, TextureData, TextureImage};
+
+    fn vertex(position: [f32; 3]) -> ModelVertex {
+        ModelVertex { position, tex_coords: [position[0], position[1]], ..ModelVertex::zeroed() }
+    }
+
+    fn texture(file_name: &str, sampler: SamplerSettings) -> TextureData {
+        TextureData {
+            file_name: file_name.to_owned(),
+            color_space: ColorSpace::Srgb,
+            sampler,
+            image: TextureImage::Decoded(DynamicImage::new_rgba8(1, 1)),
+        }
+    }
+
+    fn model(vertex_count: u32) -> ModelData {
+        #[allow(clippy::cast_precision_loss)]
+        let vertices = (0..vertex_count).map(|index| vertex([index as f32, 1.0, 2.0])).collect();
+        let indices = (0..vertex_count).rev().collect();
+
+        ModelData {
+            meshes: vec![MeshData {
+                vertices,
+                indices,
+                material: 0,
+                bounds: Aabb { min: Point3::new(-1.0, -2.0, -3.0), max: Point3::new(1.0, 2.0, 3.0) },
+            }],
+            materials: vec![MaterialData {
+                name: "Tiled".to_owned(),
+                diffuse_texture: texture("textures/diffuse.tex", SamplerSettings::CLAMP),
+                normal_texture: texture("normal.tex", SamplerSettings { anisotropy: 4, ..SamplerSettings::REPEAT }),
+            }],
+        }
+    }
+
+    fn assert_round_trips(model: &ModelData) {
+        let baked = read_model(&write_model(model)).unwrap();
+
+        assert_eq!(baked.meshes.len(), model.meshes.len());
+
+        for (read, written) in baked.meshes.iter().zip(&model.meshes) {
+            assert_eq!(cast_slice::<_, u8>(&read.vertices), cast_slice::<_, u8>(&written.vertices));
+            assert_eq!(read.indices, written.indices);
+            assert_eq!(read.material, written.material);
+            assert_eq!(read.bounds, written.bounds);
+        }
+
+        let material = &baked.materials[0];
+
+        assert_eq!(material.name, "Tiled");
+        assert_eq!(material.diffuse_texture, "textures/diffuse.tex");
+        assert_eq!(material.normal_texture, "normal.tex");
+        assert_eq!(material.diffuse_sampler, model.materials[0].diffuse_texture.sampler);
+        assert_eq!(material.normal_sampler, model.materials[0].normal_texture.sampler);
+    }
+
+    fn error(result: anyhow::Result<impl Sized>) -> String {
+        format!("{:#}", result.err().expect("read a broken file"))
+    }
+
+    #[test]
+    fn model_round_trips() {
+        assert_round_trips(&model(3));
+    }
+
+    #[test]
+    fn large_model_round_trips() {
+        // Past what 16 bit indices can reach
+        assert_round_trips(&model(u32::from(u16::MAX) + 2));
+    }
+
+    #[test]
+    fn bad_lod_clamps_are_refused() {
+        for (min, max) in [(-1.0, 8.0), (4.0, 2.0), (f32::NAN, 8.0), (0.0, f32::NAN)] {
+            let mut model = model(3);
+
+            model.materials[0].normal_texture.sampler =
+                SamplerSettings { lod_min_clamp: min, lod_max_clamp: max, ..SamplerSettings::REPEAT };
+
+            assert!(error(read_model(&write_model(&model))).contains("lod clamps"), "{min} to {max}");
+        }
+    }
+
+    #[test]
+    fn texture_round_trips() {
+        let image = RgbaImage::from_fn(3, 2, |x, y| Rgba([x as u8, y as u8, 7, 255]));
+        let read = read_texture(&write_texture(&DynamicImage::ImageRgba8(image.clone()))).unwrap();
+
+        assert_eq!(read.to_rgba8(), image);
+    }
+
+    #[test]
+    fn truncated_files_are_cut_short() {
+        let model = write_model(&model(3));
+        let texture = write_texture(&DynamicImage::new_rgba8(2, 2));
+
+        // Anything shorter than the header isn't a packed file yet
+        for length in 8..model.len() {
+            assert!(error(read_model(&model[..length])).contains("cut short"), "{length} bytes");
+        }
+
+        for length in 8..texture.len() {
+            assert!(error(read_texture(&texture[..length])).contains("cut short"), "{length} bytes");
+        }
+    }
+
+    #[test]
+    fn bad_magic_isnt_packed() {
+        let model = write_model(&model(3));
+        let texture = write_texture(&DynamicImage::new_rgba8(1, 1));
+        let mut renamed = model.clone();
+
+        renamed[0] = b'X';
+
+        assert!(error(read_model(&renamed)).contains("not a packed file"));
+
+        // Each kind of file is only read as itself
+        assert!(!is_texture(&model));
+        assert!(error(read_model(&texture)).contains("not a packed file"));
+        assert!(error(read_texture(&model)).contains("not a packed file"));
+    }
+
+    #[test]
+    fn other_versions_are_packed_again() {
+        let mut model = write_model(&model(3));
+        let mut texture = write_texture(&DynamicImage::new_rgba8(1, 1));
+
+        model[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        texture[4..8].copy_from_slice(&(VERSION - 1).to_le_bytes());
+
+        assert!(error(read_model(&model)).contains("pack it again"));
+        assert!(error(read_texture(&texture)).contains("pack it again"));
+    }
+}
@@ -1,7 +1,236 @@
 #[cfg(not(target_arch = "wasm32"))]
-use learn_wgpu::run;
+use std::path::PathBuf;
+
+#[cfg(not(target_arch = "wasm32"))]
+use clap::{Parser, ValueEnum};
+#[cfg(not(target_arch = "wasm32"))]
+use wgpu::{Backends, PowerPreference, PresentMode};
+
+#[cfg(not(target_arch = "wasm32"))]
+use learn_wgpu::{list_adapters, run_with_options, AdapterSelector, Headless, Options};
+
+// Used for headless runs when no size is given
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_SIZE: (u32, u32) = (800, 600);
+
+// Splits LEARN_WGPU_RES
+#[cfg(all(not(target_arch = "wasm32"), windows))]
+const PATH_SEPARATOR: char = ';';
+#[cfg(all(not(target_arch = "wasm32"), not(windows)))]
+const PATH_SEPARATOR: char = ':';
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Log filter, e.g. "info" or "learn_wgpu=debug,wgpu=warn"
+    #[arg(long, env = "RUST_LOG")]
+    log: Option<String>,
+
+    /// Write a chrome trace event file, open it in Perfetto or chrome://tracing
+    #[arg(long, env = "LEARN_WGPU_TRACE")]
+    trace: Option<PathBuf>,
+
+    /// Obj model to load from res, overrides the scene's model
+    #[arg(long)]
+    model: Option<String>,
+
+    /// Ron scene file to load from res, replaces the default grid of instances
+    #[arg(long)]
+    scene: Option<String>,
+
+    /// Directory or zip to look for res files in before res next to the executable, can be given more than once.
+    /// The environment variable takes a list like PATH
+    #[arg(long = "res", value_name = "DIR", env = "LEARN_WGPU_RES", value_delimiter = PATH_SEPARATOR)]
+    res_dirs: Vec<PathBuf>,
+
+    /// Window size in physical pixels, e.g. 1280x720
+    #[arg(long, value_parser = parse_size)]
+    size: Option<(u32, u32)>,
+
+    /// Start in borderless fullscreen
+    #[arg(long)]
+    fullscreen: bool,
+
+    /// How frames are presented, overrides --no-vsync
+    #[arg(long, value_enum)]
+    present_mode: Option<PresentModeArg>,
+
+    /// Present as soon as possible, tearing is allowed
+    #[arg(long)]
+    no_vsync: bool,
+
+    /// Graphics backends to pick an adapter from
+    #[arg(long, value_enum, default_value_t = BackendArg::All)]
+    backend: BackendArg,
+
+    /// Adapter to use, by index from --list-adapters, backend name or part of its name.
+    /// Overrides --power-preference
+    #[arg(long)]
+    adapter: Option<AdapterSelector>,
+
+    /// Print the adapters for --backend and exit
+    #[arg(long)]
+    list_adapters: bool,
+
+    /// Prefer an integrated or a discrete gpu
+    #[arg(long, value_enum, default_value_t = PowerPreferenceArg::Low)]
+    power_preference: PowerPreferenceArg,
+
+    /// Use the software adapter, ignored when --adapter is given
+    #[arg(long)]
+    fallback_adapter: bool,
+
+    /// Multisample count, only 1 and 4 are supported everywhere
+    #[arg(long, default_value_t = 1, value_parser = parse_sample_count)]
+    msaa: u32,
+
+    /// Render offscreen without a window and write the frames to --output
+    #[arg(long)]
+    headless: bool,
+
+    /// Number of frames to render in headless mode
+    #[arg(long, default_value_t = 1, requires = "headless")]
+    frames: u32,
+
+    /// Directory for headless frames
+    #[arg(long, default_value = "frames", requires = "headless")]
+    output: PathBuf,
+
+    /// Reload shaders, models and textures when they're changed in src/shaders or res
+    #[arg(long, conflicts_with = "headless")]
+    watch: bool,
+
+    /// Simulate losing the gpu device before this frame, the renderer rebuilds everything
+    #[arg(long, value_name = "FRAME")]
+    lose_device_at: Option<u32>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PresentModeArg {
+    AutoVsync,
+    AutoNoVsync,
+    Fifo,
+    FifoRelaxed,
+    Immediate,
+    Mailbox,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum BackendArg {
+    All,
+    Primary,
+    Vulkan,
+    Metal,
+    Dx12,
+    Dx11,
+    Gl,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PowerPreferenceArg {
+    Low,
+    High,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<PresentModeArg> for PresentMode {
+    fn from(present_mode: PresentModeArg) -> Self {
+        match present_mode {
+            PresentModeArg::AutoVsync => Self::AutoVsync,
+            PresentModeArg::AutoNoVsync => Self::AutoNoVsync,
+            PresentModeArg::Fifo => Self::Fifo,
+            PresentModeArg::FifoRelaxed => Self::FifoRelaxed,
+            PresentModeArg::Immediate => Self::Immediate,
+            PresentModeArg::Mailbox => Self::Mailbox,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<BackendArg> for Backends {
+    fn from(backend: BackendArg) -> Self {
+        match backend {
+            BackendArg::All => Self::all(),
+            BackendArg::Primary => Self::PRIMARY,
+            BackendArg::Vulkan => Self::VULKAN,
+            BackendArg::Metal => Self::METAL,
+            BackendArg::Dx12 => Self::DX12,
+            BackendArg::Dx11 => Self::DX11,
+            BackendArg::Gl => Self::GL,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<PowerPreferenceArg> for PowerPreference {
+    fn from(power_preference: PowerPreferenceArg) -> Self {
+        match power_preference {
+            PowerPreferenceArg::Low => Self::LowPower,
+            PowerPreferenceArg::High => Self::HighPerformance,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<Cli> for Options {
+    fn from(cli: Cli) -> Self {
+        Self {
+            log_filter: cli.log,
+            trace_file: cli.trace,
+            model: cli.model,
+            scene: cli.scene,
+            res_dirs: cli.res_dirs,
+            window_size: cli.size,
+            fullscreen: cli.fullscreen,
+            present_mode: cli.present_mode.map(Into::into),
+            vsync: !cli.no_vsync,
+            backends: cli.backend.into(),
+            adapter: cli.adapter,
+            power_preference: cli.power_preference.into(),
+            fallback_adapter: cli.fallback_adapter,
+            sample_count: cli.msaa,
+            headless: cli.headless.then(|| Headless {
+                frames: cli.frames,
+                output: cli.output,
+                size: cli.size.unwrap_or(DEFAULT_SIZE),
+            }),
+            lose_device_at: cli.lose_device_at,
+            watch: cli.watch,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_size(size: &str) -> Result<(u32, u32), String> {
+    let parse = |value: &str| value.trim().parse::<u32>().ok().filter(|&value| value > 0);
+
+    size.split_once('x')
+        .and_then(|(width, height)| Some((parse(width)?, parse(height)?)))
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got {size:?}"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_sample_count(count: &str) -> Result<u32, String> {
+    match count.parse() {
+        Ok(count @ (1 | 4)) => Ok(count),
+        _ => Err(format!("expected 1 or 4, got {count:?}")),
+    }
+}
 
 fn main() {
     #[cfg(not(target_arch = "wasm32"))]
-    pollster::block_on(run());
+    {
+        let cli = Cli::parse();
+
+        if cli.list_adapters {
+            list_adapters(cli.backend.into());
+        } else if let Err(err) = pollster::block_on(run_with_options(cli.into())) {
+            eprintln!("error: {err:#}");
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,71 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+#[cfg(not(target_arch = "wasm32"))]
+use clap::{Parser, ValueEnum};
+
+#[cfg(not(target_arch = "wasm32"))]
+use learn_wgpu::{pack, PackedTextureFormat, PackOptions};
+
+/// Packs obj models and their textures into files the app loads without parsing them
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    /// Obj models to pack, their materials and textures are read from next to them
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Directory for the packed files, load a model from there with --res and --model NAME.mesh
+    #[arg(long, short, default_value = "packed")]
+    output: PathBuf,
+
+    /// What textures are converted to, compressed ones are copied as they are
+    #[arg(long, value_enum, default_value_t = TextureFormatArg::Rgba8)]
+    texture_format: TextureFormatArg,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum TextureFormatArg {
+    /// Raw pixels, nothing to decode when they're loaded
+    Rgba8,
+    /// Smaller files, decoded when they're loaded
+    Png,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<TextureFormatArg> for PackedTextureFormat {
+    fn from(format: TextureFormatArg) -> Self {
+        match format {
+            TextureFormatArg::Rgba8 => Self::Rgba8,
+            TextureFormatArg::Png => Self::Png,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<Cli> for PackOptions {
+    fn from(cli: Cli) -> Self {
+        Self {
+            inputs: cli.inputs,
+            output: cli.output,
+            texture_format: cli.texture_format.into(),
+        }
+    }
+}
+
+fn main() {
+    #[cfg(not(target_arch = "wasm32"))]
+    match pack(&Cli::parse().into()) {
+        Ok(files) => {
+            for (path, size) in files {
+                println!("{} ({} KiB)", path.display(), size.div_ceil(1024));
+            }
+        }
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,929 @@
+// Astc blocks are 128 bits whatever their footprint, from 4x4 to 12x12 texels. Ldr textures come out as rgba8
+// the way the decode_unorm8 extension has it, hdr ones as half floats. Blocks that break the spec's rules, or use
+// hdr endpoints in an ldr texture, are magenta
+
+#[derive(Clone, Copy, Debug)]
+pub enum Profile {
+    Ldr { srgb: bool },
+    Hdr,
+}
+
+// How many values a quantized range has, color endpoints can use all of them and weights up to 32
+const RANGES: [u32; 21] = [2, 3, 4, 5, 6, 8, 10, 12, 16, 20, 24, 32, 40, 48, 64, 80, 96, 128, 160, 192, 256];
+
+const MAGENTA_UNORM: [u16; 4] = [0xffff, 0, 0xffff, 0xffff];
+const MAGENTA_HALF: [u16; 4] = [0x3c00, 0, 0x3c00, 0x3c00];
+
+// Alpha of the hdr endpoint modes without one, 1.0 in the log encoding
+const HDR_ONE: i32 = 0x7800;
+
+// A texel's channels as unorm16, or in the spec's 16 bit log encoding when they're from an hdr endpoint mode
+#[derive(Clone, Copy, Default)]
+struct Color {
+    values: [u32; 4],
+    hdr: [bool; 4],
+}
+
+enum Block {
+    Error,
+    // Void extent blocks, unorm16 or half floats
+    Constant([u16; 4], bool),
+    Texels(Vec<Color>),
+}
+
+// Texels a row at a time, 4 bytes each for ldr and 8 for hdr. Ldr keeps the top byte of each unorm16 channel
+#[allow(clippy::cast_possible_truncation)]
+pub fn decode_block(block: &[u8], width: usize, height: usize, profile: Profile, texels: &mut [u8]) {
+    let block = block.iter().rev().fold(0, |value, &byte| value << 8 | u128::from(byte));
+    let count = width * height;
+    let decoded = read_block(block, width, height, matches!(profile, Profile::Ldr { srgb: true }));
+
+    let rgba = match (profile, decoded) {
+        (Profile::Ldr { .. }, Block::Constant(rgba, false)) | (Profile::Hdr, Block::Constant(rgba, true)) => {
+            vec![rgba; count]
+        }
+        (Profile::Ldr { .. }, Block::Texels(colors)) if colors.iter().all(|color| !color.hdr.contains(&true)) => {
+            colors.iter().map(|color| color.values.map(|value| value as u16)).collect()
+        }
+        (Profile::Ldr { .. }, _) => vec![MAGENTA_UNORM; count],
+        (Profile::Hdr, Block::Constant(rgba, false)) => vec![rgba.map(|value| unorm16_to_half(u32::from(value))); count],
+        (Profile::Hdr, Block::Texels(colors)) => colors
+            .iter()
+            .map(|color| std::array::from_fn(|channel| {
+                let value = color.values[channel];
+
+                if color.hdr[channel] { lns_to_half(value) } else { unorm16_to_half(value) }
+            }))
+            .collect(),
+        (Profile::Hdr, Block::Error) => vec![MAGENTA_HALF; count],
+    };
+
+    match profile {
+        Profile::Ldr { .. } => {
+            for (texel, rgba) in texels.chunks_exact_mut(4).zip(rgba) {
+                texel.copy_from_slice(&rgba.map(|value| value.to_be_bytes()[0]));
+            }
+        }
+        Profile::Hdr => {
+            for (texel, rgba) in texels.chunks_exact_mut(8).zip(rgba) {
+                texel.copy_from_slice(&rgba.map(u16::to_le_bytes).concat());
+            }
+        }
+    }
+}
+
+fn read_block(block: u128, width: usize, height: usize, srgb: bool) -> Block {
+    let mode = field(block, 0, 11);
+
+    // Bit 9 says whether the color is in half floats
+    if mode & 0x1ff == 0x1fc {
+        let rgba = std::array::from_fn(|channel| (block >> (64 + 16 * channel) & 0xffff) as u16);
+
+        return Block::Constant(rgba, mode & 0x200 != 0);
+    }
+
+    let Some(grid) = WeightGrid::new(mode) else {
+        return Block::Error;
+    };
+
+    let planes = 1 + usize::from(grid.dual_plane);
+    let weight_count = grid.width * grid.height * planes;
+    let weight_bits = sequence_length(weight_count, grid.levels);
+
+    if grid.width > width || grid.height > height || weight_count > 64 || !(24..=96).contains(&weight_bits) {
+        return Block::Error;
+    }
+
+    let partitions = field(block, 11, 2) + 1;
+
+    if partitions == 4 && grid.dual_plane {
+        return Block::Error;
+    }
+
+    // What's between the endpoint values and the weights goes down from the weights
+    let mut below_weights = 128 - weight_bits;
+    let mut modes = [0; 4];
+
+    let (partition_seed, values_start) = if partitions == 1 {
+        modes[0] = field(block, 13, 4);
+
+        (0, 17)
+    } else {
+        let selector = field(block, 23, 6);
+
+        match selector & 3 {
+            0 => modes = [selector >> 2; 4],
+            lowest_class => {
+                // Each partition's mode is a class relative to the lowest one, then two bits
+                let extra = 3 * partitions - 4;
+
+                below_weights -= extra;
+
+                let selector = selector | field(block, below_weights, extra) << 6;
+
+                for (partition, mode) in (0..).zip(&mut modes[..partitions as usize]) {
+                    let class = lowest_class - 1 + (selector >> (2 + partition) & 1);
+
+                    *mode = class << 2 | selector >> (2 + partitions + 2 * partition) & 3;
+                }
+            }
+        }
+
+        (field(block, 13, 10), 29)
+    };
+
+    // Dual plane blocks weigh one channel separately
+    let plane_channel = if grid.dual_plane {
+        below_weights -= 2;
+
+        Some(field(block, below_weights, 2) as usize)
+    } else {
+        None
+    };
+
+    let value_count = modes[..partitions as usize].iter().map(|mode| (mode / 4 + 1) * 2).sum::<u32>() as usize;
+
+    if value_count > 18 || below_weights < values_start {
+        return Block::Error;
+    }
+
+    // The endpoint values take as many levels as fit in what's left
+    let color_bits = below_weights - values_start;
+
+    let Some(&levels) = RANGES.iter().rev().find(|&&levels| sequence_length(value_count, levels) <= color_bits) else {
+        return Block::Error;
+    };
+
+    if levels < 6 {
+        return Block::Error;
+    }
+
+    let values = integer_sequence(block, values_start, value_count, levels)
+        .into_iter()
+        .map(|value| unquantize_color(value, levels))
+        .collect::<Vec<_>>();
+
+    let mut endpoints = [(Color::default(), Color::default()); 4];
+    let mut rest = values.as_slice();
+
+    for (endpoints, &mode) in endpoints.iter_mut().zip(&modes[..partitions as usize]) {
+        let (used, unused) = rest.split_at((mode as usize / 4 + 1) * 2);
+
+        *endpoints = endpoint_pair(mode, used, srgb);
+        rest = unused;
+    }
+
+    // Stored from the top of the block down, bits and all
+    let weights = integer_sequence(block.reverse_bits(), 0, weight_count, grid.levels)
+        .into_iter()
+        .map(|weight| unquantize_weight(weight, grid.levels))
+        .collect::<Vec<_>>();
+
+    let small = width * height < 31;
+
+    let colors = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let partition = if partitions == 1 { 0 } else { select_partition(partition_seed, x, y, partitions, small) };
+            let (first, second) = endpoints[partition];
+            let plane_weights: [u32; 2] =
+                std::array::from_fn(|plane| infill(&weights, &grid, plane, x, y, width, height));
+
+            let values = std::array::from_fn(|channel| {
+                let weight = plane_weights[usize::from(plane_channel == Some(channel))];
+
+                (first.values[channel] * (64 - weight) + second.values[channel] * weight + 32) >> 6
+            });
+
+            Color { values, hdr: first.hdr }
+        })
+        .collect();
+
+    Block::Texels(colors)
+}
+
+// The size of the grid the weights are stored in, and how they're quantized
+struct WeightGrid {
+    width: usize,
+    height: usize,
+    levels: u32,
+    dual_plane: bool,
+}
+
+impl WeightGrid {
+    // From the low 11 bits of the block
+    fn new(mode: u32) -> Option<Self> {
+        let bits = |shift: u32, count: u32| mode >> shift & ((1 << count) - 1);
+        let (a, b) = (bits(5, 2), bits(7, 2));
+        let (mut high_precision, mut dual_plane) = (bits(9, 1) == 1, bits(10, 1) == 1);
+
+        let (width, height, precision) = if bits(0, 2) == 0 {
+            let (width, height) = match bits(7, 2) {
+                0 => (12, a + 2),
+                1 => (a + 2, 12),
+                2 => {
+                    // Those two bits are the height instead
+                    high_precision = false;
+                    dual_plane = false;
+
+                    (a + 6, bits(9, 2) + 6)
+                }
+                _ => match a {
+                    0 => (6, 10),
+                    1 => (10, 6),
+                    _ => return None,
+                },
+            };
+
+            (width, height, bits(4, 1) | bits(2, 2) << 1)
+        } else {
+            let (width, height) = match bits(2, 2) {
+                0 => (b + 4, a + 2),
+                1 => (b + 8, a + 2),
+                2 => (a + 2, b + 8),
+                _ if bits(8, 1) == 1 => ((b & 1) + 2, a + 2),
+                _ => (a + 2, (b & 1) + 6),
+            };
+
+            (width, height, bits(4, 1) | bits(0, 2) << 1)
+        };
+
+        // Reserved
+        if precision < 2 {
+            return None;
+        }
+
+        Some(Self {
+            width: width as usize,
+            height: height as usize,
+            levels: RANGES[(precision - 2 + 6 * u32::from(high_precision)) as usize],
+            dual_plane,
+        })
+    }
+}
+
+// Texels between the grid's points get a bilinear mix of the four around them, which is never more than 64
+#[allow(clippy::cast_possible_truncation)]
+fn infill(weights: &[u32], grid: &WeightGrid, plane: usize, x: usize, y: usize, width: usize, height: usize) -> u32 {
+    let planes = 1 + usize::from(grid.dual_plane);
+    let scale = |size: usize| (1024 + size / 2) / (size - 1);
+    let s = (scale(width) * x * (grid.width - 1) + 32) >> 6;
+    let t = (scale(height) * y * (grid.height - 1) + 32) >> 6;
+    let (fs, ft) = (s & 15, t & 15);
+
+    // Points past the edge of the grid only come up with no share of the mix
+    let point = (s >> 4) + (t >> 4) * grid.width;
+    let weight = |point: usize| weights.get(point * planes + plane).copied().unwrap_or(0) as usize;
+
+    let both = (fs * ft + 8) >> 4;
+    let mixed = weight(point) * (16 + both - fs - ft)
+        + weight(point + 1) * (fs - both)
+        + weight(point + grid.width) * (ft - both)
+        + weight(point + grid.width + 1) * both;
+
+    ((mixed + 8) >> 4) as u32
+}
+
+// Which partition a texel is in comes from hashing the seed, z is always 0 for 2d textures
+#[allow(clippy::cast_possible_truncation)]
+fn select_partition(seed: u32, x: usize, y: usize, partitions: u32, small: bool) -> usize {
+    // Blocks are 12x12 at most
+    let (x, y) = if small { (x as u32 * 2, y as u32 * 2) } else { (x as u32, y as u32) };
+    let seed = seed + (partitions - 1) * 1024;
+    let random = hash52(seed);
+
+    let seeds: [u32; 8] = std::array::from_fn(|index| {
+        let value = random >> (4 * index) & 15;
+        let shift = match (index % 2, seed & 1, partitions) {
+            (0, 1, _) | (1, 0, _) => if seed & 2 == 0 { 5 } else { 4 },
+            (_, _, 3) => 6,
+            _ => 5,
+        };
+
+        (value * value) >> shift
+    });
+
+    let scores = [(0, 14), (2, 10), (4, 6), (6, 2)].map(|(first, shift): (usize, u32)| {
+        seeds[first].wrapping_mul(x).wrapping_add(seeds[first + 1].wrapping_mul(y)).wrapping_add(random >> shift) & 0x3f
+    });
+
+    // The first of the highest
+    (0..partitions as usize).fold(0, |best, partition| if scores[partition] > scores[best] { partition } else { best })
+}
+
+const fn hash52(value: u32) -> u32 {
+    let mut value = value ^ value >> 15;
+
+    value = value.wrapping_sub(value << 17);
+    value = value.wrapping_add(value << 7);
+    value = value.wrapping_add(value << 4);
+    value ^= value >> 5;
+    value = value.wrapping_add(value << 16);
+    value ^= value >> 7;
+    value ^= value >> 3;
+    value ^= value << 6;
+
+    value ^ value >> 17
+}
+
+// The endpoint modes. Ldr ones are made of 8 bit values, which are widened to 16 bits for interpolating,
+// and hdr ones of 12 bit values in the log encoding
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn endpoint_pair(mode: u32, values: &[u32], srgb: bool) -> (Color, Color) {
+    let v: [i32; 8] = std::array::from_fn(|index| values.get(index).map_or(0, |&value| value as i32));
+
+    let widen = |value: i32| {
+        let value = value.clamp(0, 255);
+
+        if srgb { value << 8 | 0x80 } else { value * 257 }
+    };
+
+    let ldr = |rgba: [i32; 4]| Color { values: rgba.map(|value| widen(value) as u32), hdr: [false; 4] };
+
+    let hdr = |[r, g, b]: [i32; 3], alpha: i32, hdr_alpha: bool| Color {
+        values: [r, g, b, alpha].map(|value| value.clamp(0, 0xffff) as u32),
+        hdr: [true, true, true, hdr_alpha],
+    };
+
+    let luminance = |value: i32, alpha: i32| [value, value, value, alpha];
+
+    match mode {
+        0 => (ldr(luminance(v[0], 255)), ldr(luminance(v[1], 255))),
+        1 => {
+            let low = v[0] >> 2 | v[1] & 0xc0;
+            let high = (low + (v[1] & 0x3f)).min(255);
+
+            (ldr(luminance(low, 255)), ldr(luminance(high, 255)))
+        }
+        2 | 3 => {
+            let luminance = if mode == 2 { hdr_luminance_large } else { hdr_luminance_small };
+            let (first, second) = luminance(v[0], v[1]);
+
+            (hdr([first; 3], HDR_ONE, true), hdr([second; 3], HDR_ONE, true))
+        }
+        4 => (ldr(luminance(v[0], v[2])), ldr(luminance(v[1], v[3]))),
+        5 => {
+            let (luminance_base, luminance_offset) = transfer(v[1], v[0]);
+            let (alpha_base, alpha_offset) = transfer(v[3], v[2]);
+
+            (
+                ldr(luminance(luminance_base, alpha_base)),
+                ldr(luminance(luminance_base + luminance_offset, alpha_base + alpha_offset)),
+            )
+        }
+        6 | 10 => {
+            let alpha = if mode == 10 { [v[4], v[5]] } else { [255, 255] };
+            let scaled = [v[0], v[1], v[2]].map(|value| (value * v[3]) >> 8);
+
+            (ldr([scaled[0], scaled[1], scaled[2], alpha[0]]), ldr([v[0], v[1], v[2], alpha[1]]))
+        }
+        8 | 12 => {
+            let alpha = if mode == 12 { [v[6], v[7]] } else { [255, 255] };
+            let first = [v[0], v[2], v[4], alpha[0]];
+            let second = [v[1], v[3], v[5], alpha[1]];
+
+            if v[1] + v[3] + v[5] >= v[0] + v[2] + v[4] {
+                (ldr(first), ldr(second))
+            } else {
+                (ldr(blue_contract(second)), ldr(blue_contract(first)))
+            }
+        }
+        9 | 13 => {
+            let pairs = [transfer(v[1], v[0]), transfer(v[3], v[2]), transfer(v[5], v[4]), transfer(v[7], v[6])];
+            let alpha = if mode == 13 { pairs[3] } else { (255, 0) };
+            let base = [pairs[0].0, pairs[1].0, pairs[2].0, alpha.0];
+            let offsets = [pairs[0].1, pairs[1].1, pairs[2].1, alpha.1];
+            let offset = std::array::from_fn(|channel| base[channel] + offsets[channel]);
+
+            if pairs[0].1 + pairs[1].1 + pairs[2].1 >= 0 {
+                (ldr(base), ldr(offset))
+            } else {
+                (ldr(blue_contract(offset)), ldr(blue_contract(base)))
+            }
+        }
+        7 => {
+            let (first, second) = hdr_rgb_scale([v[0], v[1], v[2], v[3]]);
+
+            (hdr(first, HDR_ONE, true), hdr(second, HDR_ONE, true))
+        }
+        _ => {
+            let (first, second) = hdr_rgb([v[0], v[1], v[2], v[3], v[4], v[5]]);
+
+            // 14 has ldr alpha, 15 hdr
+            match mode {
+                11 => (hdr(first, HDR_ONE, true), hdr(second, HDR_ONE, true)),
+                14 => (hdr(first, widen(v[6]), false), hdr(second, widen(v[7]), false)),
+                _ => {
+                    let (first_alpha, second_alpha) = hdr_alpha(v[6], v[7]);
+
+                    (hdr(first, first_alpha, true), hdr(second, second_alpha, true))
+                }
+            }
+        }
+    }
+}
+
+// The base takes the offset's top bit, the offset is left with 6 bits and a sign
+const fn transfer(offset: i32, base: i32) -> (i32, i32) {
+    let base = base >> 1 | offset & 0x80;
+    let offset = offset >> 1 & 0x3f;
+
+    (base, if offset & 0x20 == 0 { offset } else { offset - 0x40 })
+}
+
+const fn blue_contract([r, g, b, a]: [i32; 4]) -> [i32; 4] {
+    [(r + b) >> 1, (g + b) >> 1, b, a]
+}
+
+const fn hdr_luminance_large(v0: i32, v1: i32) -> (i32, i32) {
+    let (first, second) = if v1 >= v0 { (v0 << 4, v1 << 4) } else { ((v1 << 4) + 8, (v0 << 4) - 8) };
+
+    (first << 4, second << 4)
+}
+
+fn hdr_luminance_small(v0: i32, v1: i32) -> (i32, i32) {
+    let (first, offset) = if v0 & 0x80 == 0 {
+        ((v1 & 0xf0) << 4 | (v0 & 0x7f) << 1, (v1 & 0x0f) << 1)
+    } else {
+        ((v1 & 0xe0) << 4 | (v0 & 0x7f) << 2, (v1 & 0x1f) << 2)
+    };
+
+    (first << 4, (first + offset).min(0xfff) << 4)
+}
+
+// A major channel and a scale, with the bits spread over the values differently in each of six submodes
+#[allow(clippy::cast_sign_loss)]
+fn hdr_rgb_scale([v0, v1, v2, v3]: [i32; 4]) -> ([i32; 3], [i32; 3]) {
+    let selector = (v0 & 0xc0) >> 6 | (v1 & 0x80) >> 5 | (v2 & 0x80) >> 4;
+
+    let (major, submode) = match selector {
+        0xf => (0, 5),
+        _ if selector & 0xc == 0xc => (selector & 3, 4),
+        _ => (selector >> 2, selector & 3),
+    };
+
+    let spare_bits = [(v1, 6), (v1, 5), (v2, 6), (v2, 5), (v3, 7), (v3, 6), (v3, 5)];
+    let [x0, x1, x2, x3, x4, x5, x6] = spare_bits.map(|(value, shift)| value >> shift & 1);
+    let mut channels = [v0 & 0x3f, v1 & 0x1f, v2 & 0x1f, v3 & 0x1f];
+
+    // The submodes a bit is in, which of red, green, blue and the scale it goes to and where
+    for (submodes, channel, bit, shift) in [
+        (0x30, 1, x0, 6), (0x3a, 1, x1, 5), (0x30, 2, x2, 6), (0x3a, 2, x3, 5), (0x3d, 3, x6, 5), (0x2d, 3, x5, 6),
+        (0x04, 3, x4, 7), (0x3b, 0, x4, 6), (0x04, 0, x3, 6), (0x10, 0, x5, 7), (0x0f, 0, x2, 7), (0x05, 0, x1, 8),
+        (0x0a, 0, x0, 8), (0x05, 0, x0, 9), (0x02, 0, x6, 9), (0x01, 0, x3, 10), (0x02, 0, x5, 10),
+    ] {
+        if submodes >> submode & 1 == 1 {
+            channels[channel] |= bit << shift;
+        }
+    }
+
+    let shift = [1, 1, 2, 3, 4, 5][submode as usize];
+    let [red, mut green, mut blue, scale] = channels.map(|value| value << shift);
+
+    // Green and blue are differences from the major channel, but in the last submode
+    if submode != 5 {
+        green = red - green;
+        blue = red - blue;
+    }
+
+    let mut second = [red, green, blue];
+
+    if major > 0 {
+        second.swap(0, major as usize);
+    }
+
+    let first = second.map(|value| (value - scale).clamp(0, 0xfff) << 4);
+
+    (first, second.map(|value| value.clamp(0, 0xfff) << 4))
+}
+
+// Channels given as differences from the major one, with eight submodes of where the bits go
+#[allow(clippy::cast_sign_loss)]
+fn hdr_rgb([v0, v1, v2, v3, v4, v5]: [i32; 6]) -> ([i32; 3], [i32; 3]) {
+    let submode = (v1 & 0x80) >> 7 | (v2 & 0x80) >> 6 | (v3 & 0x80) >> 5;
+    let major = (v4 & 0x80) >> 7 | (v5 & 0x80) >> 6;
+
+    // The channels as they are, with blue getting one bit less
+    if major == 3 {
+        return ([v0 << 8, v2 << 8, (v4 & 0x7f) << 9], [v1 << 8, v3 << 8, (v5 & 0x7f) << 9]);
+    }
+
+    let spare_bits = [(v2, 6), (v3, 6), (v4, 6), (v5, 6), (v4, 5), (v5, 5)];
+    let [x0, x1, x2, x3, x4, x5] = spare_bits.map(|(value, shift)| value >> shift & 1);
+    let mut values = [v0 | (v1 & 0x40) << 2, v2 & 0x3f, v3 & 0x3f, v1 & 0x3f, v4 & 0x7f, v5 & 0x7f];
+
+    // The submodes a bit is in, which of a, b0, b1, c, d0 and d1 it goes to and where
+    for (submodes, value, bit, shift) in [
+        (0xa4, 0, x0, 9), (0x08, 0, x2, 9), (0x50, 0, x4, 9), (0x50, 0, x5, 10), (0xa0, 0, x1, 10), (0xc0, 0, x2, 11),
+        (0x04, 3, x1, 6), (0xe8, 3, x3, 6), (0x20, 3, x2, 7), (0x5b, 1, x0, 6), (0x5b, 2, x1, 6), (0x12, 1, x2, 7),
+        (0x12, 2, x3, 7), (0xaf, 4, x4, 5), (0xaf, 5, x5, 5), (0x05, 4, x2, 6), (0x05, 5, x3, 6),
+    ] {
+        if submodes >> submode & 1 == 1 {
+            values[value] |= bit << shift;
+        }
+    }
+
+    let [a, b0, b1, c, d0, d1] = values;
+    // The d values are signed
+    let d_bits = [7, 6, 7, 6, 5, 6, 5, 6][submode as usize];
+    let sign_extend = |value: i32| value << (32 - d_bits) >> (32 - d_bits);
+    let shift = (submode >> 1) ^ 3;
+    let [a, b0, b1, c, d0, d1] = [a, b0, b1, c, sign_extend(d0), sign_extend(d1)].map(|value| value << shift);
+
+    let mut first = [a - c, a - b0 - c - d0, a - b1 - c - d1].map(|value| value.clamp(0, 0xfff));
+    let mut second = [a, a - b0, a - b1].map(|value| value.clamp(0, 0xfff));
+
+    if major > 0 {
+        first.swap(0, major as usize);
+        second.swap(0, major as usize);
+    }
+
+    (first.map(|value| value << 4), second.map(|value| value << 4))
+}
+
+fn hdr_alpha(v6: i32, v7: i32) -> (i32, i32) {
+    let selector = (v6 >> 7 & 1) | (v7 >> 6 & 2);
+    let (v6, v7) = (v6 & 0x7f, v7 & 0x7f);
+
+    if selector == 3 {
+        return (v6 << 9, v7 << 9);
+    }
+
+    let first = v6 | (v7 << (selector + 1)) & 0x780;
+    let offset = ((v7 & (0x3f >> selector)) ^ (32 >> selector)) - (32 >> selector);
+    let (first, offset) = (first << (4 - selector), offset << (4 - selector));
+
+    (first << 4, (first + offset).clamp(0, 0xfff) << 4)
+}
+
+// Values are packed as bits alone, or with five trits sharing 8 bits or three quints sharing 7
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Packing {
+    Bits,
+    Trits,
+    Quints,
+}
+
+// What the values of a range are packed with, and how many bits each has besides
+const fn encoding(levels: u32) -> (Packing, u32) {
+    match levels {
+        3 | 6 | 12 | 24 | 48 | 96 | 192 => (Packing::Trits, (levels / 3).trailing_zeros()),
+        5 | 10 | 20 | 40 | 80 | 160 => (Packing::Quints, (levels / 5).trailing_zeros()),
+        _ => (Packing::Bits, levels.trailing_zeros()),
+    }
+}
+
+// Never more than 64 values
+#[allow(clippy::cast_possible_truncation)]
+const fn sequence_length(count: usize, levels: u32) -> u32 {
+    let count = count as u32;
+    let (packing, bits) = encoding(levels);
+
+    count * bits + match packing {
+        Packing::Bits => 0,
+        Packing::Trits => (8 * count).div_ceil(5),
+        Packing::Quints => (7 * count).div_ceil(3),
+    }
+}
+
+// Groups at the end of a sequence can be cut short, the bits that aren't there read as zero
+fn integer_sequence(block: u128, start: u32, count: usize, levels: u32) -> Vec<u32> {
+    let (packing, bits) = encoding(levels);
+    let mut reader = Reader { block, position: start, end: start + sequence_length(count, levels) };
+    let mut values = Vec::with_capacity(count + 4);
+
+    while values.len() < count {
+        match packing {
+            Packing::Bits => values.push(reader.take(bits)),
+            Packing::Trits => {
+                let mut low = [0; 5];
+                let mut packed = 0;
+
+                for ((low, shift), count) in low.iter_mut().zip([0, 2, 4, 5, 7]).zip([2, 2, 1, 2, 1]) {
+                    *low = reader.take(bits);
+                    packed |= reader.take(count) << shift;
+                }
+
+                values.extend(trits(packed).iter().zip(low).map(|(trit, low)| trit << bits | low));
+            }
+            Packing::Quints => {
+                let mut low = [0; 3];
+                let mut packed = 0;
+
+                for ((low, shift), count) in low.iter_mut().zip([0, 3, 5]).zip([3, 2, 2]) {
+                    *low = reader.take(bits);
+                    packed |= reader.take(count) << shift;
+                }
+
+                values.extend(quints(packed).iter().zip(low).map(|(quint, low)| quint << bits | low));
+            }
+        }
+    }
+
+    values.truncate(count);
+
+    values
+}
+
+// Bits high to low, inclusive
+const fn span(value: u32, high: u32, low: u32) -> u32 {
+    value >> low & ((1 << (high - low + 1)) - 1)
+}
+
+fn trits(packed: u32) -> [u32; 5] {
+    let t = |high, low| span(packed, high, low);
+
+    let (c, t4, t3) = if t(4, 2) == 7 {
+        (t(7, 5) << 2 | t(1, 0), 2, 2)
+    } else if t(6, 5) == 3 {
+        (t(4, 0), 2, t(7, 7))
+    } else {
+        (t(4, 0), t(7, 7), t(6, 5))
+    };
+
+    let c = |high, low| span(c, high, low);
+
+    let (t2, t1, t0) = if c(1, 0) == 3 {
+        (2, c(4, 4), c(3, 3) << 1 | (c(2, 2) & !c(3, 3) & 1))
+    } else if c(3, 2) == 3 {
+        (2, 2, c(1, 0))
+    } else {
+        (c(4, 4), c(3, 2), c(1, 1) << 1 | (c(0, 0) & !c(1, 1) & 1))
+    };
+
+    [t0, t1, t2, t3, t4]
+}
+
+fn quints(packed: u32) -> [u32; 3] {
+    let q = |high, low| span(packed, high, low);
+
+    if q(2, 1) == 3 && q(6, 5) == 0 {
+        let not_q0 = !q(0, 0) & 1;
+
+        return [4, 4, q(0, 0) << 2 | (q(4, 4) & not_q0) << 1 | (q(3, 3) & not_q0)];
+    }
+
+    let (q2, c) = if q(2, 1) == 3 {
+        (4, q(4, 3) << 3 | (!q(6, 5) & 3) << 1 | q(0, 0))
+    } else {
+        (q(6, 5), q(4, 0))
+    };
+
+    let (q1, q0) = if c & 7 == 5 { (4, c >> 3 & 3) } else { (c >> 3 & 3, c & 7) };
+
+    [q0, q1, q2]
+}
+
+// To 0 to 255. Trits and quints are spread over the range with the lowest bit flipping it around
+fn unquantize_color(value: u32, levels: u32) -> u32 {
+    let (packing, bits) = encoding(levels);
+
+    if packing == Packing::Bits {
+        return replicate(value, bits, 8);
+    }
+
+    let (high, low) = (value >> bits, value & ((1 << bits) - 1));
+    let bit = |index: u32| low >> index & 1;
+
+    let (b, c) = match levels {
+        6 => (0, 204),
+        12 => (bit(1) * 0x116, 93),
+        24 => (bit(2) * 0x10a + bit(1) * 0x85, 44),
+        48 => (bit(3) * 0x104 + bit(2) * 0x82 + bit(1) * 0x41, 22),
+        96 => (bit(4) * 0x102 + bit(3) * 0x81 + bit(2) * 0x40 + bit(1) * 0x20, 11),
+        192 => (bit(5) * 0x101 + bit(4) * 0x80 + bit(3) * 0x40 + bit(2) * 0x20 + bit(1) * 0x10, 5),
+        10 => (0, 113),
+        20 => (bit(1) * 0x10c, 54),
+        40 => (bit(2) * 0x105 + bit(1) * 0x82, 26),
+        80 => (bit(3) * 0x102 + bit(2) * 0x81 + bit(1) * 0x40, 13),
+        _ => (bit(4) * 0x101 + bit(3) * 0x80 + bit(2) * 0x40 + bit(1) * 0x20, 6),
+    };
+
+    let a = if low & 1 == 1 { 0x1ff } else { 0 };
+
+    a & 0x80 | ((high * c + b) ^ a) >> 2
+}
+
+// To 0 to 64, the same way as colors but for the two smallest ranges
+fn unquantize_weight(value: u32, levels: u32) -> u32 {
+    let (packing, bits) = encoding(levels);
+    let (high, low) = (value >> bits, value & ((1 << bits) - 1));
+    let bit = |index: u32| low >> index & 1;
+    let a = if low & 1 == 1 { 0x7f } else { 0 };
+
+    let weight = match (packing, levels) {
+        (Packing::Bits, _) => replicate(value, bits, 6),
+        (_, 3) => [0, 32, 63][high as usize],
+        (_, 5) => [0, 16, 32, 47, 63][high as usize],
+        (_, 6) => a & 0x20 | ((high * 50) ^ a) >> 2,
+        (_, 12) => a & 0x20 | ((high * 23 + bit(1) * 0x45) ^ a) >> 2,
+        (_, 24) => a & 0x20 | ((high * 11 + bit(2) * 0x42 + bit(1) * 0x21) ^ a) >> 2,
+        (_, 10) => a & 0x20 | ((high * 28) ^ a) >> 2,
+        _ => a & 0x20 | ((high * 13 + bit(1) * 0x42) ^ a) >> 2,
+    };
+
+    if weight > 32 { weight + 1 } else { weight }
+}
+
+// Repeats the value's bits until there are enough
+const fn replicate(value: u32, bits: u32, target: u32) -> u32 {
+    let mut result = 0;
+    let mut length = 0;
+
+    while length < target {
+        result = result << bits | value;
+        length += bits;
+    }
+
+    result >> (length - target)
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn lns_to_half(value: u32) -> u16 {
+    let (exponent, mantissa) = (value >> 11, value & 0x7ff);
+
+    let mantissa = if mantissa < 512 {
+        3 * mantissa
+    } else if mantissa < 1536 {
+        4 * mantissa - 512
+    } else {
+        5 * mantissa - 2048
+    };
+
+    // Infinity and beyond are the largest finite value instead
+    ((exponent << 10) + (mantissa >> 3)).min(0x7bff) as u16
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn unorm16_to_half(value: u32) -> u16 {
+    match value {
+        0xffff => 0x3c00,
+        0..=3 => (value << 8) as u16,
+        _ => {
+            let zeros = (value as u16).leading_zeros();
+            let mantissa = (value << (zeros + 1) & 0xffff) >> 6;
+
+            (mantissa | (14 - zeros) << 10) as u16
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn field(block: u128, shift: u32, count: u32) -> u32 {
+    (block >> shift & ((1 << count) - 1)) as u32
+}
+
+struct Reader {
+    block: u128,
+    position: u32,
+    end: u32,
+}
+
+impl Reader {
+    fn take(&mut self, count: u32) -> u32 {
+        let available = self.end.saturating_sub(self.position).min(count);
+        let value = if available == 0 { 0 } else { field(self.block, self.position, available) };
+
+        self.position += count;
+
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    // A single 4x4 block, decoded texels a row at a time
+    fn ldr(block: u128, srgb: bool) -> Vec<[u8; 4]> {
+        let mut texels = vec![0; 16 * 4];
+
+        decode_block(&block.to_le_bytes(), 4, 4, Profile::Ldr { srgb }, &mut texels);
+
+        texels.chunks_exact(4).map(|texel| [texel[0], texel[1], texel[2], texel[3]]).collect()
+    }
+
+    fn hdr(block: u128) -> Vec<[u16; 4]> {
+        let mut texels = vec![0; 16 * 8];
+
+        decode_block(&block.to_le_bytes(), 4, 4, Profile::Hdr, &mut texels);
+
+        texels
+            .chunks_exact(8)
+            .map(|texel| std::array::from_fn(|channel| u16::from_le_bytes([texel[channel * 2], texel[channel * 2 + 1]])))
+            .collect()
+    }
+
+    // Weights go from the top bit down
+    fn weights(bits: usize, values: impl IntoIterator<Item = u32>) -> u128 {
+        let stream = values.into_iter().enumerate().fold(0, |stream, (index, value)| {
+            stream | u128::from(value) << (bits * index)
+        });
+
+        stream.reverse_bits()
+    }
+
+    // A 4x4 grid of 2 bit weights, 0 to 3 across each row, with one partition and 8 bit endpoint values
+    fn gradient(endpoint_mode: u32, values: &[u32]) -> u128 {
+        let block = (0..).zip(values).fold(66 | u128::from(endpoint_mode) << 13, |block, (index, &value)| {
+            block | u128::from(value) << (17 + 8 * index)
+        });
+
+        block | weights(2, (0..16).map(|texel| texel % 4))
+    }
+
+    // Void extent blocks, with no extent
+    const ORANGE: u128 = 0xffff_0000_8000_ffff_ffff_ffff_ffff_fdfc;
+    const HALF_ORANGE: u128 = 0x3c00_0000_3800_3c00_ffff_ffff_ffff_fffc;
+
+    const MAGENTA: [u8; 4] = [255, 0, 255, 255];
+
+    #[test]
+    fn void_extent() {
+        assert_eq!(ldr(ORANGE, false), vec![[255, 128, 0, 255]; 16]);
+        assert_eq!(hdr(ORANGE), vec![[0x3c00, 0x3800, 0, 0x3c00]; 16]);
+        assert_eq!(hdr(HALF_ORANGE), vec![[0x3c00, 0x3800, 0, 0x3c00]; 16]);
+        assert_eq!(ldr(HALF_ORANGE, false), vec![MAGENTA; 16]);
+    }
+
+    #[test]
+    fn ldr_endpoints() {
+        let block = gradient(8, &[0, 255, 0, 255, 0, 255]);
+
+        assert_eq!(ldr(block, false), [[0, 0, 0, 255], [84, 84, 84, 255], [171, 171, 171, 255], [255; 4]].repeat(4));
+        assert_eq!(ldr(block, true), [[0, 0, 0, 255], [84, 84, 84, 255], [171, 171, 171, 255], [255; 4]].repeat(4));
+
+        // Srgb endpoints widen to the middle of their 8 bit step instead, which shows near black
+        let block = gradient(8, &[0, 1, 0, 1, 0, 1]);
+
+        assert_eq!(ldr(block, false), [[0, 0, 0, 255], [0, 0, 0, 255], [0, 0, 0, 255], [1, 1, 1, 255]].repeat(4));
+        assert_eq!(ldr(block, true), [[0, 0, 0, 255], [0, 0, 0, 255], [1, 1, 1, 255], [1, 1, 1, 255]].repeat(4));
+    }
+
+    #[test]
+    fn hdr_endpoints() {
+        // Luminance from 1.0 to 2.0
+        let block = gradient(2, &[0x78, 0x80]);
+        let row = [0x3c00, 0x3d10, 0x3e70, 0x4000].map(|value| [value, value, value, 0x3c00]);
+
+        assert_eq!(hdr(block), row.repeat(4));
+        assert_eq!(ldr(block, false), vec![MAGENTA; 16]);
+    }
+
+    #[test]
+    fn weights_are_infilled() {
+        // A 3x3 grid of 3 bit weights, the middle column 1.0 and the rest 0
+        let block = 0x1bf | 8 << 13 | u128::from(0xff00_ff00_ff00_u64) << 17 | weights(3, [0, 7, 0].repeat(3));
+
+        let row = [[0, 0, 0, 255], [175, 175, 175, 255], [175, 175, 175, 255], [0, 0, 0, 255]];
+
+        assert_eq!(ldr(block, false), row.repeat(4));
+    }
+
+    #[test]
+    fn reserved_block_mode() {
+        assert_eq!(ldr(0, false), vec![MAGENTA; 16]);
+        assert_eq!(hdr(0), vec![MAGENTA_HALF; 16]);
+    }
+
+    #[test]
+    fn trits_and_quints_cover_every_combination() {
+        let trits = (0..256).map(trits).collect::<BTreeSet<_>>();
+        let quints = (0..128).map(quints).collect::<BTreeSet<_>>();
+
+        assert_eq!(trits.len(), 3_usize.pow(5));
+        assert!(trits.iter().flatten().all(|&trit| trit < 3));
+        assert_eq!(quints.len(), 5_usize.pow(3));
+        assert!(quints.iter().flatten().all(|&quint| quint < 5));
+    }
+
+    #[test]
+    fn unquantized_ranges_are_complete() {
+        for levels in RANGES.into_iter().filter(|&levels| levels >= 6) {
+            let values = (0..levels).map(|value| unquantize_color(value, levels)).collect::<BTreeSet<_>>();
+
+            assert_eq!(values.len(), levels as usize, "{levels}");
+            assert_eq!((values.first(), values.last()), (Some(&0), Some(&255)), "{levels}");
+        }
+
+        for levels in RANGES.into_iter().take_while(|&levels| levels <= 32) {
+            let weights = (0..levels).map(|value| unquantize_weight(value, levels)).collect::<BTreeSet<_>>();
+
+            assert_eq!(weights.len(), levels as usize, "{levels}");
+            assert_eq!((weights.first(), weights.last()), (Some(&0), Some(&64)), "{levels}");
+        }
+    }
+
+    #[test]
+    fn partitions_are_in_range() {
+        for seed in 0..1024 {
+            for partitions in 2..=4 {
+                for (x, y) in [(0, 0), (3, 1), (11, 11)] {
+                    assert!(select_partition(seed, x, y, partitions, x < 4) < partitions as usize);
+                }
+            }
+        }
+    }
+}
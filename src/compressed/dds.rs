@@ -0,0 +1,149 @@
+use anyhow::{bail, ensure, Context};
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use wgpu::TextureFormat;
+
+use crate::compressed::GpuImage;
+use crate::models::ColorSpace;
+
+pub const MAGIC: [u8; 4] = *b"DDS ";
+
+// Only the first image of an array or cube map is read
+pub fn read(data: &[u8], color_space: ColorSpace) -> anyhow::Result<GpuImage> {
+    let dds = Dds::read(data)?;
+
+    ensure!(dds.get_depth() <= 1, "only 2d textures are supported");
+
+    // Only the dx10 header says which dxgi format it is, ddsfile guesses one for older files that's always srgb.
+    // Their d3d format is read with the color space instead, bc4 and bc5 files only have the guess
+    let format = match (&dds.header10, dds.get_d3d_format(), dds.get_dxgi_format()) {
+        (Some(_), _, Some(format)) | (None, None, Some(format)) => {
+            dxgi_format(format).with_context(|| format!("{format:?} isn't supported"))?
+        }
+        (None, Some(format), _) => {
+            d3d_format(format, color_space).with_context(|| format!("{format:?} isn't supported"))?
+        }
+        _ => bail!("it doesn't have a format that's supported"),
+    };
+
+    let mut image = GpuImage { format, width: dds.get_width(), height: dds.get_height(), levels: Vec::new() };
+    let mut remaining = dds.data.as_slice();
+
+    // Levels follow each other, largest first. More than 32 can't be right and wouldn't fit a u32 size
+    for level in 0..dds.get_num_mipmap_levels().clamp(1, 32) {
+        let length = image.level_length(level);
+        let data = remaining.get(..length).context("the file is cut short")?;
+
+        image.levels.push(data.to_vec());
+        remaining = &remaining[length..];
+    }
+
+    GpuImage::new(image.format, image.width, image.height, image.levels)
+}
+
+const fn dxgi_format(format: DxgiFormat) -> Option<TextureFormat> {
+    Some(match format {
+        DxgiFormat::R8G8B8A8_UNorm => TextureFormat::Rgba8Unorm,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => TextureFormat::Rgba8UnormSrgb,
+        DxgiFormat::B8G8R8A8_UNorm => TextureFormat::Bgra8Unorm,
+        DxgiFormat::B8G8R8A8_UNorm_sRGB => TextureFormat::Bgra8UnormSrgb,
+        DxgiFormat::BC1_UNorm => TextureFormat::Bc1RgbaUnorm,
+        DxgiFormat::BC1_UNorm_sRGB => TextureFormat::Bc1RgbaUnormSrgb,
+        DxgiFormat::BC2_UNorm => TextureFormat::Bc2RgbaUnorm,
+        DxgiFormat::BC2_UNorm_sRGB => TextureFormat::Bc2RgbaUnormSrgb,
+        DxgiFormat::BC3_UNorm => TextureFormat::Bc3RgbaUnorm,
+        DxgiFormat::BC3_UNorm_sRGB => TextureFormat::Bc3RgbaUnormSrgb,
+        DxgiFormat::BC4_UNorm => TextureFormat::Bc4RUnorm,
+        DxgiFormat::BC4_SNorm => TextureFormat::Bc4RSnorm,
+        DxgiFormat::BC5_UNorm => TextureFormat::Bc5RgUnorm,
+        DxgiFormat::BC5_SNorm => TextureFormat::Bc5RgSnorm,
+        DxgiFormat::BC6H_UF16 => TextureFormat::Bc6hRgbUfloat,
+        DxgiFormat::BC6H_SF16 => TextureFormat::Bc6hRgbSfloat,
+        DxgiFormat::BC7_UNorm => TextureFormat::Bc7RgbaUnorm,
+        DxgiFormat::BC7_UNorm_sRGB => TextureFormat::Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+// Older files don't say what color space they're in, that's up to what they're used for
+const fn d3d_format(format: D3DFormat, color_space: ColorSpace) -> Option<TextureFormat> {
+    let srgb = matches!(color_space, ColorSpace::Srgb);
+
+    Some(match format {
+        D3DFormat::A8B8G8R8 => color_space.rgba8(),
+        D3DFormat::A8R8G8B8 if srgb => TextureFormat::Bgra8UnormSrgb,
+        D3DFormat::A8R8G8B8 => TextureFormat::Bgra8Unorm,
+        D3DFormat::DXT1 if srgb => TextureFormat::Bc1RgbaUnormSrgb,
+        D3DFormat::DXT1 => TextureFormat::Bc1RgbaUnorm,
+        D3DFormat::DXT3 if srgb => TextureFormat::Bc2RgbaUnormSrgb,
+        D3DFormat::DXT3 => TextureFormat::Bc2RgbaUnorm,
+        D3DFormat::DXT5 if srgb => TextureFormat::Bc3RgbaUnormSrgb,
+        D3DFormat::DXT5 => TextureFormat::Bc3RgbaUnorm,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ddsfile::{AlphaMode, D3D10ResourceDimension, NewD3dParams, NewDxgiParams};
+
+    use super::*;
+
+    fn write(dds: &Dds) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        dds.write(&mut data).unwrap();
+
+        data
+    }
+
+    fn d3d_file(format: D3DFormat) -> Vec<u8> {
+        write(&Dds::new_d3d(NewD3dParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format,
+            mipmap_levels: None,
+            caps2: None,
+        }).unwrap())
+    }
+
+    #[test]
+    fn d3d_formats_take_the_color_space() {
+        for (format, srgb, linear) in [
+            (D3DFormat::A8B8G8R8, TextureFormat::Rgba8UnormSrgb, TextureFormat::Rgba8Unorm),
+            (D3DFormat::A8R8G8B8, TextureFormat::Bgra8UnormSrgb, TextureFormat::Bgra8Unorm),
+            (D3DFormat::DXT1, TextureFormat::Bc1RgbaUnormSrgb, TextureFormat::Bc1RgbaUnorm),
+            (D3DFormat::DXT3, TextureFormat::Bc2RgbaUnormSrgb, TextureFormat::Bc2RgbaUnorm),
+            (D3DFormat::DXT5, TextureFormat::Bc3RgbaUnormSrgb, TextureFormat::Bc3RgbaUnorm),
+        ] {
+            assert_eq!(d3d_format(format, ColorSpace::Srgb), Some(srgb), "{format:?}");
+            assert_eq!(d3d_format(format, ColorSpace::Linear), Some(linear), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn files_without_a_color_space_take_the_hint() {
+        let data = d3d_file(D3DFormat::DXT1);
+
+        assert_eq!(read(&data, ColorSpace::Srgb).unwrap().format, TextureFormat::Bc1RgbaUnormSrgb);
+        assert_eq!(read(&data, ColorSpace::Linear).unwrap().format, TextureFormat::Bc1RgbaUnorm);
+    }
+
+    #[test]
+    fn files_with_a_color_space_keep_it() {
+        let data = write(&Dds::new_dxgi(NewDxgiParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format: DxgiFormat::BC1_UNorm,
+            mipmap_levels: None,
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Unknown,
+        }).unwrap());
+
+        assert_eq!(read(&data, ColorSpace::Srgb).unwrap().format, TextureFormat::Bc1RgbaUnorm);
+    }
+}
@@ -0,0 +1,1268 @@
+use anyhow::bail;
+use wgpu::{AstcChannel, TextureFormat};
+
+use crate::compressed::{astc, GpuImage};
+
+// A 4x4 block of rgba pixels, a row at a time
+type Texels = [[u8; 4]; 16];
+
+const ETC_MODIFIERS: [[i32; 4]; 8] = [
+    [2, 8, -2, -8],
+    [5, 17, -5, -17],
+    [9, 29, -9, -29],
+    [13, 42, -13, -42],
+    [18, 60, -18, -60],
+    [24, 80, -24, -80],
+    [33, 106, -33, -106],
+    [47, 183, -47, -183],
+];
+
+const ETC_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+// 1.0 in the signed formats, whose texels are i8 from -127 to 127
+const SNORM_ONE: u8 = 127;
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+// What a format's blocks decode with
+#[derive(Clone, Copy)]
+enum Decoder {
+    Texels(fn(&[u8]) -> Texels),
+    // Signed or not
+    Bc6h(bool),
+    Astc(astc::Profile),
+}
+
+impl Decoder {
+    // Into texels of the output format, a row at a time
+    fn decode(self, block: &[u8], width: usize, height: usize, texels: &mut [u8]) {
+        match self {
+            Self::Texels(decoder) => {
+                for (texel, rgba) in texels.chunks_exact_mut(4).zip(decoder(block)) {
+                    texel.copy_from_slice(&rgba);
+                }
+            }
+            Self::Bc6h(signed) => {
+                for (texel, rgba) in texels.chunks_exact_mut(8).zip(bc6h(block, signed)) {
+                    texel.copy_from_slice(&rgba.map(u16::to_le_bytes).concat());
+                }
+            }
+            Self::Astc(profile) => astc::decode_block(block, width, height, profile, texels),
+        }
+    }
+}
+
+// The bc, etc2 and astc formats, to rgba8 in the same color space, or rgba8 snorm for the signed ones.
+// One and two channel formats come out the way the gpu would sample them, with the rest zero.
+// Bc6h and hdr astc are half floats instead, which filter on every adapter
+pub fn decode(image: &GpuImage) -> anyhow::Result<GpuImage> {
+    let info = image.format.describe();
+
+    let decoder = match image.format {
+        TextureFormat::Bc6hRgbUfloat => Decoder::Bc6h(false),
+        TextureFormat::Bc6hRgbSfloat => Decoder::Bc6h(true),
+        TextureFormat::Astc { channel: AstcChannel::Hdr, .. } => Decoder::Astc(astc::Profile::Hdr),
+        TextureFormat::Astc { .. } => Decoder::Astc(astc::Profile::Ldr { srgb: info.srgb }),
+        format => Decoder::Texels(texel_decoder(format)?),
+    };
+
+    let format = match image.format {
+        TextureFormat::Bc4RSnorm
+        | TextureFormat::Bc5RgSnorm
+        | TextureFormat::EacR11Snorm
+        | TextureFormat::EacRg11Snorm => TextureFormat::Rgba8Snorm,
+        TextureFormat::Bc6hRgbUfloat
+        | TextureFormat::Bc6hRgbSfloat
+        | TextureFormat::Astc { channel: AstcChannel::Hdr, .. } => TextureFormat::Rgba16Float,
+        _ if info.srgb => TextureFormat::Rgba8UnormSrgb,
+        _ => TextureFormat::Rgba8Unorm,
+    };
+
+    let texel_size = usize::from(format.describe().block_size);
+    let (block_width, block_height) = (usize::from(info.block_dimensions.0), usize::from(info.block_dimensions.1));
+    let block_row = block_width * texel_size;
+    let mut texels = vec![0; block_row * block_height];
+    let mut levels = Vec::with_capacity(image.levels.len());
+
+    for (level, data) in (0..).zip(&image.levels) {
+        let size = image.level_size(level);
+        let (width, height) = (size.width as usize, size.height as usize);
+        let blocks_wide = width.div_ceil(block_width);
+        let mut pixels = vec![0; width * height * texel_size];
+
+        for (index, block) in data.chunks_exact(usize::from(info.block_size)).enumerate() {
+            let (left, top) = (index % blocks_wide * block_width, index / blocks_wide * block_height);
+
+            decoder.decode(block, block_width, block_height, &mut texels);
+
+            // Blocks at the right and bottom edges can hang over
+            let columns = block_width.min(width - left) * texel_size;
+
+            for (y, row) in (top..height).zip(texels.chunks_exact(block_row)) {
+                pixels[(y * width + left) * texel_size..][..columns].copy_from_slice(&row[..columns]);
+            }
+        }
+
+        levels.push(pixels);
+    }
+
+    Ok(GpuImage { format, width: image.width, height: image.height, levels })
+}
+
+// The formats in 4x4 blocks of up to four 8 bit channels
+fn texel_decoder(format: TextureFormat) -> anyhow::Result<fn(&[u8]) -> Texels> {
+    Ok(match format {
+        TextureFormat::Bc1RgbaUnorm | TextureFormat::Bc1RgbaUnormSrgb => |block| color_block(block, false),
+        TextureFormat::Bc2RgbaUnorm | TextureFormat::Bc2RgbaUnormSrgb => bc2,
+        TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb => bc3,
+        TextureFormat::Bc4RUnorm => |block| channel_block(block, false).map(|red| [red, 0, 0, 255]),
+        TextureFormat::Bc4RSnorm => |block| channel_block(block, true).map(|red| [red, 0, 0, SNORM_ONE]),
+        TextureFormat::Bc5RgUnorm => |block| bc5(block, false),
+        TextureFormat::Bc5RgSnorm => |block| bc5(block, true),
+        TextureFormat::Bc7RgbaUnorm | TextureFormat::Bc7RgbaUnormSrgb => bc7,
+        TextureFormat::Etc2Rgb8Unorm | TextureFormat::Etc2Rgb8UnormSrgb => |block| etc2_rgb(block, false),
+        TextureFormat::Etc2Rgb8A1Unorm | TextureFormat::Etc2Rgb8A1UnormSrgb => |block| etc2_rgb(block, true),
+        TextureFormat::Etc2Rgba8Unorm | TextureFormat::Etc2Rgba8UnormSrgb => etc2_rgba,
+        TextureFormat::EacR11Unorm => |block| eac_block(block, Eac::Unsigned).map(|red| [red, 0, 0, 255]),
+        TextureFormat::EacR11Snorm => |block| eac_block(block, Eac::Signed).map(|red| [red, 0, 0, SNORM_ONE]),
+        TextureFormat::EacRg11Unorm => |block| eac_rg(block, Eac::Unsigned),
+        TextureFormat::EacRg11Snorm => |block| eac_rg(block, Eac::Signed),
+        format => bail!(
+            "there's no decoder for {format:?}, it only loads in whole blocks on adapters with {:?}",
+            format.describe().required_features,
+        ),
+    })
+}
+
+fn bc2(block: &[u8]) -> Texels {
+    let alpha = le_u64(&block[..8]);
+    let mut texels = color_block(&block[8..], true);
+
+    for (shift, texel) in (0..).step_by(4).zip(&mut texels) {
+        texel[3] = bits(alpha, shift, 4) * 17;
+    }
+
+    texels
+}
+
+fn bc3(block: &[u8]) -> Texels {
+    let alpha = channel_block(&block[..8], false);
+    let mut texels = color_block(&block[8..], true);
+
+    for (texel, alpha) in texels.iter_mut().zip(alpha) {
+        texel[3] = alpha;
+    }
+
+    texels
+}
+
+fn bc5(block: &[u8], signed: bool) -> Texels {
+    let (red, green) = (channel_block(&block[..8], signed), channel_block(&block[8..], signed));
+    let alpha = if signed { SNORM_ONE } else { 255 };
+
+    std::array::from_fn(|texel| [red[texel], green[texel], 0, alpha])
+}
+
+// Bc1, and the color half of bc2 and bc3 which always have four colors
+fn color_block(block: &[u8], four_colors: bool) -> Texels {
+    let (first, second) = (u16::from_le_bytes([block[0], block[1]]), u16::from_le_bytes([block[2], block[3]]));
+    let (a, b) = (rgb565(first), rgb565(second));
+
+    let palette = if four_colors || first > second {
+        [a, b, mix(a, b, 1, 3), mix(a, b, 2, 3)]
+    } else {
+        [a, b, mix(a, b, 1, 2), [0; 4]]
+    };
+
+    let indices = le_u64(&block[4..8]);
+    let mut texels = [[0; 4]; 16];
+
+    for (shift, texel) in (0..).step_by(2).zip(&mut texels) {
+        *texel = palette[usize::from(bits(indices, shift, 2))];
+    }
+
+    texels
+}
+
+// Bc4, and the alpha of bc3. Signed endpoints are i8, with -128 read as -127
+fn channel_block(block: &[u8], signed: bool) -> [u8; 16] {
+    let endpoint = |byte| if signed { i32::from(i8::from_le_bytes([byte]).max(-127)) } else { i32::from(byte) };
+    let (a, b) = (endpoint(block[0]), endpoint(block[1]));
+    let (low, high) = if signed { (-127, 127) } else { (0, 255) };
+    let mut palette = [a, b, 0, 0, 0, 0, low, high];
+
+    if a > b {
+        for (weight, value) in (1..7).zip(&mut palette[2..]) {
+            *value = ((7 - weight) * a + weight * b) / 7;
+        }
+    } else {
+        for (weight, value) in (1..5).zip(&mut palette[2..6]) {
+            *value = ((5 - weight) * a + weight * b) / 5;
+        }
+    }
+
+    let indices = le_u64(&block[2..8]);
+    let mut values = [0; 16];
+
+    for (shift, value) in (0..).step_by(3).zip(&mut values) {
+        let index = usize::from(bits(indices, shift, 3));
+
+        *value = if signed { snorm(palette[index]) } else { saturate(palette[index]) };
+    }
+
+    values
+}
+
+// Bc7 fields in the order they're read, per mode. Every endpoint has a p-bit, or each subset shares one
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    secondary_index_bits: u32,
+}
+
+const fn bc7_mode(
+    subsets: usize,
+    [partition_bits, rotation_bits, selection_bits]: [u32; 3],
+    [color_bits, alpha_bits]: [u32; 2],
+    [endpoint_pbits, shared_pbits]: [bool; 2],
+    [index_bits, secondary_index_bits]: [u32; 2],
+) -> Bc7Mode {
+    Bc7Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_pbits,
+        shared_pbits,
+        index_bits,
+        secondary_index_bits,
+    }
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    bc7_mode(3, [4, 0, 0], [4, 0], [true, false], [3, 0]),
+    bc7_mode(2, [6, 0, 0], [6, 0], [false, true], [3, 0]),
+    bc7_mode(3, [6, 0, 0], [5, 0], [false, false], [2, 0]),
+    bc7_mode(2, [6, 0, 0], [7, 0], [true, false], [2, 0]),
+    bc7_mode(1, [0, 2, 1], [5, 6], [false, false], [2, 3]),
+    bc7_mode(1, [0, 2, 0], [7, 8], [false, false], [2, 2]),
+    bc7_mode(1, [0, 0, 0], [7, 7], [true, false], [4, 0]),
+    bc7_mode(2, [6, 0, 0], [5, 5], [true, false], [2, 0]),
+];
+
+// Which subset each texel is in, a bit a texel for two subsets and two bits for three
+const BC7_PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80,
+    0xc800, 0xffec, 0xfe80, 0xe800, 0xffe8, 0xff00, 0xfff0, 0xf000,
+    0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c,
+    0xaaaa, 0xf0f0, 0x5a5a, 0x33cc, 0x3c3c, 0x55aa, 0x9696, 0xa55a,
+    0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c,
+    0x9336, 0x9cc6, 0x817e, 0xe718, 0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+const BC7_PARTITIONS_3: [u32; 64] = [
+    0xaa68_5050, 0x6a5a_5040, 0x5a5a_4200, 0x5450_a0a8, 0xa5a5_0000, 0xa0a0_5050, 0x5555_a0a0, 0x5a5a_5050,
+    0xaa55_0000, 0xaa55_5500, 0xaaaa_5500, 0x9090_9090, 0x9494_9494, 0xa4a4_a4a4, 0xa9a5_9450, 0x2a0a_4250,
+    0xa594_5040, 0x0a42_5054, 0xa5a5_a500, 0x55a0_a0a0, 0xa8a8_5454, 0x6a6a_4040, 0xa4a4_5000, 0x1a1a_0500,
+    0x0050_a4a4, 0xaaa5_9090, 0x1469_6914, 0x6969_1400, 0xa085_85a0, 0xaa82_1414, 0x50a4_a450, 0x6a5a_0200,
+    0xa9a5_8000, 0x5090_a0a8, 0xa8a0_9050, 0x2424_2424, 0x00aa_5500, 0x2492_4924, 0x2449_9224, 0x50a5_0a50,
+    0x500a_a550, 0xaaaa_4444, 0x6666_0000, 0xa5a0_a5a0, 0x50a0_50a0, 0x6928_6928, 0x44aa_aa44, 0x6666_6600,
+    0xaa44_4444, 0x54a8_54a8, 0x9580_9580, 0x9696_9600, 0xa854_54a8, 0x8095_9580, 0xaa14_1414, 0x9696_0000,
+    0xaaaa_1414, 0xa050_50a0, 0xa0a5_a5a0, 0x9600_0000, 0x4080_4080, 0xa9a8_a9a8, 0xaaaa_aa44, 0x2a4a_5254,
+];
+
+// The texel whose index leaves out its top bit, past the first subset's which is always texel 0
+const BC7_ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+const BC7_ANCHORS_3: [[u8; 2]; 64] = [
+    [3, 15], [3, 8], [15, 8], [15, 3], [8, 15], [3, 15], [15, 3], [15, 8],
+    [8, 15], [8, 15], [6, 15], [6, 15], [6, 15], [5, 15], [3, 15], [3, 8],
+    [3, 15], [3, 8], [8, 15], [15, 3], [3, 15], [3, 8], [6, 15], [10, 8],
+    [5, 3], [8, 15], [8, 6], [6, 10], [8, 15], [5, 15], [15, 10], [15, 8],
+    [8, 15], [15, 3], [3, 15], [5, 10], [6, 10], [10, 8], [8, 9], [15, 10],
+    [15, 6], [3, 15], [15, 8], [5, 15], [15, 3], [15, 6], [15, 6], [15, 8],
+    [3, 15], [15, 3], [5, 15], [5, 15], [5, 15], [8, 15], [5, 15], [10, 15],
+    [5, 15], [10, 15], [8, 15], [13, 15], [15, 3], [12, 15], [3, 15], [3, 8],
+];
+
+const BC7_WEIGHTS_2: [i32; 4] = [0, 21, 43, 64];
+const BC7_WEIGHTS_3: [i32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const BC7_WEIGHTS_4: [i32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+// The mode is the lowest bit set, then everything else is read from the low bits up
+fn bc7(block: &[u8]) -> Texels {
+    let Some(mode) = BC7_MODES.get(block[0].trailing_zeros() as usize) else {
+        // Reserved, decoders give transparent black
+        return [[0; 4]; 16];
+    };
+
+    let value = block.iter().rev().fold(0, |value, &byte| value << 8 | u128::from(byte));
+    let mut reader = BlockReader { block: value, shift: 0 };
+
+    reader.take(block[0].trailing_zeros() + 1);
+
+    let partition = usize::from(reader.take(mode.partition_bits));
+    let rotation = reader.take(mode.rotation_bits);
+    let selection = reader.take(mode.selection_bits);
+
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0; 4]; 6];
+
+    for channel in 0..4 {
+        let count = if channel < 3 { mode.color_bits } else { mode.alpha_bits };
+
+        for endpoint in &mut endpoints[..endpoint_count] {
+            endpoint[channel] = reader.take(count);
+        }
+    }
+
+    let mut pbits = [0; 6];
+
+    if mode.endpoint_pbits {
+        for pbit in &mut pbits[..endpoint_count] {
+            *pbit = reader.take(1);
+        }
+    } else if mode.shared_pbits {
+        for subset in 0..mode.subsets {
+            pbits[subset * 2] = reader.take(1);
+            pbits[subset * 2 + 1] = pbits[subset * 2];
+        }
+    }
+
+    let has_pbits = mode.endpoint_pbits || mode.shared_pbits;
+
+    // The p-bit is the lowest bit of every channel, then they're widened to 8 bits
+    let endpoints: [[i32; 4]; 6] = std::array::from_fn(|endpoint| {
+        std::array::from_fn(|channel| {
+            let count = if channel < 3 { mode.color_bits } else { mode.alpha_bits };
+            let value = endpoints[endpoint][channel];
+
+            match (count, has_pbits) {
+                (0, _) => 255,
+                (_, true) => extend(value << 1 | pbits[endpoint], count + 1),
+                (_, false) => extend(value, count),
+            }
+        })
+    });
+
+    let subset = |texel: usize| match mode.subsets {
+        2 => usize::from(BC7_PARTITIONS_2[partition] >> texel & 1),
+        3 => (BC7_PARTITIONS_3[partition] >> (texel * 2) & 3) as usize,
+        _ => 0,
+    };
+
+    let anchor = |texel: usize| match (mode.subsets, subset(texel)) {
+        (_, 0) => texel == 0,
+        (2, _) => texel == usize::from(BC7_ANCHORS_2[partition]),
+        (_, subset) => texel == usize::from(BC7_ANCHORS_3[partition][subset - 1]),
+    };
+
+    let indices: [u8; 16] = std::array::from_fn(|texel| reader.take(mode.index_bits - u32::from(anchor(texel))));
+    let secondary_indices: [u8; 16] = std::array::from_fn(|texel| {
+        if mode.secondary_index_bits == 0 { 0 } else { reader.take(mode.secondary_index_bits - u32::from(texel == 0)) }
+    });
+
+    let weight = |count, index: u8| match count {
+        2 => BC7_WEIGHTS_2[usize::from(index)],
+        3 => BC7_WEIGHTS_3[usize::from(index)],
+        _ => BC7_WEIGHTS_4[usize::from(index)],
+    };
+
+    std::array::from_fn(|texel| {
+        let primary = weight(mode.index_bits, indices[texel]);
+        let secondary = weight(mode.secondary_index_bits, secondary_indices[texel]);
+
+        let (color_weight, alpha_weight) = match (mode.secondary_index_bits, selection) {
+            (0, _) => (primary, primary),
+            (_, 0) => (primary, secondary),
+            _ => (secondary, primary),
+        };
+
+        let subset = subset(texel);
+        let (first, second) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+
+        let mut rgba: [u8; 4] = std::array::from_fn(|channel| {
+            let weight = if channel < 3 { color_weight } else { alpha_weight };
+
+            saturate(((64 - weight) * first[channel] + weight * second[channel] + 32) >> 6)
+        });
+
+        // Modes 4 and 5 can store a color channel where alpha goes, which has more bits
+        if rotation > 0 {
+            rgba.swap(usize::from(rotation - 1), 3);
+        }
+
+        rgba
+    })
+}
+
+// Where bc6h endpoint bits go, endpoint w, x, y or z and then the channel
+const RW: usize = 0;
+const GW: usize = 1;
+const BW: usize = 2;
+const RX: usize = 3;
+const GX: usize = 4;
+const BX: usize = 5;
+const RY: usize = 6;
+const GY: usize = 7;
+const BY: usize = 8;
+const RZ: usize = 9;
+const GZ: usize = 10;
+const BZ: usize = 11;
+
+// Bc6h modes are 2 bits, or 5 when the first two are 2 or 3. Endpoints are spread over the block in runs of
+// (endpoint channel, lowest bit, count), the ones after w are deltas from it in modes with fewer bits for them
+struct Bc6hMode {
+    bits: u8,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    fields: &'static [(usize, u32, u32)],
+}
+
+const fn bc6h_mode(
+    bits: u8,
+    [endpoint_bits, red, green, blue]: [u32; 4],
+    fields: &'static [(usize, u32, u32)],
+) -> Bc6hMode {
+    Bc6hMode { bits, endpoint_bits, delta_bits: [red, green, blue], fields }
+}
+
+#[rustfmt::skip]
+const BC6H_MODES: [Bc6hMode; 14] = [
+    bc6h_mode(0b00, [10, 5, 5, 5], &[
+        (GY, 4, 1), (BY, 4, 1), (BZ, 4, 1), (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5), (GZ, 4, 1),
+        (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 5),
+        (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+    ]),
+    bc6h_mode(0b01, [7, 6, 6, 6], &[
+        (GY, 5, 1), (GZ, 4, 1), (GZ, 5, 1), (RW, 0, 7), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 7),
+        (BY, 5, 1), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 7), (BZ, 3, 1), (BZ, 5, 1), (BZ, 4, 1), (RX, 0, 6),
+        (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4), (RY, 0, 6), (RZ, 0, 6),
+    ]),
+    bc6h_mode(0b00010, [11, 5, 4, 4], &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5), (RW, 10, 1), (GY, 0, 4), (GX, 0, 4), (GW, 10, 1),
+        (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1),
+        (RZ, 0, 5), (BZ, 3, 1),
+    ]),
+    bc6h_mode(0b00110, [11, 4, 5, 4], &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5),
+        (GW, 10, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 4), (BZ, 0, 1),
+        (BZ, 2, 1), (RZ, 0, 4), (GY, 4, 1), (BZ, 3, 1),
+    ]),
+    bc6h_mode(0b01010, [11, 4, 4, 5], &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (BY, 4, 1), (GY, 0, 4), (GX, 0, 4),
+        (GW, 10, 1), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BW, 10, 1), (BY, 0, 4), (RY, 0, 4), (BZ, 1, 1),
+        (BZ, 2, 1), (RZ, 0, 4), (BZ, 4, 1), (BZ, 3, 1),
+    ]),
+    bc6h_mode(0b01110, [9, 5, 5, 5], &[
+        (RW, 0, 9), (BY, 4, 1), (GW, 0, 9), (GY, 4, 1), (BW, 0, 9), (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1),
+        (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 5),
+        (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+    ]),
+    bc6h_mode(0b10010, [8, 6, 5, 5], &[
+        (RW, 0, 8), (GZ, 4, 1), (BY, 4, 1), (GW, 0, 8), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 8), (BZ, 3, 1),
+        (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+        (BY, 0, 4), (RY, 0, 6), (RZ, 0, 6),
+    ]),
+    bc6h_mode(0b10110, [8, 5, 6, 5], &[
+        (RW, 0, 8), (BZ, 0, 1), (BY, 4, 1), (GW, 0, 8), (GY, 5, 1), (GY, 4, 1), (BW, 0, 8), (GZ, 5, 1),
+        (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+        (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+    ]),
+    bc6h_mode(0b11010, [8, 5, 5, 6], &[
+        (RW, 0, 8), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 8), (BY, 5, 1), (GY, 4, 1), (BW, 0, 8), (BZ, 5, 1),
+        (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 6),
+        (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+    ]),
+    bc6h_mode(0b11110, [6, 6, 6, 6], &[
+        (RW, 0, 6), (GZ, 4, 1), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 6), (GY, 5, 1), (BY, 5, 1),
+        (BZ, 2, 1), (GY, 4, 1), (BW, 0, 6), (GZ, 5, 1), (BZ, 3, 1), (BZ, 5, 1), (BZ, 4, 1), (RX, 0, 6),
+        (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4), (RY, 0, 6), (RZ, 0, 6),
+    ]),
+    bc6h_mode(0b00011, [10, 10, 10, 10], &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 10), (GX, 0, 10), (BX, 0, 10),
+    ]),
+    bc6h_mode(0b00111, [11, 9, 9, 9], &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 9), (RW, 10, 1), (GX, 0, 9), (GW, 10, 1), (BX, 0, 9),
+        (BW, 10, 1),
+    ]),
+    // The top bits of w are stored highest first
+    bc6h_mode(0b01011, [12, 8, 8, 8], &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 8), (RW, 11, 1), (RW, 10, 1), (GX, 0, 8), (GW, 11, 1),
+        (GW, 10, 1), (BX, 0, 8), (BW, 11, 1), (BW, 10, 1),
+    ]),
+    bc6h_mode(0b01111, [16, 4, 4, 4], &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 15, 1), (RW, 14, 1), (RW, 13, 1), (RW, 12, 1),
+        (RW, 11, 1), (RW, 10, 1), (GX, 0, 4), (GW, 15, 1), (GW, 14, 1), (GW, 13, 1), (GW, 12, 1), (GW, 11, 1),
+        (GW, 10, 1), (BX, 0, 4), (BW, 15, 1), (BW, 14, 1), (BW, 13, 1), (BW, 12, 1), (BW, 11, 1), (BW, 10, 1),
+    ]),
+];
+
+// 1.0 as a half float
+const HALF_ONE: u16 = 0x3c00;
+
+// To half floats, the way d3d does. Modes 10 to 13 have one region and no partition
+fn bc6h(block: &[u8], signed: bool) -> [[u16; 4]; 16] {
+    let value = block.iter().rev().fold(0, |value, &byte| value << 8 | u128::from(byte));
+    let mut reader = BlockReader { block: value, shift: 0 };
+    let low = reader.take(2);
+    let bits = if low < 2 { low } else { low | reader.take(3) << 2 };
+
+    let Some(mode) = BC6H_MODES.iter().find(|mode| mode.bits == bits) else {
+        // Reserved, decoders give black
+        return [[0, 0, 0, HALF_ONE]; 16];
+    };
+
+    let mut endpoints = [[0; 3]; 4];
+
+    for &(field, shift, count) in mode.fields {
+        endpoints[field / 3][field % 3] |= reader.take_wide(count) << shift;
+    }
+
+    let regions = if bits & 3 == 3 { 1 } else { 2 };
+    let partition = if regions == 2 { usize::from(reader.take(5)) } else { 0 };
+    let endpoint_bits = mode.endpoint_bits;
+    let sign_extend = |value: i32, count: u32| value << (32 - count) >> (32 - count);
+
+    if signed {
+        endpoints[0] = endpoints[0].map(|value| sign_extend(value, endpoint_bits));
+    }
+
+    // Deltas wrap around in the endpoint's bits
+    let base = endpoints[0];
+
+    for endpoint in &mut endpoints[1..regions * 2] {
+        for ((value, base), delta_bits) in endpoint.iter_mut().zip(base).zip(mode.delta_bits) {
+            if delta_bits < endpoint_bits {
+                *value = (base + sign_extend(*value, delta_bits)) & ((1 << endpoint_bits) - 1);
+            }
+
+            if signed {
+                *value = sign_extend(*value, endpoint_bits);
+            }
+        }
+    }
+
+    // Widened to 16 bits, then to the half floats' range after interpolating
+    let endpoints = endpoints.map(|endpoint| endpoint.map(|value| bc6h_unquantize(value, endpoint_bits, signed)));
+
+    let anchor = |texel: usize| texel == 0 || regions == 2 && texel == usize::from(BC7_ANCHORS_2[partition]);
+    let index_bits = if regions == 2 { 3 } else { 4 };
+
+    std::array::from_fn(|texel| {
+        let index = usize::from(reader.take(index_bits - u32::from(anchor(texel))));
+        let weight = if regions == 2 { BC7_WEIGHTS_3[index] } else { BC7_WEIGHTS_4[index] };
+        let region = if regions == 2 { usize::from(BC7_PARTITIONS_2[partition] >> texel & 1) } else { 0 };
+        let (first, second) = (endpoints[region * 2], endpoints[region * 2 + 1]);
+
+        let [red, green, blue] = std::array::from_fn(|channel| {
+            let value = ((64 - weight) * first[channel] + weight * second[channel] + 32) >> 6;
+
+            bc6h_finish(value, signed)
+        });
+
+        [red, green, blue, HALF_ONE]
+    })
+}
+
+const fn bc6h_unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if !signed {
+        return match value {
+            _ if bits >= 15 => value,
+            0 => 0,
+            _ if value == (1 << bits) - 1 => 0xffff,
+            _ => ((value << 16) + 0x8000) >> bits,
+        };
+    }
+
+    if bits >= 16 {
+        return value;
+    }
+
+    let magnitude = match value.abs() {
+        0 => 0,
+        magnitude if magnitude >= (1 << (bits - 1)) - 1 => 0x7fff,
+        magnitude => ((magnitude << 15) + 0x4000) >> (bits - 1),
+    };
+
+    if value < 0 { -magnitude } else { magnitude }
+}
+
+// Scaled to 31/64 of the unquantized range, so the largest value is the largest finite half float
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+const fn bc6h_finish(value: i32, signed: bool) -> u16 {
+    if !signed {
+        return ((value * 31) >> 6) as u16;
+    }
+
+    let magnitude = ((value.abs() * 31) >> 5) as u16;
+
+    if value < 0 { 0x8000 | magnitude } else { magnitude }
+}
+
+struct BlockReader {
+    block: u128,
+    shift: u32,
+}
+
+impl BlockReader {
+    // Never more than 8 bits, so the cast can't lose any
+    #[allow(clippy::cast_possible_truncation)]
+    const fn take(&mut self, count: u32) -> u8 {
+        let value = (self.block >> self.shift & ((1 << count) - 1)) as u8;
+
+        self.shift += count;
+
+        value
+    }
+
+    // Bc6h's endpoints can be read 10 bits at a time
+    fn take_wide(&mut self, count: u32) -> i32 {
+        let low = self.take(count.min(8));
+
+        i32::from(self.take(count.saturating_sub(8))) << 8 | i32::from(low)
+    }
+}
+
+fn etc2_rgba(block: &[u8]) -> Texels {
+    let alpha = eac_block(&block[..8], Eac::Alpha);
+    let mut texels = etc2_rgb(&block[8..], false);
+
+    for (texel, alpha) in texels.iter_mut().zip(alpha) {
+        texel[3] = alpha;
+    }
+
+    texels
+}
+
+fn eac_rg(block: &[u8], eac: Eac) -> Texels {
+    let (red, green) = (eac_block(&block[..8], eac), eac_block(&block[8..], eac));
+    let alpha = if matches!(eac, Eac::Signed) { SNORM_ONE } else { 255 };
+
+    std::array::from_fn(|texel| [red[texel], green[texel], 0, alpha])
+}
+
+// Etc2 blocks are big endian, with the pixels a column at a time. Differential colors that overflow
+// pick the t, h and planar modes, in that order.
+// Punchthrough blocks are always differential, the bit says whether they're opaque instead. When they aren't,
+// the texels that would take the third color or modifier are transparent black, except in the planar mode
+fn etc2_rgb(block: &[u8], punchthrough: bool) -> Texels {
+    let block = be_u64(block);
+    let field = |shift, count| bits(block, shift, count);
+    let extend4 = |value| extend(value, 4);
+    let transparent = punchthrough && field(33, 1) == 0;
+
+    if !punchthrough && field(33, 1) == 0 {
+        let first = [field(60, 4), field(52, 4), field(44, 4)].map(extend4);
+        let second = [field(56, 4), field(48, 4), field(40, 4)].map(extend4);
+
+        return etc_subblocks(block, first, second, false);
+    }
+
+    let base = [field(59, 5), field(51, 5), field(43, 5)].map(i32::from);
+    let delta = [field(56, 3), field(48, 3), field(40, 3)].map(|value| i32::from(value) - if value < 4 { 0 } else { 8 });
+    let overflows = |channel: usize| !(0..32).contains(&(base[channel] + delta[channel]));
+
+    let mut texels = if overflows(0) {
+        let first = [field(59, 2) << 2 | field(56, 2), field(52, 4), field(48, 4)].map(extend4);
+        let second = [field(44, 4), field(40, 4), field(36, 4)].map(extend4);
+        let distance = ETC_DISTANCES[usize::from(field(34, 2) << 1 | field(32, 1))];
+        let paint = [first, offset(second, distance), second, offset(second, -distance)];
+
+        etc_texels(|x, y| paint[etc_selector(block, x, y)])
+    } else if overflows(1) {
+        let first = [field(59, 4), field(56, 3) << 1 | field(52, 1), field(51, 1) << 3 | field(47, 3)];
+        let second = [field(43, 4), field(39, 4), field(35, 4)];
+        let value = |[r, g, b]: [u8; 3]| u32::from(r) << 8 | u32::from(g) << 4 | u32::from(b);
+        let order = u8::from(value(first) >= value(second));
+        let distance = ETC_DISTANCES[usize::from(field(34, 1) << 2 | field(32, 1) << 1 | order)];
+        let (first, second) = (first.map(extend4), second.map(extend4));
+
+        let paint = [
+            offset(first, distance),
+            offset(first, -distance),
+            offset(second, distance),
+            offset(second, -distance),
+        ];
+
+        etc_texels(|x, y| paint[etc_selector(block, x, y)])
+    } else if overflows(2) {
+        let origin = [
+            extend(field(57, 6), 6),
+            extend(field(56, 1) << 6 | field(49, 6), 7),
+            extend(field(48, 1) << 5 | field(43, 2) << 3 | field(39, 3), 6),
+        ];
+        let horizontal = [extend(field(34, 5) << 1 | field(32, 1), 6), extend(field(25, 7), 7), extend(field(19, 6), 6)];
+        let vertical = [extend(field(13, 6), 6), extend(field(6, 7), 7), extend(field(0, 6), 6)];
+
+        return etc_texels(|x, y| {
+            let (x, y) = (i32::from(x), i32::from(y));
+
+            std::array::from_fn(|channel| {
+                let origin = origin[channel];
+
+                (x * (horizontal[channel] - origin) + y * (vertical[channel] - origin) + 4 * origin + 2) >> 2
+            })
+        });
+    } else {
+        let extend5 = |value: i32| value << 3 | value >> 2;
+
+        let first = base.map(extend5);
+        let second = std::array::from_fn(|channel| extend5(base[channel] + delta[channel]));
+
+        etc_subblocks(block, first, second, transparent)
+    };
+
+    if transparent {
+        for y in 0..4 {
+            for x in 0..4 {
+                if etc_selector(block, x, y) == 2 {
+                    texels[usize::from(y * 4 + x)] = [0; 4];
+                }
+            }
+        }
+    }
+
+    texels
+}
+
+// The individual and differential modes, two halves side by side or one above the other.
+// Transparent blocks don't use the smaller modifiers
+fn etc_subblocks(block: u64, first: [i32; 3], second: [i32; 3], transparent: bool) -> Texels {
+    let flipped = bits(block, 32, 1) == 1;
+    let tables = [bits(block, 37, 3), bits(block, 34, 3)].map(|table| {
+        let [small, large, minus_small, minus_large] = ETC_MODIFIERS[usize::from(table)];
+
+        if transparent { [0, large, 0, minus_large] } else { [small, large, minus_small, minus_large] }
+    });
+
+    etc_texels(|x, y| {
+        let second_half = if flipped { y >= 2 } else { x >= 2 };
+        let base = if second_half { second } else { first };
+
+        offset(base, tables[usize::from(second_half)][etc_selector(block, x, y)])
+    })
+}
+
+// Two bits a pixel, all the high bits first
+fn etc_selector(block: u64, x: u8, y: u8) -> usize {
+    let index = u32::from(x * 4 + y);
+
+    usize::from(bits(block, 16 + index, 1) << 1 | bits(block, index, 1))
+}
+
+fn etc_texels(color: impl Fn(u8, u8) -> [i32; 3]) -> Texels {
+    let mut texels = [[0; 4]; 16];
+
+    for y in 0..4 {
+        for x in 0..4 {
+            let [r, g, b] = color(x, y);
+
+            texels[usize::from(y * 4 + x)] = [saturate(r), saturate(g), saturate(b), 255];
+        }
+    }
+
+    texels
+}
+
+// How an eac block's values are read, etc2 alpha has 8 bits and the channels of the eac formats 11
+#[derive(Clone, Copy)]
+enum Eac {
+    Alpha,
+    Unsigned,
+    Signed,
+}
+
+// The 11 bit channels are scaled down to 8 here, signed ones to i8 from -127 to 127
+fn eac_block(block: &[u8], eac: Eac) -> [u8; 16] {
+    let block = be_u64(block);
+    let base = bits(block, 56, 8);
+    let multiplier = i32::from(bits(block, 52, 4));
+    let modifiers = EAC_MODIFIERS[usize::from(bits(block, 48, 4))];
+    let mut values = [0; 16];
+
+    for x in 0..4_u8 {
+        for y in 0..4 {
+            let modifier = modifiers[usize::from(bits(block, 45 - 3 * u32::from(x * 4 + y), 3))];
+
+            let eleven_bit_modifier = modifier * if multiplier == 0 { 1 } else { multiplier * 8 };
+
+            values[usize::from(y * 4 + x)] = match eac {
+                Eac::Alpha => saturate(i32::from(base) + modifier * multiplier),
+                Eac::Unsigned => {
+                    let value = i32::from(base) * 8 + 4 + eleven_bit_modifier;
+
+                    saturate((value.clamp(0, 2047) * 255 + 1023) / 2047)
+                }
+                Eac::Signed => {
+                    let base = i32::from(i8::from_le_bytes([base]).max(-127));
+                    let value = (base * 8 + eleven_bit_modifier).clamp(-1023, 1023);
+
+                    snorm((value * 127 + value.signum() * 511) / 1023)
+                }
+            };
+        }
+    }
+
+    values
+}
+
+fn rgb565(color: u16) -> [u8; 4] {
+    let color = u64::from(color);
+    let [r, g, b] = [(11, 5), (5, 6), (0, 5)].map(|(shift, count)| saturate(extend(bits(color, shift, count), count)));
+
+    [r, g, b, 255]
+}
+
+// Weight parts of b to total - weight parts of a
+fn mix(a: [u8; 4], b: [u8; 4], weight: i32, total: i32) -> [u8; 4] {
+    std::array::from_fn(|channel| saturate(((total - weight) * i32::from(a[channel]) + weight * i32::from(b[channel])) / total))
+}
+
+fn offset(color: [i32; 3], amount: i32) -> [i32; 3] {
+    color.map(|value| value + amount)
+}
+
+// Widens a channel to 8 bits by repeating its high bits in the low ones
+fn extend(value: u8, bits: u32) -> i32 {
+    let value = i32::from(value);
+
+    value << (8 - bits) | value >> (2 * bits - 8)
+}
+
+// Never more than 8 bits, so the cast can't lose any
+#[allow(clippy::cast_possible_truncation)]
+const fn bits(value: u64, shift: u32, count: u32) -> u8 {
+    (value >> shift & ((1 << count) - 1)) as u8
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+const fn saturate(value: i32) -> u8 {
+    if value < 0 { 0 } else if value > 255 { 255 } else { value as u8 }
+}
+
+// Two's complement, which is how the gpu reads an i8
+const fn snorm(value: i32) -> u8 {
+    let value = if value < -127 { -127 } else if value > 127 { 127 } else { value };
+    let [low, ..] = value.to_le_bytes();
+
+    low
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |value, &byte| value << 8 | u64::from(byte))
+}
+
+fn be_u64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |value, &byte| value << 8 | u64::from(byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A single 4x4 block, decoded texels a row at a time
+    fn decode_block(format: TextureFormat, block: &[u8]) -> (TextureFormat, Vec<[u8; 4]>) {
+        let image = GpuImage { format, width: 4, height: 4, levels: vec![block.to_vec()] };
+        let decoded = decode(&image).unwrap();
+        let texels = decoded.levels[0].chunks_exact(4).map(|texel| [texel[0], texel[1], texel[2], texel[3]]).collect();
+
+        (decoded.format, texels)
+    }
+
+    fn texels(format: TextureFormat, block: &[u8]) -> Vec<[u8; 4]> {
+        decode_block(format, block).1
+    }
+
+    // Every row the same
+    fn rows(row: [[u8; 4]; 4]) -> Vec<[u8; 4]> {
+        row.repeat(4)
+    }
+
+    // Fields from the low bits up, the way bc7 reads them
+    fn bc7_block(fields: &[(u32, u32)]) -> Vec<u8> {
+        let (block, length) = fields
+            .iter()
+            .fold((0_u128, 0), |(block, shift), &(count, value)| (block | u128::from(value) << shift, shift + count));
+
+        assert_eq!(length, 128);
+
+        block.to_le_bytes().to_vec()
+    }
+
+    // Signed texels as the bytes the gpu would read
+    fn signed(values: [i8; 4]) -> [u8; 4] {
+        values.map(|value| value.to_le_bytes()[0])
+    }
+
+    // Indices 0, 1, 2 and 3 across each row
+    const BC1_INDICES: [u8; 4] = [0xe4; 4];
+
+    // Indices 0 to 7 for the first two rows, 0 for the rest
+    const CHANNEL_INDICES: [u8; 6] = [0x88, 0xc6, 0xfa, 0, 0, 0];
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const WHITE: [u8; 2] = [0xff, 0xff];
+
+    #[test]
+    fn bc1_four_colors() {
+        let block = [[0x00, 0xf8, 0x1f, 0x00].as_slice(), &BC1_INDICES].concat();
+
+        let (format, texels) = decode_block(TextureFormat::Bc1RgbaUnormSrgb, &block);
+
+        assert_eq!(format, TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(texels, rows([RED, BLUE, [170, 0, 85, 255], [85, 0, 170, 255]]));
+    }
+
+    #[test]
+    fn bc1_three_colors_and_transparent() {
+        let block = [[0x1f, 0x00, 0x00, 0xf8].as_slice(), &BC1_INDICES].concat();
+
+        let (format, texels) = decode_block(TextureFormat::Bc1RgbaUnorm, &block);
+
+        assert_eq!(format, TextureFormat::Rgba8Unorm);
+        assert_eq!(texels, rows([BLUE, RED, [127, 0, 127, 255], [0; 4]]));
+    }
+
+    #[test]
+    fn bc2_alpha() {
+        let alpha = [0x10, 0x32, 0x54, 0x76, 0x98, 0xba, 0xdc, 0xfe];
+        let block = [alpha.as_slice(), &WHITE, &[0; 6]].concat();
+
+        let expected = (0..16).map(|texel| [255, 255, 255, texel * 17]).collect::<Vec<_>>();
+
+        assert_eq!(texels(TextureFormat::Bc2RgbaUnorm, &block), expected);
+    }
+
+    #[test]
+    fn bc3_alpha() {
+        let block = [[255, 0].as_slice(), &CHANNEL_INDICES, &WHITE, &[0; 6]].concat();
+
+        let alpha = [255, 0, 218, 182, 145, 109, 72, 36].into_iter().chain([255; 8]);
+        let expected = alpha.map(|alpha| [255, 255, 255, alpha]).collect::<Vec<_>>();
+
+        assert_eq!(texels(TextureFormat::Bc3RgbaUnorm, &block), expected);
+    }
+
+    #[test]
+    fn bc4_unsigned() {
+        let block = [[0, 255].as_slice(), &CHANNEL_INDICES].concat();
+
+        // Four steps between the endpoints, then black and white
+        let red = [0, 255, 51, 102, 153, 204, 0, 255].into_iter().chain([0; 8]);
+        let expected = red.map(|red| [red, 0, 0, 255]).collect::<Vec<_>>();
+
+        assert_eq!(texels(TextureFormat::Bc4RUnorm, &block), expected);
+    }
+
+    #[test]
+    fn bc4_signed() {
+        let six_steps = [[0x7f, 0x81].as_slice(), &CHANNEL_INDICES].concat();
+        let four_steps = [[0x80, 0x00].as_slice(), &CHANNEL_INDICES].concat();
+
+        let (format, decoded) = decode_block(TextureFormat::Bc4RSnorm, &six_steps);
+        let red = [127, -127, 90, 54, 18, -18, -54, -90].into_iter().chain([127; 8]);
+
+        assert_eq!(format, TextureFormat::Rgba8Snorm);
+        assert_eq!(decoded, red.map(|red| signed([red, 0, 0, 127])).collect::<Vec<_>>());
+
+        // -128 is read as -127
+        let red = [-127, 0, -101, -76, -50, -25, -127, 127].into_iter().chain([-127; 8]);
+        let expected = red.map(|red| signed([red, 0, 0, 127])).collect::<Vec<_>>();
+
+        assert_eq!(texels(TextureFormat::Bc4RSnorm, &four_steps), expected);
+    }
+
+    #[test]
+    fn bc5_signed() {
+        let block = [[0x7f, 0x00].as_slice(), &[0; 6], &[0x81, 0x00], &[0; 6]].concat();
+
+        assert_eq!(texels(TextureFormat::Bc5RgSnorm, &block), vec![signed([127, -127, 0, 127]); 16]);
+    }
+
+    #[test]
+    fn bc7_single_subset() {
+        // Mode 6, the first endpoint's p-bit is set
+        let block = bc7_block(&[
+            (7, 1 << 6),
+            (7, 127), (7, 0),
+            (7, 0), (7, 0),
+            (7, 0), (7, 127),
+            (7, 127), (7, 63),
+            (1, 1), (1, 0),
+            (3, 0),
+            (4, 1), (4, 2), (4, 3), (4, 4), (4, 5), (4, 6), (4, 7),
+            (4, 8), (4, 9), (4, 10), (4, 11), (4, 12), (4, 13), (4, 14), (4, 15),
+        ]);
+
+        let texels = texels(TextureFormat::Bc7RgbaUnorm, &block);
+
+        assert_eq!(texels[0], [255, 1, 1, 255]);
+        assert_eq!(texels[1], [239, 1, 17, 247]);
+        assert_eq!(texels[8], [120, 0, 135, 186]);
+        assert_eq!(texels[15], [0, 0, 254, 126]);
+    }
+
+    #[test]
+    fn bc7_two_subsets() {
+        // Mode 1 with the bottom two rows in the second subset, whose anchor is the last texel
+        let block = bc7_block(&[
+            (2, 1 << 1),
+            (6, 13),
+            (6, 0), (6, 63), (6, 63), (6, 63),
+            (6, 0), (6, 63), (6, 0), (6, 0),
+            (6, 0), (6, 63), (6, 0), (6, 0),
+            (1, 0), (1, 1),
+            (2, 0), (3, 1), (3, 2), (3, 3), (3, 4), (3, 5), (3, 6), (3, 7),
+            (3, 0), (3, 0), (3, 0), (3, 0), (3, 0), (3, 0), (3, 0), (2, 0),
+        ]);
+
+        let gray = [0, 36, 71, 107, 146, 182, 217, 253].map(|value| [value, value, value, 255]);
+        let expected = gray.into_iter().chain([[255, 2, 2, 255]; 8]).collect::<Vec<_>>();
+
+        assert_eq!(texels(TextureFormat::Bc7RgbaUnorm, &block), expected);
+    }
+
+    #[test]
+    fn bc7_rotation() {
+        // Mode 5 with red where alpha goes
+        let block = bc7_block(&[
+            (6, 1 << 5),
+            (2, 1),
+            (7, 127), (7, 127), (7, 0), (7, 0), (7, 0), (7, 0),
+            (8, 0), (8, 0),
+            (31, 0), (31, 0),
+        ]);
+
+        assert_eq!(texels(TextureFormat::Bc7RgbaUnormSrgb, &block), vec![[0, 0, 0, 255]; 16]);
+    }
+
+    #[test]
+    fn bc7_reserved_mode() {
+        assert_eq!(texels(TextureFormat::Bc7RgbaUnorm, &[0; 16]), vec![[0; 4]; 16]);
+    }
+
+    #[test]
+    fn bc7_anchors_are_in_their_subsets() {
+        for partition in 0..64 {
+            let two = BC7_PARTITIONS_2[partition];
+            let three = BC7_PARTITIONS_3[partition];
+            let [second, third] = BC7_ANCHORS_3[partition];
+
+            assert_eq!(two & 1, 0, "partition {partition}");
+            assert_eq!(two >> BC7_ANCHORS_2[partition] & 1, 1, "partition {partition}");
+            assert_eq!(three & 3, 0, "partition {partition}");
+            assert_eq!(three >> (second * 2) & 3, 1, "partition {partition}");
+            assert_eq!(three >> (third * 2) & 3, 2, "partition {partition}");
+        }
+    }
+
+    // Individual mode, red on the left and blue on the right, with the top left texel taking the third
+    // modifier and the bottom right one the fourth
+    const ETC2_INDIVIDUAL: [u8; 8] = [0xf0, 0x00, 0x0f, 0x1c, 0x80, 0x01, 0x80, 0x00];
+
+    #[test]
+    fn etc2_individual() {
+        let mut expected = rows([[255, 2, 2, 255], [255, 2, 2, 255], [47, 47, 255, 255], [47, 47, 255, 255]]);
+
+        expected[0] = [253, 0, 0, 255];
+        expected[15] = [0, 0, 72, 255];
+
+        assert_eq!(texels(TextureFormat::Etc2Rgb8Unorm, &ETC2_INDIVIDUAL), expected);
+    }
+
+    #[test]
+    fn etc2_alpha() {
+        // Every texel takes the first modifier but the one at (1, 0), which takes the fifth
+        let alpha = [0x80, 0x10, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00];
+        let block = [alpha.as_slice(), &ETC2_INDIVIDUAL].concat();
+
+        let texels = texels(TextureFormat::Etc2Rgba8UnormSrgb, &block);
+
+        assert_eq!(texels[0], [253, 0, 0, 125]);
+        assert_eq!(texels[1], [255, 2, 2, 130]);
+        assert_eq!(texels[15], [0, 0, 72, 125]);
+    }
+
+    #[test]
+    fn etc2_punchthrough() {
+        // Differential, gray in both halves. Across the top row the texels take each modifier in turn
+        let transparent = [0x80, 0x80, 0x80, 0x00, 0x11, 0x00, 0x10, 0x10];
+        let opaque = [0x80, 0x80, 0x80, 0x02, 0x11, 0x00, 0x10, 0x10];
+
+        let gray = |value| [value, value, value, 255];
+
+        let mut expected = vec![gray(132); 16];
+
+        expected[..4].copy_from_slice(&[gray(132), gray(140), [0; 4], gray(124)]);
+
+        assert_eq!(texels(TextureFormat::Etc2Rgb8A1Unorm, &transparent), expected);
+
+        // Opaque blocks have the usual modifiers
+        let mut expected = vec![gray(134); 16];
+
+        expected[..4].copy_from_slice(&[gray(134), gray(140), gray(130), gray(124)]);
+
+        assert_eq!(texels(TextureFormat::Etc2Rgb8A1UnormSrgb, &opaque), expected);
+        assert_eq!(texels(TextureFormat::Etc2Rgb8Unorm, &opaque), expected);
+    }
+
+    #[test]
+    fn eac_unsigned() {
+        let block = [0x80, 0x10, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00];
+
+        let mut expected = vec![[125, 0, 0, 255]; 16];
+
+        expected[1] = [130, 0, 0, 255];
+
+        assert_eq!(texels(TextureFormat::EacR11Unorm, &block), expected);
+    }
+
+    #[test]
+    fn eac_signed() {
+        let block = [0x40, 0x10, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x10, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00];
+
+        let (format, texels) = decode_block(TextureFormat::EacRg11Snorm, &block);
+        let mut expected = vec![signed([61, -67, 0, 127]); 16];
+
+        expected[1] = signed([66, -62, 0, 127]);
+
+        assert_eq!(format, TextureFormat::Rgba8Snorm);
+        assert_eq!(texels, expected);
+    }
+
+    // Half float texels of a single 4x4 block
+    fn halves(format: TextureFormat, block: &[u8]) -> (TextureFormat, Vec<[u16; 4]>) {
+        let image = GpuImage { format, width: 4, height: 4, levels: vec![block.to_vec()] };
+        let decoded = decode(&image).unwrap();
+        let texels = decoded.levels[0]
+            .chunks_exact(8)
+            .map(|texel| std::array::from_fn(|channel| u16::from_le_bytes([texel[channel * 2], texel[channel * 2 + 1]])))
+            .collect();
+
+        (decoded.format, texels)
+    }
+
+    const HALF_BLACK: [u16; 4] = [0, 0, 0, HALF_ONE];
+
+    #[test]
+    fn bc6h_one_region() {
+        let mut fields = vec![(5, 0b00011), (10, 495), (10, 495), (10, 495), (10, 0), (10, 0), (10, 0), (3, 0), (4, 8)];
+
+        fields.extend([(4, 15); 14]);
+
+        let (format, texels) = halves(TextureFormat::Bc6hRgbUfloat, &bc7_block(&fields));
+        let mut expected = vec![HALF_BLACK; 16];
+
+        expected[0] = [HALF_ONE; 4];
+        expected[1] = [0x1c20, 0x1c20, 0x1c20, HALF_ONE];
+
+        assert_eq!(format, TextureFormat::Rgba16Float);
+        assert_eq!(texels, expected);
+    }
+
+    #[test]
+    fn bc6h_two_regions() {
+        // Mode 0, with only the red of the second region's first endpoint set
+        let fields = [
+            (2, 0), (1, 0), (1, 0), (1, 0), (30, 0), (5, 0), (1, 0), (4, 0), (5, 0), (1, 0), (4, 0), (5, 0), (1, 0),
+            (4, 0), (5, 15), (1, 0), (5, 0), (1, 0), (5, 0), (46, 0),
+        ];
+
+        let (_, texels) = halves(TextureFormat::Bc6hRgbUfloat, &bc7_block(&fields));
+
+        assert_eq!(texels, [HALF_BLACK, HALF_BLACK, [0x1e0, 0, 0, HALF_ONE], [0x1e0, 0, 0, HALF_ONE]].repeat(4));
+    }
+
+    #[test]
+    fn bc6h_reversed_bits_and_deltas() {
+        // Mode 13, 0x4000 in red with a delta of -8
+        let mut fields = vec![
+            (5, 0b01111), (10, 0), (10, 0), (10, 0), (4, 8), (1, 0), (1, 1), (4, 0), (4, 0), (6, 0), (4, 0), (6, 0),
+            (3, 0),
+        ];
+
+        fields.extend([(4, 15); 15]);
+
+        let block = bc7_block(&fields);
+
+        let (_, texels) = halves(TextureFormat::Bc6hRgbUfloat, &block);
+        let mut expected = vec![[0x1efc, 0, 0, HALF_ONE]; 16];
+
+        expected[0] = [0x1f00, 0, 0, HALF_ONE];
+
+        assert_eq!(texels, expected);
+    }
+
+    #[test]
+    fn bc6h_signed() {
+        let fields = [(5, 0b00011), (10, 0x200), (10, 0), (10, 0), (10, 0), (10, 0), (10, 0), (63, 0)];
+
+        let (_, texels) = halves(TextureFormat::Bc6hRgbSfloat, &bc7_block(&fields));
+
+        assert_eq!(texels, vec![[0xfbff, 0, 0, HALF_ONE]; 16]);
+    }
+
+    #[test]
+    fn bc6h_reserved_mode() {
+        let (_, texels) = halves(TextureFormat::Bc6hRgbUfloat, &[[0x13].as_slice(), &[0xff; 15]].concat());
+
+        assert_eq!(texels, vec![HALF_BLACK; 16]);
+    }
+
+    #[test]
+    fn astc_blocks_are_their_footprint() {
+        // A void extent block, orange
+        let block = 0xffff_0000_8000_ffff_ffff_ffff_ffff_fdfc_u128.to_le_bytes().to_vec();
+        let format = TextureFormat::Astc { block: wgpu::AstcBlock::B6x6, channel: AstcChannel::Unorm };
+        let image = GpuImage { format, width: 5, height: 3, levels: vec![block] };
+
+        let decoded = decode(&image).unwrap();
+
+        assert_eq!(decoded.format, TextureFormat::Rgba8Unorm);
+        assert_eq!(decoded.levels[0], [255, 128, 0, 255].repeat(5 * 3));
+    }
+
+    #[test]
+    fn edge_blocks_are_cropped() {
+        let block = [[0x00, 0xf8, 0x1f, 0x00].as_slice(), &BC1_INDICES].concat();
+        let image = GpuImage { format: TextureFormat::Bc1RgbaUnorm, width: 3, height: 2, levels: vec![block] };
+
+        let decoded = decode(&image).unwrap();
+
+        assert_eq!(decoded.levels[0].len(), 3 * 2 * 4);
+        assert_eq!(&decoded.levels[0][..12], [RED, BLUE, [170, 0, 85, 255]].concat());
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::{ensure, Context};
+use ktx2::{Format, Reader};
+use wgpu::{AstcBlock, AstcChannel, TextureFormat};
+
+use crate::compressed::GpuImage;
+
+pub const MAGIC: [u8; 12] = [0xab, 0x4b, 0x54, 0x58, 0x20, 0x32, 0x30, 0xbb, 0x0d, 0x0a, 0x1a, 0x0a];
+
+// The level index follows the header, an offset, a length and an uncompressed length for each level
+const HEADER_LENGTH: usize = 80;
+const LEVEL_LENGTH: usize = 24;
+
+pub fn read(data: &[u8]) -> anyhow::Result<GpuImage> {
+    let reader = Reader::new(data)?;
+    let header = reader.header();
+
+    ensure!(header.supercompression_scheme.is_none(), "supercompressed ktx2 files aren't supported");
+    ensure!(
+        header.pixel_depth <= 1 && header.layer_count <= 1 && header.face_count == 1,
+        "only 2d textures are supported",
+    );
+
+    // Basis universal files leave the format out, they'd have to be transcoded first
+    let format = header.format.context("ktx2 files without a format aren't supported")?;
+    let format = texture_format(format).with_context(|| format!("{format:?} isn't supported"))?;
+
+    let levels = (0..header.level_count.max(1) as usize)
+        .map(|index| level(data, index).map(<[u8]>::to_vec).context("the file is cut short"))
+        .collect::<anyhow::Result<_>>()?;
+
+    GpuImage::new(format, header.pixel_width, header.pixel_height, levels)
+}
+
+// Reader::levels panics on a level that runs past the end of the file, so they're looked up here
+fn level(data: &[u8], index: usize) -> Option<&[u8]> {
+    let read = |at: usize| usize::try_from(u64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?)).ok();
+    let entry = HEADER_LENGTH + index * LEVEL_LENGTH;
+    let (offset, length) = (read(entry)?, read(entry + 8)?);
+
+    data.get(offset..offset.checked_add(length)?)
+}
+
+fn texture_format(format: Format) -> Option<TextureFormat> {
+    let astc = |block, channel| TextureFormat::Astc { block, channel };
+
+    Some(match format {
+        Format::R8G8B8A8_UNORM => TextureFormat::Rgba8Unorm,
+        Format::R8G8B8A8_SRGB => TextureFormat::Rgba8UnormSrgb,
+        Format::B8G8R8A8_UNORM => TextureFormat::Bgra8Unorm,
+        Format::B8G8R8A8_SRGB => TextureFormat::Bgra8UnormSrgb,
+        Format::BC1_RGB_UNORM_BLOCK | Format::BC1_RGBA_UNORM_BLOCK => TextureFormat::Bc1RgbaUnorm,
+        Format::BC1_RGB_SRGB_BLOCK | Format::BC1_RGBA_SRGB_BLOCK => TextureFormat::Bc1RgbaUnormSrgb,
+        Format::BC2_UNORM_BLOCK => TextureFormat::Bc2RgbaUnorm,
+        Format::BC2_SRGB_BLOCK => TextureFormat::Bc2RgbaUnormSrgb,
+        Format::BC3_UNORM_BLOCK => TextureFormat::Bc3RgbaUnorm,
+        Format::BC3_SRGB_BLOCK => TextureFormat::Bc3RgbaUnormSrgb,
+        Format::BC4_UNORM_BLOCK => TextureFormat::Bc4RUnorm,
+        Format::BC4_SNORM_BLOCK => TextureFormat::Bc4RSnorm,
+        Format::BC5_UNORM_BLOCK => TextureFormat::Bc5RgUnorm,
+        Format::BC5_SNORM_BLOCK => TextureFormat::Bc5RgSnorm,
+        Format::BC6H_UFLOAT_BLOCK => TextureFormat::Bc6hRgbUfloat,
+        Format::BC6H_SFLOAT_BLOCK => TextureFormat::Bc6hRgbSfloat,
+        Format::BC7_UNORM_BLOCK => TextureFormat::Bc7RgbaUnorm,
+        Format::BC7_SRGB_BLOCK => TextureFormat::Bc7RgbaUnormSrgb,
+        Format::ETC2_R8G8B8_UNORM_BLOCK => TextureFormat::Etc2Rgb8Unorm,
+        Format::ETC2_R8G8B8_SRGB_BLOCK => TextureFormat::Etc2Rgb8UnormSrgb,
+        Format::ETC2_R8G8B8A1_UNORM_BLOCK => TextureFormat::Etc2Rgb8A1Unorm,
+        Format::ETC2_R8G8B8A1_SRGB_BLOCK => TextureFormat::Etc2Rgb8A1UnormSrgb,
+        Format::ETC2_R8G8B8A8_UNORM_BLOCK => TextureFormat::Etc2Rgba8Unorm,
+        Format::ETC2_R8G8B8A8_SRGB_BLOCK => TextureFormat::Etc2Rgba8UnormSrgb,
+        Format::EAC_R11_UNORM_BLOCK => TextureFormat::EacR11Unorm,
+        Format::EAC_R11_SNORM_BLOCK => TextureFormat::EacR11Snorm,
+        Format::EAC_R11G11_UNORM_BLOCK => TextureFormat::EacRg11Unorm,
+        Format::EAC_R11G11_SNORM_BLOCK => TextureFormat::EacRg11Snorm,
+        Format::ASTC_4x4_UNORM_BLOCK => astc(AstcBlock::B4x4, AstcChannel::Unorm),
+        Format::ASTC_4x4_SRGB_BLOCK => astc(AstcBlock::B4x4, AstcChannel::UnormSrgb),
+        Format::ASTC_5x4_UNORM_BLOCK => astc(AstcBlock::B5x4, AstcChannel::Unorm),
+        Format::ASTC_5x4_SRGB_BLOCK => astc(AstcBlock::B5x4, AstcChannel::UnormSrgb),
+        Format::ASTC_5x5_UNORM_BLOCK => astc(AstcBlock::B5x5, AstcChannel::Unorm),
+        Format::ASTC_5x5_SRGB_BLOCK => astc(AstcBlock::B5x5, AstcChannel::UnormSrgb),
+        Format::ASTC_6x5_UNORM_BLOCK => astc(AstcBlock::B6x5, AstcChannel::Unorm),
+        Format::ASTC_6x5_SRGB_BLOCK => astc(AstcBlock::B6x5, AstcChannel::UnormSrgb),
+        Format::ASTC_6x6_UNORM_BLOCK => astc(AstcBlock::B6x6, AstcChannel::Unorm),
+        Format::ASTC_6x6_SRGB_BLOCK => astc(AstcBlock::B6x6, AstcChannel::UnormSrgb),
+        Format::ASTC_8x5_UNORM_BLOCK => astc(AstcBlock::B8x5, AstcChannel::Unorm),
+        Format::ASTC_8x5_SRGB_BLOCK => astc(AstcBlock::B8x5, AstcChannel::UnormSrgb),
+        Format::ASTC_8x6_UNORM_BLOCK => astc(AstcBlock::B8x6, AstcChannel::Unorm),
+        Format::ASTC_8x6_SRGB_BLOCK => astc(AstcBlock::B8x6, AstcChannel::UnormSrgb),
+        Format::ASTC_8x8_UNORM_BLOCK => astc(AstcBlock::B8x8, AstcChannel::Unorm),
+        Format::ASTC_8x8_SRGB_BLOCK => astc(AstcBlock::B8x8, AstcChannel::UnormSrgb),
+        Format::ASTC_10x5_UNORM_BLOCK => astc(AstcBlock::B10x5, AstcChannel::Unorm),
+        Format::ASTC_10x5_SRGB_BLOCK => astc(AstcBlock::B10x5, AstcChannel::UnormSrgb),
+        Format::ASTC_10x6_UNORM_BLOCK => astc(AstcBlock::B10x6, AstcChannel::Unorm),
+        Format::ASTC_10x6_SRGB_BLOCK => astc(AstcBlock::B10x6, AstcChannel::UnormSrgb),
+        Format::ASTC_10x8_UNORM_BLOCK => astc(AstcBlock::B10x8, AstcChannel::Unorm),
+        Format::ASTC_10x8_SRGB_BLOCK => astc(AstcBlock::B10x8, AstcChannel::UnormSrgb),
+        Format::ASTC_10x10_UNORM_BLOCK => astc(AstcBlock::B10x10, AstcChannel::Unorm),
+        Format::ASTC_10x10_SRGB_BLOCK => astc(AstcBlock::B10x10, AstcChannel::UnormSrgb),
+        Format::ASTC_12x10_UNORM_BLOCK => astc(AstcBlock::B12x10, AstcChannel::Unorm),
+        Format::ASTC_12x10_SRGB_BLOCK => astc(AstcBlock::B12x10, AstcChannel::UnormSrgb),
+        Format::ASTC_12x12_UNORM_BLOCK => astc(AstcBlock::B12x12, AstcChannel::Unorm),
+        Format::ASTC_12x12_SRGB_BLOCK => astc(AstcBlock::B12x12, AstcChannel::UnormSrgb),
+        _ => return None,
+    })
+}
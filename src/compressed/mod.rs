@@ -0,0 +1,84 @@
+use anyhow::ensure;
+use wgpu::{Extent3d, Features, TextureFormat};
+
+use crate::models::ColorSpace;
+
+mod astc;
+mod dds;
+mod decode;
+mod ktx;
+
+// What's asked of the device when the adapter has it, anything it doesn't have is decoded by read_image
+pub const FEATURES: Features = Features::TEXTURE_COMPRESSION_BC
+    .union(Features::TEXTURE_COMPRESSION_ETC2)
+    .union(Features::TEXTURE_COMPRESSION_ASTC_LDR);
+
+// Pixels already in a wgpu format, with every mip level the file has, largest first
+pub struct GpuImage {
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl GpuImage {
+    // Checked here since a level of the wrong size would only fail on the gpu
+    fn new(format: TextureFormat, width: u32, height: u32, levels: Vec<Vec<u8>>) -> anyhow::Result<Self> {
+        ensure!(width > 0 && height > 0, "it's {width}x{height}");
+
+        let max_levels = 32 - width.max(height).leading_zeros();
+
+        ensure!(
+            !levels.is_empty() && levels.len() <= max_levels as usize,
+            "{} mip levels don't fit {width}x{height}", levels.len(),
+        );
+
+        let image = Self { format, width, height, levels };
+
+        for (level, data) in (0..).zip(&image.levels) {
+            let length = image.level_length(level);
+
+            ensure!(data.len() == length, "mip level {level} is {} bytes, {format:?} takes {length}", data.len());
+        }
+
+        Ok(image)
+    }
+
+    #[inline]
+    pub fn level_size(&self, level: u32) -> Extent3d {
+        Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 }.mip_level_size(level, false)
+    }
+
+    // Compressed levels are padded out to whole blocks
+    fn level_length(&self, level: u32) -> usize {
+        let size = self.level_size(level);
+        let info = self.format.describe();
+        let (block_width, block_height) = info.block_dimensions;
+
+        (size.width as usize).div_ceil(usize::from(block_width))
+            * (size.height as usize).div_ceil(usize::from(block_height))
+            * usize::from(info.block_size)
+    }
+}
+
+#[inline]
+pub fn is_compressed(data: &[u8]) -> bool {
+    data.starts_with(&ktx::MAGIC) || data.starts_with(&dds::MAGIC)
+}
+
+// Ktx2 or dds. Formats the device can't sample are decoded to rgba8, or half floats for bc6h, mip levels and all,
+// which is also what happens when the size isn't a whole number of blocks since wgpu won't take those compressed
+pub fn read_image(data: &[u8], color_space: ColorSpace, features: Features) -> anyhow::Result<GpuImage> {
+    let image = if data.starts_with(&ktx::MAGIC) { ktx::read(data)? } else { dds::read(data, color_space)? };
+    let info = image.format.describe();
+    let (block_width, block_height) = info.block_dimensions;
+    let whole_blocks = image.width % u32::from(block_width) == 0 && image.height % u32::from(block_height) == 0;
+
+    if features.contains(info.required_features) && whole_blocks {
+        Ok(image)
+    } else {
+        debug!("decoding {:?} {}x{}, it can't be sampled as it is", image.format, image.width, image.height);
+
+        decode::decode(&image)
+    }
+}
@@ -0,0 +1,56 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use wgpu::RequestDeviceError;
+use winit::error::OsError;
+
+use crate::resources::NotFound;
+
+// Everything that can stop the app from starting, in place of panics
+#[derive(Debug)]
+pub enum InitError {
+    Window(OsError),
+    // Says what was looked for, and what's available when an adapter was selected
+    NoAdapter(String),
+    RequestDevice(RequestDeviceError),
+    // Holds the adapter's name
+    UnsupportedSurface(String),
+    AssetNotFound { file_name: String, source: anyhow::Error },
+    AssetParse { file_name: String, source: anyhow::Error },
+}
+
+impl InitError {
+    // Loaders mark files they couldn't fetch, anything else went wrong reading them
+    pub(crate) fn asset(file_name: &str, source: anyhow::Error) -> Self {
+        match source.downcast_ref::<NotFound>() {
+            Some(NotFound(missing)) => Self::AssetNotFound { file_name: missing.clone(), source },
+            None => Self::AssetParse { file_name: file_name.to_owned(), source },
+        }
+    }
+}
+
+impl Display for InitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Window(_) => write!(f, "couldn't create a window"),
+            Self::NoAdapter(message) => write!(f, "{message}"),
+            Self::RequestDevice(_) => write!(f, "couldn't get a device from the adapter"),
+            Self::UnsupportedSurface(adapter) => write!(f, "{adapter} can't present to this window"),
+            Self::AssetNotFound { file_name, .. } => write!(f, "couldn't find {file_name}"),
+            Self::AssetParse { file_name, .. } => write!(f, "couldn't load {file_name}"),
+        }
+    }
+}
+
+impl Error for InitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Window(err) => Some(err),
+            Self::RequestDevice(err) => Some(err),
+            // The outermost error is the NotFound marker, which says the same as this
+            Self::AssetNotFound { source, .. } => source.source(),
+            Self::AssetParse { source, .. } => Some(source.as_ref()),
+            Self::NoAdapter(_) | Self::UnsupportedSurface(_) => None,
+        }
+    }
+}
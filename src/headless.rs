@@ -0,0 +1,44 @@
+use std::fs;
+
+use instant::Duration;
+use winit::dpi::PhysicalSize;
+
+use crate::{Headless, Options, State};
+
+// Frames are a fixed 60th of a second apart, so a batch renders the same however fast it runs
+const FRAME_TIME: Duration = Duration::from_nanos(16_666_667);
+
+#[allow(clippy::future_not_send)] // todo: the gamepad source is not send
+pub async fn run_headless(options: &Options, headless: &Headless) -> anyhow::Result<()> {
+    let (width, height) = headless.size;
+    let mut state = State::new_headless(options, PhysicalSize::new(width, height)).await?;
+
+    state.wait_for_loads()?;
+
+    fs::create_dir_all(&headless.output)?;
+
+    for frame in 0..headless.frames {
+        let _frame = info_span!("frame", frame).entered();
+
+        if options.lose_device_at == Some(frame) {
+            state.simulate_device_loss();
+        }
+
+        if state.device_lost() {
+            state.recover().await?;
+            state.wait_for_loads()?;
+        }
+
+        state.update(FRAME_TIME);
+        state.render()?;
+
+        let path = headless.output.join(format!("frame-{frame:05}.png"));
+
+        state.capture()?.save(&path)?;
+        debug!("wrote {}", path.display());
+    }
+
+    info!("wrote {} frames to {}", headless.frames, headless.output.display());
+
+    Ok(())
+}
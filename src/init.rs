@@ -1,10 +1,13 @@
+use winit::dpi::PhysicalSize;
 use winit::event_loop::EventLoop;
-use winit::window::{Window, WindowBuilder};
+use winit::window::{Fullscreen, Window, WindowBuilder};
+
+use crate::error::InitError;
+use crate::Options;
 
 #[cfg(target_arch = "wasm32")]
 pub fn initialize_canvas(window: &Window) {
     use web_sys::Element;
-    use winit::dpi::PhysicalSize;
     use winit::platform::web::WindowExtWebSys;
 
     // Winit prevents sizing with CSS, so we have to set
@@ -24,23 +27,113 @@ pub fn initialize_canvas(window: &Window) {
         .expect("Couldn't append canvas to document body.");
 }
 
-pub fn initialize_environment() -> (EventLoop<()>, Window) {
+// Replaces the canvas, or where it would have gone, with the message
+#[cfg(target_arch = "wasm32")]
+pub fn show_error(message: &str) {
+    let Some(document) = web_sys::window().and_then(|win| win.document()) else {
+        return;
+    };
+
+    let (Some(container), Ok(element)) = (document.get_element_by_id("lean-wgpu"), document.create_element("pre")) else {
+        return;
+    };
+
+    element.set_class_name("error");
+    element.set_text_content(Some(message));
+    container.set_text_content(None);
+
+    // Nothing more can be done if this fails, the message is in the console too
+    let _ = container.append_child(&element);
+}
+
+pub fn initialize_environment(options: &Options) -> Result<(EventLoop<()>, Window), InitError> {
     let event_loop = EventLoop::new();
-    let window = WindowBuilder::new()
-        .with_title("learn-wgpu")
-        .build(&event_loop)
-        .unwrap();
+    let mut builder = WindowBuilder::new().with_title("learn-wgpu");
+
+    if let Some((width, height)) = options.window_size {
+        builder = builder.with_inner_size(PhysicalSize::new(width, height));
+    }
+
+    if options.fullscreen {
+        builder = builder.with_fullscreen(Some(Fullscreen::Borderless(None)));
+    }
+
+    let window = builder.build(&event_loop).map_err(InitError::Window)?;
+
+    Ok((event_loop, window))
+}
+
+// Keeps the chrome trace open, it's only complete once this is dropped
+#[must_use]
+pub struct TraceGuard {
+    #[cfg(not(target_arch = "wasm32"))]
+    _chrome: Option<tracing_chrome::FlushGuard>,
+}
 
-    (event_loop, window)
+#[cfg(target_arch = "wasm32")]
+pub fn initialize_logging(options: &Options) -> TraceGuard {
+    use tracing_wasm::WASMLayerConfigBuilder;
+
+    console_error_panic_hook::set_once();
+
+    // The web has no env filter, only a level
+    let level = options.log_filter
+        .as_deref()
+        .and_then(|filter| filter.parse().ok())
+        .unwrap_or(tracing::Level::INFO);
+
+    tracing_wasm::set_as_global_default_with_config(WASMLayerConfigBuilder::new().set_max_level(level).build());
+
+    TraceGuard {}
 }
 
-pub fn initialize_logging() {
-    cfg_if! {
-        if #[cfg(target_arch = "wasm32")] {
-            console_error_panic_hook::set_once();
-            tracing_wasm::set_as_global_default();
-        } else {
-            tracing_subscriber::fmt::init();
+#[cfg(not(target_arch = "wasm32"))]
+pub fn initialize_logging(options: &Options) -> TraceGuard {
+    use std::fs::File;
+
+    use tracing_chrome::ChromeLayerBuilder;
+    use tracing_subscriber::EnvFilter;
+    use tracing_subscriber::prelude::*;
+
+    // Options win over RUST_LOG, which wins over info
+    let filter = options.log_filter.as_deref().map_or_else(
+        || EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new("info")),
+        EnvFilter::try_new,
+    );
+
+    let trace_file = options.trace_file.as_ref().map(|path| (path, File::create(path)));
+
+    let (chrome, guard, trace_error) = match trace_file {
+        Some((_, Ok(file))) => {
+            let (layer, guard) = ChromeLayerBuilder::new().writer(file).include_args(true).build();
+
+            (Some(layer), Some(guard), None)
         }
+        Some((path, Err(err))) => (None, None, Some((path, err))),
+        None => (None, None, None),
+    };
+
+    let (filter, filter_error) = match filter {
+        Ok(filter) => (filter, None),
+        Err(err) => (EnvFilter::new("info"), Some(err)),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(chrome)
+        .init();
+
+    // Problems are reported once there's a subscriber to report them to
+    if let Some(err) = filter_error {
+        warn!("invalid log filter, using info: {err}");
+    }
+
+    if let Some((path, err)) = trace_error {
+        warn!("couldn't create trace file {}: {err}", path.display());
+    } else if let Some(path) = options.trace_file.as_ref() {
+        info!("writing chrome trace to {}", path.display());
     }
+
+    TraceGuard { _chrome: guard }
 }
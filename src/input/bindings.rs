@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::models::ViewMode;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    // Held to rotate the camera with mouse motion
+    Look,
+    Zoom,
+    // Picks the instance under the cursor
+    Select,
+    TranslateGizmo,
+    RotateGizmo,
+    ScaleGizmo,
+    // Held to snap gizmo edits to fixed increments
+    Snap,
+    ToggleFlythrough,
+    ToggleDebugDraw,
+    ToggleStats,
+    ToggleUi,
+    ViewMode(ViewMode),
+    Exit,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Binding {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    Scroll,
+}
+
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct InputBindings {
+    bindings: HashMap<Binding, Action>,
+}
+
+impl InputBindings {
+    #[inline]
+    #[must_use]
+    pub fn action(&self, binding: Binding) -> Option<Action> {
+        self.bindings.get(&binding).copied()
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let bindings = [
+            (Binding::Key(VirtualKeyCode::W), Action::MoveForward),
+            (Binding::Key(VirtualKeyCode::Up), Action::MoveForward),
+            (Binding::Key(VirtualKeyCode::S), Action::MoveBackward),
+            (Binding::Key(VirtualKeyCode::Down), Action::MoveBackward),
+            (Binding::Key(VirtualKeyCode::A), Action::MoveLeft),
+            (Binding::Key(VirtualKeyCode::Left), Action::MoveLeft),
+            (Binding::Key(VirtualKeyCode::D), Action::MoveRight),
+            (Binding::Key(VirtualKeyCode::Right), Action::MoveRight),
+            (Binding::Key(VirtualKeyCode::Q), Action::MoveUp),
+            (Binding::Key(VirtualKeyCode::Space), Action::MoveUp),
+            (Binding::Key(VirtualKeyCode::E), Action::MoveDown),
+            (Binding::Key(VirtualKeyCode::LShift), Action::MoveDown),
+            (Binding::Key(VirtualKeyCode::Key1), Action::TranslateGizmo),
+            (Binding::Key(VirtualKeyCode::Key2), Action::RotateGizmo),
+            (Binding::Key(VirtualKeyCode::Key3), Action::ScaleGizmo),
+            (Binding::Key(VirtualKeyCode::LControl), Action::Snap),
+            (Binding::Key(VirtualKeyCode::P), Action::ToggleFlythrough),
+            (Binding::Key(VirtualKeyCode::F1), Action::ToggleDebugDraw),
+            (Binding::Key(VirtualKeyCode::F2), Action::ViewMode(ViewMode::Lit)),
+            (Binding::Key(VirtualKeyCode::F3), Action::ViewMode(ViewMode::Wireframe)),
+            (Binding::Key(VirtualKeyCode::F4), Action::ViewMode(ViewMode::Normals)),
+            (Binding::Key(VirtualKeyCode::F5), Action::ViewMode(ViewMode::TangentNormals)),
+            (Binding::Key(VirtualKeyCode::F6), Action::ViewMode(ViewMode::Uvs)),
+            (Binding::Key(VirtualKeyCode::F7), Action::ViewMode(ViewMode::Albedo)),
+            (Binding::Key(VirtualKeyCode::F8), Action::ViewMode(ViewMode::Depth)),
+            (Binding::Key(VirtualKeyCode::F9), Action::ViewMode(ViewMode::Overdraw)),
+            (Binding::Key(VirtualKeyCode::F10), Action::ToggleStats),
+            (Binding::Key(VirtualKeyCode::Grave), Action::ToggleUi),
+            (Binding::Key(VirtualKeyCode::Escape), Action::Exit),
+            (Binding::Mouse(MouseButton::Left), Action::Look),
+            (Binding::Mouse(MouseButton::Right), Action::Select),
+            (Binding::Scroll, Action::Zoom),
+        ];
+
+        Self { bindings: bindings.into_iter().collect() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_round_trip() {
+        let defaults = InputBindings::default();
+        let text = ron::to_string(&defaults).unwrap();
+
+        assert_eq!(ron::from_str::<InputBindings>(&text).unwrap(), defaults);
+    }
+
+    #[test]
+    fn shipped_bindings_are_the_defaults() {
+        let shipped: InputBindings = ron::from_str(include_str!("../../res/bindings.ron")).unwrap();
+
+        assert_eq!(shipped, InputBindings::default());
+    }
+
+    #[test]
+    fn bindings_map_keys_buttons_and_scroll() {
+        let bindings: InputBindings = ron::from_str("{ Key(Z): MoveForward, Key(Comma): MoveForward, \
+            Mouse(Middle): Select, Scroll: Zoom, Key(F3): ViewMode(Depth) }").unwrap();
+
+        assert_eq!(bindings.action(Binding::Key(VirtualKeyCode::Z)), Some(Action::MoveForward));
+        assert_eq!(bindings.action(Binding::Key(VirtualKeyCode::Comma)), Some(Action::MoveForward));
+        assert_eq!(bindings.action(Binding::Mouse(MouseButton::Middle)), Some(Action::Select));
+        assert_eq!(bindings.action(Binding::Scroll), Some(Action::Zoom));
+        assert_eq!(bindings.action(Binding::Key(VirtualKeyCode::F3)), Some(Action::ViewMode(ViewMode::Depth)));
+
+        // Only what's in the file is bound, the defaults don't fill in
+        assert_eq!(bindings.action(Binding::Key(VirtualKeyCode::W)), None);
+    }
+
+    #[test]
+    fn defaults_bind_the_usual_keys() {
+        let defaults = InputBindings::default();
+
+        assert_eq!(defaults.action(Binding::Key(VirtualKeyCode::W)), Some(Action::MoveForward));
+        assert_eq!(defaults.action(Binding::Key(VirtualKeyCode::Escape)), Some(Action::Exit));
+        assert_eq!(defaults.action(Binding::Mouse(MouseButton::Left)), Some(Action::Look));
+        assert_eq!(defaults.action(Binding::Key(VirtualKeyCode::Z)), None);
+    }
+
+    #[test]
+    fn unknown_actions_are_refused() {
+        assert!(ron::from_str::<InputBindings>("{ Key(W): Jump }").is_err());
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::VecDeque;
+
+use serde::Deserialize;
+
+use crate::input::Action;
+use crate::models::CameraController;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+// Sticks report -1.0..=1.0 with up and right positive, triggers report 0.0..=1.0
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GamepadEvent {
+    pub axis: GamepadAxis,
+    pub value: f32,
+}
+
+pub trait GamepadSource {
+    fn next_event(&mut self) -> Option<GamepadEvent>;
+}
+
+// Replays queued events, used when no gamepad backend is compiled in and for scripted input
+#[derive(Debug, Default)]
+pub struct SimulatedGamepad {
+    events: VecDeque<GamepadEvent>,
+}
+
+impl SimulatedGamepad {
+    pub fn push(&mut self, axis: GamepadAxis, value: f32) {
+        self.events.push_back(GamepadEvent { axis, value });
+    }
+}
+
+impl<I> From<I> for SimulatedGamepad
+    where I: IntoIterator<Item=GamepadEvent>
+{
+    fn from(events: I) -> Self {
+        Self { events: events.into_iter().collect() }
+    }
+}
+
+impl GamepadSource for SimulatedGamepad {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        self.events.pop_front()
+    }
+}
+
+#[cfg(feature = "gamepad")]
+pub struct GilrsGamepad {
+    gilrs: gilrs::Gilrs,
+}
+
+#[cfg(feature = "gamepad")]
+impl GilrsGamepad {
+    /// # Errors
+    /// When the platform's gamepad backend can't be initialized
+    pub fn new() -> anyhow::Result<Self> {
+        let gilrs = gilrs::Gilrs::new().map_err(|err| anyhow::anyhow!("{err}"))?;
+
+        Ok(Self { gilrs })
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl GamepadSource for GilrsGamepad {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        use gilrs::{Axis, Button, EventType};
+
+        while let Some(event) = self.gilrs.next_event() {
+            let (axis, value) = match event.event {
+                EventType::AxisChanged(Axis::LeftStickX, value, _) => (GamepadAxis::LeftStickX, value),
+                EventType::AxisChanged(Axis::LeftStickY, value, _) => (GamepadAxis::LeftStickY, value),
+                EventType::AxisChanged(Axis::RightStickX, value, _) => (GamepadAxis::RightStickX, value),
+                EventType::AxisChanged(Axis::RightStickY, value, _) => (GamepadAxis::RightStickY, value),
+                // Analog triggers are reported as buttons with a value
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _) => (GamepadAxis::LeftTrigger, value),
+                EventType::ButtonChanged(Button::RightTrigger2, value, _) => (GamepadAxis::RightTrigger, value),
+                _ => continue,
+            };
+
+            return Some(GamepadEvent { axis, value });
+        }
+
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct GamepadSettings {
+    // Stick deflection below this is treated as centered
+    pub dead_zone: f32,
+    // Exponent applied to the deflection past the dead zone, 1.0 is linear
+    pub response_curve: f32,
+    pub move_sensitivity: f32,
+    pub look_sensitivity: f32,
+    pub invert_look: bool,
+}
+
+impl GamepadSettings {
+    fn shape(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+
+        if magnitude <= self.dead_zone {
+            return 0.0;
+        }
+
+        let scaled = ((magnitude - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+
+        scaled.powf(self.response_curve).copysign(value)
+    }
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.15,
+            response_curve: 2.0,
+            move_sensitivity: 1.0,
+            look_sensitivity: 5.0,
+            invert_look: false,
+        }
+    }
+}
+
+pub struct Gamepad {
+    settings: GamepadSettings,
+    source: Box<dyn GamepadSource>,
+    left_stick: (f32, f32),
+    right_stick: (f32, f32),
+    triggers: (f32, f32),
+}
+
+impl Gamepad {
+    #[must_use]
+    pub fn new(settings: GamepadSettings, source: Box<dyn GamepadSource>) -> Self {
+        Self {
+            settings,
+            source,
+            left_stick: (0.0, 0.0),
+            right_stick: (0.0, 0.0),
+            triggers: (0.0, 0.0),
+        }
+    }
+
+    pub fn set_source(&mut self, source: Box<dyn GamepadSource>) {
+        self.source = source;
+    }
+
+    pub fn update(&mut self, controller: &mut CameraController) {
+        let mut moved = false;
+
+        while let Some(GamepadEvent { axis, value }) = self.source.next_event() {
+            let value = self.settings.shape(value);
+
+            match axis {
+                GamepadAxis::LeftStickX => self.left_stick.0 = value,
+                GamepadAxis::LeftStickY => self.left_stick.1 = value,
+                GamepadAxis::RightStickX => self.right_stick.0 = value,
+                GamepadAxis::RightStickY => self.right_stick.1 = value,
+                GamepadAxis::LeftTrigger => self.triggers.0 = value,
+                GamepadAxis::RightTrigger => self.triggers.1 = value,
+            }
+
+            moved |= !matches!(axis, GamepadAxis::RightStickX | GamepadAxis::RightStickY);
+        }
+
+        // Only push movement when the pad changed, otherwise a resting pad would cancel the keyboard
+        if moved {
+            let sensitivity = self.settings.move_sensitivity;
+            let (x, y) = self.left_stick;
+
+            controller.process_axis(Action::MoveRight, x.max(0.0) * sensitivity);
+            controller.process_axis(Action::MoveLeft, (-x).max(0.0) * sensitivity);
+            controller.process_axis(Action::MoveForward, y.max(0.0) * sensitivity);
+            controller.process_axis(Action::MoveBackward, (-y).max(0.0) * sensitivity);
+            controller.process_axis(Action::MoveDown, self.triggers.0 * sensitivity);
+            controller.process_axis(Action::MoveUp, self.triggers.1 * sensitivity);
+        }
+
+        // Looking is a rate rather than a delta, so it is applied every frame the stick is held
+        let (x, y) = self.right_stick;
+
+        if x != 0.0 || y != 0.0 {
+            let sensitivity = self.settings.look_sensitivity;
+            let y = if self.settings.invert_look { y } else { -y };
+
+            controller.process_mouse(f64::from(x * sensitivity), f64::from(y * sensitivity));
+        }
+    }
+}
+
+#[must_use]
+pub fn default_gamepad_source() -> Box<dyn GamepadSource> {
+    #[cfg(feature = "gamepad")]
+    match GilrsGamepad::new() {
+        Ok(gamepad) => return Box::new(gamepad),
+        Err(err) => warn!("gamepad support unavailable: {err}"),
+    }
+
+    Box::<SimulatedGamepad>::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use cgmath::Rad;
+
+    use super::*;
+    use crate::models::Camera;
+
+    // Linear and without a dead zone, so a full deflection is a full step
+    const LINEAR: GamepadSettings = GamepadSettings {
+        dead_zone: 0.0,
+        response_curve: 1.0,
+        move_sensitivity: 1.0,
+        look_sensitivity: 1.0,
+        invert_look: false,
+    };
+
+    fn drive(settings: GamepadSettings, events: &[(GamepadAxis, f32)], frames: u32) -> Camera {
+        let gamepad = SimulatedGamepad::from(events.iter().map(|&(axis, value)| GamepadEvent { axis, value }));
+        let mut gamepad = Gamepad::new(settings, Box::new(gamepad));
+        let mut controller = CameraController::new(1.0, 1.0);
+        let mut camera = Camera::new((0.0, 0.0, 0.0), Rad(0.0), Rad(0.0));
+
+        for _ in 0..frames {
+            gamepad.update(&mut controller);
+            controller.update_camera(&mut camera, Duration::from_secs(1));
+        }
+
+        camera
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn left_stick_moves() {
+        // A yaw of zero looks down +x, process_axis doubles the amount
+        let camera = drive(LINEAR, &[(GamepadAxis::LeftStickY, 1.0), (GamepadAxis::LeftStickX, -0.5)], 1);
+
+        assert!(close(camera.position.x, 2.0), "{:?}", camera.position);
+        assert!(close(camera.position.z, -1.0), "{:?}", camera.position);
+    }
+
+    #[test]
+    fn triggers_move_up_and_down() {
+        let camera = drive(LINEAR, &[(GamepadAxis::RightTrigger, 1.0), (GamepadAxis::LeftTrigger, 0.25)], 1);
+
+        assert!(close(camera.position.y, 1.5), "{:?}", camera.position);
+    }
+
+    #[test]
+    fn dead_zone_ignores_drift() {
+        let camera = drive(GamepadSettings::default(), &[(GamepadAxis::LeftStickY, 0.1), (GamepadAxis::RightStickX, -0.1)], 1);
+
+        assert!(close(camera.position.x, 0.0), "{:?}", camera.position);
+        assert!(close(camera.yaw.0, 0.0), "{:?}", camera.yaw);
+    }
+
+    #[test]
+    fn held_stick_keeps_turning() {
+        let camera = drive(LINEAR, &[(GamepadAxis::RightStickX, 0.5)], 3);
+
+        assert!(close(camera.yaw.0, 1.5), "{:?}", camera.yaw);
+    }
+
+    #[test]
+    fn look_inverts() {
+        let events = [(GamepadAxis::RightStickY, 0.5)];
+
+        let camera = drive(LINEAR, &events, 1);
+        let inverted = drive(GamepadSettings { invert_look: true, ..LINEAR }, &events, 1);
+
+        assert!(close(camera.pitch.0, 0.5), "{:?}", camera.pitch);
+        assert!(close(inverted.pitch.0, -0.5), "{:?}", inverted.pitch);
+    }
+}
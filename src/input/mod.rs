@@ -0,0 +1,10 @@
+pub use bindings::{Action, Binding, InputBindings};
+#[cfg(feature = "gamepad")]
+pub use gamepad::GilrsGamepad;
+pub use gamepad::{
+    default_gamepad_source, Gamepad, GamepadAxis, GamepadEvent, GamepadSettings, GamepadSource,
+    SimulatedGamepad,
+};
+
+mod bindings;
+mod gamepad;
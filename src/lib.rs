@@ -17,8 +17,6 @@ extern crate wgpu;
 use instant::Instant;
 use wgpu::SurfaceError;
 use winit::event::{DeviceEvent, Event, WindowEvent};
-#[cfg(not(target_arch = "wasm32"))]
-use winit::event::{ElementState, KeyboardInput, VirtualKeyCode};
 use winit::event_loop::ControlFlow;
 
 #[cfg(target_arch = "wasm32")]
@@ -26,25 +24,101 @@ use wasm_bindgen::prelude::*;
 
 use crate::init::{initialize_environment, initialize_logging};
 #[cfg(target_arch = "wasm32")]
-use crate::init::initialize_canvas;
-use crate::state::State;
+use crate::init::{initialize_canvas, show_error};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::adapters::list_adapters;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::pack::{pack, PackedTextureFormat, PackOptions};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::headless::run_headless;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::watcher::Watcher;
+pub use crate::error::InitError;
+pub use crate::options::{AdapterSelector, Headless, Options};
+use crate::state::{State, StateEvent};
 
+#[cfg(not(target_arch = "wasm32"))]
+mod adapters;
+mod assets;
+mod baked;
+mod compressed;
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+mod error;
 mod init;
+pub mod input;
+mod loader;
 mod models;
+mod options;
+#[cfg(not(target_arch = "wasm32"))]
+mod pack;
 mod resources;
+mod sources;
 mod state;
+mod ui;
+#[cfg(not(target_arch = "wasm32"))]
+mod watcher;
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
 #[allow(clippy::future_not_send)] // todo: winit event loop is not send
 pub async fn run() {
-    initialize_logging();
-    let (event_loop, window) = initialize_environment();
+    if let Err(err) = run_with_options(Options::default()).await {
+        report(&err);
+    }
+}
+
+// Whether the state could be rebuilt after losing its device
+#[cfg(not(target_arch = "wasm32"))]
+fn recover(state: &mut State) -> bool {
+    pollster::block_on(state.recover())
+        .map_err(|err| error!("couldn't recover from a device loss: {err:#}"))
+        .is_ok()
+}
+
+// The event loop can't wait on the browser, reloading the page starts over
+#[cfg(target_arch = "wasm32")]
+fn recover(_state: &mut State) -> bool {
+    show_error("the gpu device was lost, reload the page to start again");
+    false
+}
+
+// Logged, and shown on the page on the web where nobody's looking at the console
+fn report(err: &anyhow::Error) {
+    error!("{err:#}");
+
+    #[cfg(target_arch = "wasm32")]
+    show_error(&format!("{err:#}"));
+}
+
+/// Runs until the window is closed, or until the frames are written when headless.
+///
+/// # Errors
+///
+/// When anything in [`InitError`] stops it from starting, or a headless frame can't be rendered or saved.
+#[allow(clippy::future_not_send)] // todo: winit event loop is not send
+pub async fn run_with_options(options: Options) -> anyhow::Result<()> {
+    // Dropped when the loop exits, which finishes the trace file
+    let mut trace_guard = Some(initialize_logging(&options));
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(headless) = options.headless.as_ref() {
+        return run_headless(&options, headless).await;
+    }
+
+    let (event_loop, window) = initialize_environment(&options)?;
 
     #[cfg(target_arch = "wasm32")]
     initialize_canvas(&window);
 
-    let mut state = State::new(&window).await;
+    let mut state = State::new(&window, &options).await?;
     let mut last_render_time = Instant::now();
+    let mut frame = 0_u32;
+
+    // Hot reloading is a development aid, everything works without it
+    #[cfg(not(target_arch = "wasm32"))]
+    let watcher = options.watch
+        .then(|| Watcher::new().map_err(|err| error!("not watching for changes: {err}")).ok())
+        .flatten();
 
     event_loop.run(move |event, _, control_flow| {
         match event {
@@ -57,29 +131,43 @@ pub async fn run() {
             Event::WindowEvent {
                 ref event,
                 window_id,
-            } if window_id == window.id() && !state.input(event) => {
-                match event {
-                    #[cfg(not(target_arch = "wasm32"))]
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        input:
-                        KeyboardInput {
-                            state: ElementState::Pressed,
-                            virtual_keycode: Some(VirtualKeyCode::Escape),
-                            ..
-                        },
-                        ..
-                    } => *control_flow = ControlFlow::Exit,
-                    WindowEvent::Resized(physical_size) => {
-                        state.resize(*physical_size);
+            } if window_id == window.id() => {
+                // The ui gets first pick, anything it doesn't use goes to the scene
+                if !state.ui_input(event) && !state.input(event) {
+                    match event {
+                        #[cfg(not(target_arch = "wasm32"))]
+                        WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+                        WindowEvent::Resized(physical_size) => {
+                            state.resize(*physical_size);
+                        }
+                        WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                            state.resize(**new_inner_size);
+                        }
+                        _ => {}
                     }
-                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        state.resize(**new_inner_size);
-                    }
-                    _ => {}
+                }
+
+                // The exit action is bound through the input bindings, see State::input
+                #[cfg(not(target_arch = "wasm32"))]
+                if state.exit_requested() {
+                    *control_flow = ControlFlow::Exit;
                 }
             }
             Event::RedrawRequested(window_id) if window_id == window.id() => {
+                let _frame = info_span!("frame", frame).entered();
+
+                if options.lose_device_at == Some(frame) {
+                    state.simulate_device_loss();
+                }
+
+                frame = frame.wrapping_add(1);
+
+                // Rebuilt before anything touches the device again
+                if state.device_lost() && !recover(&mut state) {
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+
                 let now = Instant::now();
                 let dt = now - last_render_time;
 
@@ -87,6 +175,15 @@ pub async fn run() {
 
                 state.update(dt);
 
+                // The model loads in the background, not getting it fails like State::new would have
+                if let Some(err) = state.take_init_error() {
+                    report(&err.into());
+                    *control_flow = ControlFlow::ExitWithCode(1);
+                    return;
+                }
+
+                state.update_ui(&window);
+
                 match state.render() {
                     Ok(_) => {}
                     // Reconfigure the surface if lost
@@ -97,9 +194,26 @@ pub async fn run() {
                     Err(err) => error!("{:?}", err),
                 }
             }
-            Event::MainEventsCleared =>
-            // RedrawRequested will only trigger once, unless we manually request it.
-                window.request_redraw(),
+            Event::MainEventsCleared => {
+                #[cfg(not(target_arch = "wasm32"))]
+                for change in watcher.iter().flat_map(Watcher::changes) {
+                    state.reload(change);
+                }
+
+                while let Some(event) = state.next_event() {
+                    match event {
+                        StateEvent::Selected(pick) => info!(
+                            "selected model {}, mesh {}, instance {} at {:?}",
+                            pick.model, pick.mesh, pick.instance, pick.position,
+                        ),
+                        StateEvent::SelectionCleared => info!("selection cleared"),
+                    }
+                }
+
+                // RedrawRequested will only trigger once, unless we manually request it.
+                window.request_redraw();
+            }
+            Event::LoopDestroyed => drop(trace_guard.take()),
             _ => {}
         }
     });
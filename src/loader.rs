@@ -0,0 +1,202 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Mutex, PoisonError};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+use wgpu::Features;
+
+use crate::resources::{read_model_data, ModelData};
+use crate::sources::AssetSource;
+
+// Reading is mostly waiting on the disk, a few threads are plenty
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_WORKERS: usize = 4;
+
+// Files read by the loads in flight, starts over when a load is asked for after all of them finished
+#[derive(Debug, Default)]
+pub struct Progress {
+    bytes: AtomicU64,
+    items: AtomicUsize,
+}
+
+impl Progress {
+    // One more file read
+    pub fn add(&self, bytes: usize) {
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.items.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn items(&self) -> usize {
+        self.items.load(Ordering::Relaxed)
+    }
+
+    fn reset(&self) {
+        self.bytes.store(0, Ordering::Relaxed);
+        self.items.store(0, Ordering::Relaxed);
+    }
+}
+
+pub struct Loaded {
+    pub file_name: String,
+    pub result: anyhow::Result<ModelData>,
+}
+
+// Reads and decodes models off the main thread, on a thread pool natively and the browser's event loop on the web.
+// What finished is picked up with Loader::finished, uploading it is left to the state
+pub struct Loader {
+    #[cfg(not(target_arch = "wasm32"))]
+    jobs: Sender<String>,
+    #[cfg(target_arch = "wasm32")]
+    source: Arc<dyn AssetSource>,
+    #[cfg(target_arch = "wasm32")]
+    sender: Sender<Loaded>,
+    #[cfg(target_arch = "wasm32")]
+    features: Features,
+    loaded: Receiver<Loaded>,
+    progress: Arc<Progress>,
+    // Files asked for that haven't been picked up yet
+    pending: Vec<String>,
+}
+
+impl Loader {
+    // The workers stop once the loader is dropped and they're done with what they're reading.
+    // The features are the device's, see read_model_data
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(source: &Arc<dyn AssetSource>, features: Features) -> Self {
+        let (jobs, queue) = channel::<String>();
+        let (sender, loaded) = channel();
+        let queue = Arc::new(Mutex::new(queue));
+        let progress = Arc::new(Progress::default());
+        let workers = thread::available_parallelism().map_or(1, usize::from).min(MAX_WORKERS);
+
+        for index in 0..workers {
+            let (queue, sender, progress) = (Arc::clone(&queue), sender.clone(), Arc::clone(&progress));
+            let source = Arc::clone(source);
+
+            let spawned = thread::Builder::new()
+                .name(format!("loader {index}"))
+                .spawn(move || work(&queue, &sender, source.as_ref(), features, &progress));
+
+            if let Err(err) = spawned {
+                warn!("couldn't start loader thread {index}: {err}");
+            }
+        }
+
+        Self { jobs, loaded, progress, pending: Vec::new() }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn new(source: &Arc<dyn AssetSource>, features: Features) -> Self {
+        let (sender, loaded) = channel();
+
+        Self { source: Arc::clone(source), sender, features, loaded, progress: Arc::default(), pending: Vec::new() }
+    }
+
+    pub fn load_model(&mut self, file_name: &str) {
+        if self.pending.is_empty() {
+            self.progress.reset();
+        }
+
+        self.pending.push(file_name.to_owned());
+
+        cfg_if! {
+            if #[cfg(target_arch = "wasm32")] {
+                let (source, sender, progress) = (Arc::clone(&self.source), self.sender.clone(), Arc::clone(&self.progress));
+                let (file_name, features) = (file_name.to_owned(), self.features);
+
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = read_model_data(source.as_ref(), &file_name, features, &progress).await;
+
+                    // Nobody's waiting for it if the loader is gone
+                    sender.send(Loaded { file_name, result }).ok();
+                });
+            } else {
+                if self.jobs.send(file_name.to_owned()).is_err() {
+                    error!("no loader thread is running, {file_name} won't load");
+                }
+            }
+        }
+    }
+
+    // Whatever finished since the last call, doesn't wait for anything
+    pub fn finished(&mut self) -> Vec<Loaded> {
+        let loaded = self.loaded.try_iter().collect::<Vec<_>>();
+
+        for loaded in &loaded {
+            self.picked_up(&loaded.file_name);
+        }
+
+        loaded
+    }
+
+    // Blocks until everything asked for so far has finished
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn wait(&mut self) -> Vec<Loaded> {
+        let mut loaded = Vec::new();
+
+        while !self.pending.is_empty() {
+            // Every worker is gone, what's left can't finish
+            let Ok(next) = self.loaded.recv() else {
+                break;
+            };
+
+            self.picked_up(&next.file_name);
+            loaded.push(next);
+        }
+
+        loaded
+    }
+
+    #[inline]
+    pub fn pending(&self) -> &[String] {
+        &self.pending
+    }
+
+    #[inline]
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    fn picked_up(&mut self, file_name: &str) {
+        if let Some(index) = self.pending.iter().position(|pending| pending == file_name) {
+            self.pending.remove(index);
+        }
+
+        if self.pending.is_empty() {
+            debug!("read {} files, {} bytes", self.progress.items(), self.progress.bytes());
+        }
+    }
+}
+
+// The queue's lock is only held while waiting for a job, so the others can take the next one
+#[cfg(not(target_arch = "wasm32"))]
+fn work(
+    queue: &Mutex<Receiver<String>>,
+    sender: &Sender<Loaded>,
+    source: &dyn AssetSource,
+    features: Features,
+    progress: &Progress,
+) {
+    loop {
+        let job = queue.lock().unwrap_or_else(PoisonError::into_inner).recv();
+
+        let Ok(file_name) = job else {
+            return;
+        };
+
+        let result = pollster::block_on(read_model_data(source, &file_name, features, progress));
+
+        if sender.send(Loaded { file_name, result }).is_err() {
+            return;
+        }
+    }
+}
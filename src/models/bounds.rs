@@ -0,0 +1,97 @@
+use cgmath::{ElementWise, Matrix4, Point3, Transform, Vector3};
+
+use crate::models::Ray;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    pub fn from_points<I>(points: I) -> Self
+        where I: IntoIterator<Item=[f32; 3]>
+    {
+        let mut min = Point3::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = Point3::new(f32::MIN, f32::MIN, f32::MIN);
+
+        for [x, y, z] in points {
+            min = Point3::new(min.x.min(x), min.y.min(y), min.z.min(z));
+            max = Point3::new(max.x.max(x), max.y.max(y), max.z.max(z));
+        }
+
+        Self { min, max }
+    }
+
+    // The box around all eight transformed corners
+    pub fn transform(&self, transform: Matrix4<f32>) -> Self {
+        Self::from_points((0..8).map(|corner| {
+            transform.transform_point(Point3::new(
+                if corner & 1 == 0 { self.min.x } else { self.max.x },
+                if corner & 2 == 0 { self.min.y } else { self.max.y },
+                if corner & 4 == 0 { self.min.z } else { self.max.z },
+            )).into()
+        }))
+    }
+
+    // Slab test, returns the distance along the ray to the nearest hit
+    pub fn intersect(&self, ray: &Ray) -> Option<f32> {
+        let inverse = Vector3::new(1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z);
+
+        let t1 = (self.min - ray.origin).mul_element_wise(inverse);
+        let t2 = (self.max - ray.origin).mul_element_wise(inverse);
+
+        let near = t1.x.min(t2.x).max(t1.y.min(t2.y)).max(t1.z.min(t2.z));
+        let far = t1.x.max(t2.x).min(t1.y.max(t2.y)).min(t1.z.max(t2.z));
+
+        (far >= near.max(0.0)).then_some(near.max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, InnerSpace};
+
+    use super::*;
+
+    const UNIT: Aabb = Aabb { min: Point3::new(-1.0, -1.0, -1.0), max: Point3::new(1.0, 1.0, 1.0) };
+
+    fn ray(origin: [f32; 3], direction: [f32; 3]) -> Ray {
+        Ray { origin: origin.into(), direction: Vector3::from(direction).normalize() }
+    }
+
+    #[test]
+    fn rays_hit_the_near_face() {
+        assert_eq!(UNIT.intersect(&ray([0.0, 0.0, 5.0], [0.0, 0.0, -1.0])), Some(4.0));
+        assert_eq!(UNIT.intersect(&ray([-3.0, 0.5, 0.5], [1.0, 0.0, 0.0])), Some(2.0));
+    }
+
+    #[test]
+    fn rays_from_inside_hit_straight_away() {
+        assert_eq!(UNIT.intersect(&ray([0.0, 0.0, 0.0], [0.0, 1.0, 0.0])), Some(0.0));
+    }
+
+    #[test]
+    fn rays_miss_beside_and_behind() {
+        assert_eq!(UNIT.intersect(&ray([2.0, 0.0, 5.0], [0.0, 0.0, -1.0])), None);
+        assert_eq!(UNIT.intersect(&ray([0.0, 0.0, 5.0], [0.0, 0.0, 1.0])), None);
+        assert_eq!(UNIT.intersect(&ray([0.0, 3.0, 5.0], [0.0, 0.0, -1.0])), None);
+    }
+
+    #[test]
+    fn from_points_takes_the_extremes() {
+        let bounds = Aabb::from_points([[1.0, -2.0, 3.0], [-4.0, 5.0, 0.0], [0.0, 0.0, -6.0]]);
+
+        assert_eq!(bounds, Aabb { min: Point3::new(-4.0, -2.0, -6.0), max: Point3::new(1.0, 5.0, 3.0) });
+    }
+
+    #[test]
+    fn transformed_bounds_hold_every_corner() {
+        let turned = Matrix4::from_translation(Vector3::new(10.0, 0.0, 0.0)) * Matrix4::from_angle_z(Deg(45.0));
+        let bounds = UNIT.transform(turned);
+        let reach = 2.0_f32.sqrt();
+
+        assert!((bounds.min - Point3::new(10.0 - reach, -reach, -1.0)).magnitude() < 1e-5, "{bounds:?}");
+        assert!((bounds.max - Point3::new(10.0 + reach, reach, 1.0)).magnitude() < 1e-5, "{bounds:?}");
+    }
+}
@@ -0,0 +1,301 @@
+use std::time::Duration;
+
+use anyhow::ensure;
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Point3, Quaternion, Rad, Rotation, Rotation3, Vector3};
+use serde::Deserialize;
+
+use crate::models::Camera;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct CameraKeyframe {
+    pub time: f32,
+    pub position: [f32; 3],
+    // yaw and pitch are authored in degrees, same as Camera::new in State::new
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl CameraKeyframe {
+    fn orientation(&self) -> Quaternion<f32> {
+        // Rotates unit_x onto the camera's forward vector, see Camera::calc_matrix
+        Quaternion::from_angle_y(-Rad::from(Deg(self.yaw)))
+            * Quaternion::from_angle_z(Rad::from(Deg(self.pitch)))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CameraTrack {
+    // Playback always advances in steps of this many seconds, regardless of frame time
+    pub timestep: f32,
+    #[serde(default)]
+    pub looping: bool,
+    pub keyframes: Vec<CameraKeyframe>,
+}
+
+impl CameraTrack {
+    // Playback and the segment search both rely on these, see load_camera_track
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(self.timestep > 0.0, "the timestep {} isn't above 0", self.timestep);
+        ensure!(!self.keyframes.is_empty(), "there are no keyframes");
+
+        for pair in self.keyframes.windows(2) {
+            ensure!(
+                pair[0].time < pair[1].time,
+                "the keyframe at {} follows the one at {}, times have to increase",
+                pair[1].time,
+                pair[0].time
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    pub fn sample(&self, time: f32) -> Option<(Point3<f32>, Rad<f32>, Rad<f32>)> {
+        let last = self.keyframes.len().checked_sub(1)?;
+        let time = time.clamp(self.keyframes[0].time, self.keyframes[last].time);
+
+        let segment = self.keyframes
+            .windows(2)
+            .position(|pair| time <= pair[1].time)
+            .unwrap_or(0)
+            .min(last.saturating_sub(1));
+
+        let k1 = &self.keyframes[segment];
+        let k2 = &self.keyframes[(segment + 1).min(last)];
+        let k0 = &self.keyframes[segment.saturating_sub(1)];
+        let k3 = &self.keyframes[(segment + 2).min(last)];
+
+        let span = k2.time - k1.time;
+        let t = if span > f32::EPSILON { (time - k1.time) / span } else { 0.0 };
+
+        let position = catmull_rom(
+            k0.position.into(),
+            k1.position.into(),
+            k2.position.into(),
+            k3.position.into(),
+            t,
+        );
+
+        let start = k1.orientation();
+        let mut end = k2.orientation();
+
+        // Take the shortest path between the two orientations
+        if start.dot(end) < 0.0 {
+            end = -end;
+        }
+
+        let forward = start.slerp(end, t).rotate_vector(Vector3::unit_x()).normalize();
+        let yaw = Rad(forward.z.atan2(forward.x));
+        let pitch = Rad(forward.y.clamp(-1.0, 1.0).asin());
+
+        Some((Point3::from_vec(position), yaw, pitch))
+    }
+}
+
+#[derive(Debug)]
+pub struct CameraAnimation {
+    track: CameraTrack,
+    time: f32,
+    // Frame time not yet spent on whole timesteps
+    pending: f32,
+    playing: bool,
+}
+
+impl CameraAnimation {
+    pub const fn new(track: CameraTrack) -> Self {
+        Self { track, time: 0.0, pending: 0.0, playing: false }
+    }
+
+    #[inline]
+    pub const fn playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn toggle(&mut self) {
+        self.playing = !self.playing;
+
+        if self.playing && self.time >= self.track.duration() {
+            self.time = 0.0;
+            self.pending = 0.0;
+        }
+    }
+
+    // Spends the frame time in whole timesteps, so a run passes through the same samples at any frame rate
+    pub fn update_camera(&mut self, camera: &mut Camera, dt: Duration) {
+        if !self.playing {
+            return;
+        }
+
+        let timestep = self.track.timestep;
+
+        self.pending += dt.as_secs_f32();
+
+        // A frame time of exactly one timestep shouldn't miss a step to rounding
+        let steps = (self.pending / timestep + 0.001).floor();
+
+        self.pending -= steps * timestep;
+        self.time += steps * timestep;
+
+        let duration = self.track.duration();
+
+        if self.time > duration {
+            if self.track.looping && duration > 0.0 {
+                self.time %= duration;
+            } else {
+                self.time = duration;
+                self.pending = 0.0;
+                self.playing = false;
+            }
+        }
+
+        if let Some((position, yaw, pitch)) = self.track.sample(self.time) {
+            camera.position = position;
+            camera.yaw = yaw;
+            camera.pitch = pitch;
+        }
+    }
+}
+
+fn catmull_rom(
+    p0: Vector3<f32>,
+    p1: Vector3<f32>,
+    p2: Vector3<f32>,
+    p3: Vector3<f32>,
+    t: f32,
+) -> Vector3<f32> {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, Rad};
+
+    use super::*;
+
+    fn keyframe(time: f32, position: [f32; 3], yaw: f32, pitch: f32) -> CameraKeyframe {
+        CameraKeyframe { time, position, yaw, pitch }
+    }
+
+    fn track(looping: bool, keyframes: Vec<CameraKeyframe>) -> CameraTrack {
+        CameraTrack { timestep: 0.25, looping, keyframes }
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn degrees(angle: Rad<f32>) -> f32 {
+        Deg::from(angle).0
+    }
+
+    fn play(track: CameraTrack, frames: &[f32]) -> (Camera, CameraAnimation) {
+        let mut animation = CameraAnimation::new(track);
+        let mut camera = Camera::new((0.0, 0.0, 0.0), Rad(0.0), Rad(0.0));
+
+        animation.toggle();
+
+        for &dt in frames {
+            animation.update_camera(&mut camera, Duration::from_secs_f32(dt));
+        }
+
+        (camera, animation)
+    }
+
+    #[test]
+    fn endpoints_are_the_keyframes() {
+        let track = track(false, vec![
+            keyframe(0.0, [1.0, 2.0, 3.0], 10.0, -20.0),
+            keyframe(1.0, [4.0, 0.0, 0.0], 50.0, 0.0),
+            keyframe(2.0, [0.0, 0.0, 8.0], 90.0, 30.0),
+        ]);
+
+        // Times outside the track clamp to its ends
+        for (time, expected) in [(-1.0, 0), (0.0, 0), (1.0, 1), (2.0, 2), (3.0, 2)] {
+            let (position, yaw, pitch) = track.sample(time).unwrap();
+            let keyframe = track.keyframes[expected];
+
+            assert_eq!(position, keyframe.position.into(), "at {time}");
+            assert!(close(degrees(yaw), keyframe.yaw), "at {time}: {:?}", Deg::from(yaw));
+            assert!(close(degrees(pitch), keyframe.pitch), "at {time}: {:?}", Deg::from(pitch));
+        }
+    }
+
+    #[test]
+    fn two_keyframes_meet_halfway() {
+        let track = track(false, vec![keyframe(0.0, [0.0, 0.0, 0.0], 0.0, 0.0), keyframe(2.0, [4.0, 2.0, -6.0], 0.0, 0.0)]);
+        let (position, _, _) = track.sample(1.0).unwrap();
+
+        assert!(close(position.x, 2.0) && close(position.y, 1.0) && close(position.z, -3.0), "{position:?}");
+    }
+
+    #[test]
+    fn yaw_and_pitch_are_slerped() {
+        let yawing = track(false, vec![keyframe(0.0, [0.0; 3], 0.0, 0.0), keyframe(1.0, [0.0; 3], 90.0, 0.0)]);
+        let (_, yaw, pitch) = yawing.sample(0.5).unwrap();
+
+        assert!(close(degrees(yaw), 45.0) && close(degrees(pitch), 0.0), "{:?} {:?}", Deg::from(yaw), Deg::from(pitch));
+
+        let pitching = track(false, vec![keyframe(0.0, [0.0; 3], 30.0, 0.0), keyframe(1.0, [0.0; 3], 30.0, 40.0)]);
+        let (_, yaw, pitch) = pitching.sample(0.25).unwrap();
+
+        assert!(close(degrees(yaw), 30.0) && close(degrees(pitch), 10.0), "{:?} {:?}", Deg::from(yaw), Deg::from(pitch));
+    }
+
+    #[test]
+    fn slerp_takes_the_short_way_round() {
+        let track = track(false, vec![keyframe(0.0, [0.0; 3], 170.0, 0.0), keyframe(1.0, [0.0; 3], -170.0, 0.0)]);
+        let (_, yaw, _) = track.sample(0.5).unwrap();
+
+        assert!(close(degrees(yaw).abs(), 180.0), "{:?}", Deg::from(yaw));
+    }
+
+    #[test]
+    fn looping_wraps_around() {
+        let keyframes = vec![keyframe(0.0, [0.0; 3], 0.0, 0.0), keyframe(1.0, [4.0, 0.0, 0.0], 0.0, 0.0)];
+        let (camera, animation) = play(track(true, keyframes), &[0.25; 5]);
+
+        assert!(animation.playing());
+        assert_eq!(camera.position, animation.track.sample(0.25).unwrap().0);
+    }
+
+    #[test]
+    fn playback_stops_at_the_end() {
+        let keyframes = vec![keyframe(0.0, [0.0; 3], 0.0, 0.0), keyframe(1.0, [4.0, 0.0, 0.0], 0.0, 0.0)];
+        let (camera, animation) = play(track(false, keyframes), &[0.25; 5]);
+
+        assert!(!animation.playing());
+        assert_eq!(camera.position, Point3::new(4.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn frame_rate_doesnt_change_the_speed() {
+        let keyframes = vec![keyframe(0.0, [0.0; 3], 0.0, 0.0), keyframe(2.0, [8.0, 0.0, 0.0], 0.0, 0.0)];
+        let (slow, _) = play(track(false, keyframes.clone()), &[0.5, 0.5]);
+        let (fast, _) = play(track(false, keyframes.clone()), &[0.125; 8]);
+        let (uneven, _) = play(track(false, keyframes), &[0.1, 0.3, 0.2, 0.4]);
+
+        assert_eq!(slow.position, fast.position);
+        assert_eq!(slow.position, uneven.position);
+    }
+
+    #[test]
+    fn bad_tracks_are_refused() {
+        let keyframes = vec![keyframe(0.0, [0.0; 3], 0.0, 0.0), keyframe(1.0, [1.0; 3], 0.0, 0.0)];
+
+        assert!(track(false, keyframes.clone()).validate().is_ok());
+        assert!(CameraTrack { timestep: 0.0, ..track(false, keyframes.clone()) }.validate().is_err());
+        assert!(track(false, vec![]).validate().is_err());
+        assert!(track(false, vec![keyframes[1], keyframes[0]]).validate().is_err());
+        assert!(track(false, vec![keyframes[0], keyframes[0]]).validate().is_err());
+    }
+}
@@ -2,8 +2,9 @@ use std::time::Duration;
 
 use cgmath::{InnerSpace, Rad, Vector3};
 use winit::dpi::PhysicalPosition;
-use winit::event::{ElementState, MouseScrollDelta, VirtualKeyCode};
+use winit::event::{ElementState, MouseScrollDelta};
 
+use crate::input::Action;
 use crate::models::Camera;
 use crate::models::camera::SAFE_FRAC_PI_2;
 
@@ -18,8 +19,8 @@ pub struct CameraController {
     rotate_horizontal: f32,
     rotate_vertical: f32,
     scroll: f32,
-    speed: f32,
-    sensitivity: f32,
+    pub speed: f32,
+    pub sensitivity: f32,
 }
 
 impl CameraController {
@@ -31,16 +32,21 @@ impl CameraController {
         }
     }
 
-    pub fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) -> bool {
-        let amount = if state == ElementState::Pressed { 2.0 } else { 0.0 };
+    pub fn process_action(&mut self, action: Action, state: ElementState) -> bool {
+        self.process_axis(action, if state == ElementState::Pressed { 1.0 } else { 0.0 })
+    }
 
-        match key {
-            VirtualKeyCode::W | VirtualKeyCode::Up => self.amount_forward = amount,
-            VirtualKeyCode::S | VirtualKeyCode::Down => self.amount_backward = amount,
-            VirtualKeyCode::A | VirtualKeyCode::Left => self.amount_left = amount,
-            VirtualKeyCode::D | VirtualKeyCode::Right => self.amount_right = amount,
-            VirtualKeyCode::Q | VirtualKeyCode::Space => self.amount_up = amount,
-            VirtualKeyCode::E | VirtualKeyCode::LShift => self.amount_down = amount,
+    // Analog counterpart of process_action, value is expected in 0.0..=1.0
+    pub fn process_axis(&mut self, action: Action, value: f32) -> bool {
+        let amount = value * 2.0;
+
+        match action {
+            Action::MoveForward => self.amount_forward = amount,
+            Action::MoveBackward => self.amount_backward = amount,
+            Action::MoveLeft => self.amount_left = amount,
+            Action::MoveRight => self.amount_right = amount,
+            Action::MoveUp => self.amount_up = amount,
+            Action::MoveDown => self.amount_down = amount,
             _ => return false,
         };
 
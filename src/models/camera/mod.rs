@@ -2,6 +2,7 @@ use std::f32::consts::FRAC_PI_2;
 
 use cgmath::{InnerSpace, Matrix4, Point3, Rad, Vector3};
 
+pub use animation::{CameraAnimation, CameraTrack};
 pub use controller::CameraController;
 pub use projection::CameraProjection;
 pub use uniform::CameraUniform;
@@ -16,6 +17,7 @@ const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
 
 const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
 
+mod animation;
 mod controller;
 mod projection;
 mod uniform;
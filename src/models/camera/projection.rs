@@ -4,9 +4,9 @@ use crate::models::camera::OPENGL_TO_WGPU_MATRIX;
 
 pub struct CameraProjection {
     aspect: f32,
-    fovy: Rad<f32>,
-    znear: f32,
-    zfar: f32,
+    pub fovy: Rad<f32>,
+    pub znear: f32,
+    pub zfar: f32,
 }
 
 impl CameraProjection {
@@ -11,6 +11,10 @@ pub struct CameraUniform {
     // We can't use cgmath with bytemuck directly so we'll have
     // to convert the Matrix4 into a 4x4 f32 array
     view_proj: [[f32; 4]; 4],
+    // The projection's far plane, what the depth view shades up to
+    far: f32,
+    // Uniform structs are a multiple of 16 bytes
+    _padding: [f32; 3],
 }
 
 impl CameraUniform {
@@ -18,11 +22,14 @@ impl CameraUniform {
         Self {
             view_position: [0.0; 4],
             view_proj: Matrix4::identity().into(),
+            far: 1.0,
+            _padding: [0.0; 3],
         }
     }
 
     pub fn update_view_proj(&mut self, camera: &Camera, projection: &CameraProjection) {
         self.view_position = camera.position.to_homogeneous().into();
         self.view_proj = (projection.calc_matrix() * camera.calc_matrix()).into();
+        self.far = projection.zfar;
     }
 }
@@ -0,0 +1,112 @@
+use std::f32::consts::TAU;
+
+use cgmath::{Matrix3, Matrix4, Point3, SquareMatrix, Transform, Vector3, Vector4};
+
+use crate::models::{Aabb, LineVertex, Mesh};
+
+const CIRCLE_SEGMENTS: u16 = 32;
+
+const RED: [f32; 3] = [0.9, 0.2, 0.2];
+const GREEN: [f32; 3] = [0.2, 0.9, 0.2];
+const BLUE: [f32; 3] = [0.2, 0.4, 0.9];
+
+// Collects debug lines for a single frame, they're cleared before the next one is built
+#[derive(Debug, Default)]
+pub struct DebugDraw {
+    pub enabled: bool,
+    vertices: Vec<LineVertex>,
+}
+
+impl DebugDraw {
+    #[inline]
+    pub fn vertices(&self) -> &[LineVertex] {
+        &self.vertices
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    pub fn line(&mut self, from: Point3<f32>, to: Point3<f32>, color: [f32; 3]) {
+        self.vertices.push(LineVertex::new(from.into(), color));
+        self.vertices.push(LineVertex::new(to.into(), color));
+    }
+
+    pub fn aabb(&mut self, bounds: &Aabb, transform: Matrix4<f32>, color: [f32; 3]) {
+        let corner = |x: bool, y: bool, z: bool| transform.transform_point(Point3::new(
+            if x { bounds.max.x } else { bounds.min.x },
+            if y { bounds.max.y } else { bounds.min.y },
+            if z { bounds.max.z } else { bounds.min.z },
+        ));
+
+        self.cuboid(corner, color);
+    }
+
+    pub fn sphere(&mut self, center: Point3<f32>, radius: f32, color: [f32; 3]) {
+        let (x, y, z) = (Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z());
+
+        for (u, v) in [(x, y), (y, z), (z, x)] {
+            let point = |segment: u16| {
+                let angle = f32::from(segment) / f32::from(CIRCLE_SEGMENTS) * TAU;
+
+                center + (u * angle.cos() + v * angle.sin()) * radius
+            };
+
+            for segment in 0..CIRCLE_SEGMENTS {
+                self.line(point(segment), point(segment + 1), color);
+            }
+        }
+    }
+
+    pub fn axes(&mut self, transform: Matrix4<f32>, size: f32) {
+        let origin = transform.transform_point(Point3::new(0.0, 0.0, 0.0));
+
+        for (axis, color) in [(Vector3::unit_x(), RED), (Vector3::unit_y(), GREEN), (Vector3::unit_z(), BLUE)] {
+            self.line(origin, transform.transform_point(Point3::new(0.0, 0.0, 0.0) + axis * size), color);
+        }
+    }
+
+    // Draws the volume a view projection matrix can see by un-projecting the corners of clip space
+    pub fn frustum(&mut self, view_proj: Matrix4<f32>, color: [f32; 3]) {
+        let Some(inverse) = view_proj.invert() else {
+            return;
+        };
+
+        let corner = |x: bool, y: bool, z: bool| Point3::from_homogeneous(inverse * Vector4::new(
+            if x { 1.0 } else { -1.0 },
+            if y { 1.0 } else { -1.0 },
+            if z { 1.0 } else { 0.0 },
+            1.0,
+        ));
+
+        self.cuboid(corner, color);
+    }
+
+    // Normals in blue, tangents in red and bi_tangents in green, as calculated by model_to_mesh
+    pub fn tangent_frames(&mut self, mesh: &Mesh, transform: Matrix4<f32>, length: f32) {
+        let basis = Matrix3::from_cols(transform.x.truncate(), transform.y.truncate(), transform.z.truncate());
+
+        for vertex in &mesh.vertices {
+            let position = transform.transform_point(vertex.position.into());
+
+            for (direction, color) in [(vertex.normal, BLUE), (vertex.tangent, RED), (vertex.bi_tangent, GREEN)] {
+                let direction = basis * Vector3::from(direction);
+
+                self.line(position, position + direction * length, color);
+            }
+        }
+    }
+
+    fn cuboid<F>(&mut self, corner: F, color: [f32; 3])
+        where F: Fn(bool, bool, bool) -> Point3<f32>
+    {
+        for a in [false, true] {
+            for b in [false, true] {
+                self.line(corner(false, a, b), corner(true, a, b), color);
+                self.line(corner(a, false, b), corner(a, true, b), color);
+                self.line(corner(a, b, false), corner(a, b, true), color);
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use wgpu::{BindGroup, RenderPass};
+
+use crate::models::{DynamicVertexBuffer, LineVertex};
+
+pub trait DrawLines<'a> {
+    fn draw_lines(
+        &mut self,
+        lines: &'a DynamicVertexBuffer<LineVertex>,
+        camera_bind_group: &'a BindGroup,
+    );
+}
+
+impl<'a, 'b> DrawLines<'b> for RenderPass<'a>
+    where 'b: 'a,
+{
+    fn draw_lines(
+        &mut self,
+        lines: &'b DynamicVertexBuffer<LineVertex>,
+        camera_bind_group: &'b BindGroup,
+    ) {
+        if lines.len == 0 {
+            return;
+        }
+
+        self.set_vertex_buffer(0, lines.buffer.slice(..));
+        self.set_bind_group(0, camera_bind_group, &[]);
+        self.draw(0..lines.len, 0..1);
+    }
+}
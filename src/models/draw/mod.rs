@@ -1,5 +1,11 @@
 pub use light::DrawLight;
+pub use lines::DrawLines;
 pub use model::DrawModel;
+pub use outline::DrawOutline;
+pub use text::DrawText;
 
 mod light;
-mod model;
\ No newline at end of file
+mod lines;
+mod model;
+mod outline;
+mod text;
@@ -2,6 +2,7 @@ use std::ops::Range;
 
 use wgpu::{BindGroup, IndexFormat, RenderPass};
 
+use crate::assets::AssetStore;
 use crate::models::{Material, Mesh, Model};
 
 pub trait DrawModel<'a> {
@@ -25,6 +26,7 @@ pub trait DrawModel<'a> {
     fn draw_model(
         &mut self,
         model: &'a Model,
+        materials: &'a AssetStore<Material>,
         camera_bind_group: &'a BindGroup,
         light_bind_group: &'a BindGroup,
     );
@@ -32,6 +34,16 @@ pub trait DrawModel<'a> {
     fn draw_model_instanced(
         &mut self,
         model: &'a Model,
+        materials: &'a AssetStore<Material>,
+        instances: Range<u32>,
+        camera_bind_group: &'a BindGroup,
+        light_bind_group: &'a BindGroup,
+    );
+
+    fn draw_model_wireframe_instanced(
+        &mut self,
+        model: &'a Model,
+        materials: &'a AssetStore<Material>,
         instances: Range<u32>,
         camera_bind_group: &'a BindGroup,
         light_bind_group: &'a BindGroup,
@@ -70,22 +82,45 @@ impl<'a, 'b> DrawModel<'b> for RenderPass<'a>
     fn draw_model(
         &mut self,
         model: &'b Model,
+        materials: &'b AssetStore<Material>,
         camera_bind_group: &'b BindGroup,
         light_bind_group: &'b BindGroup,
     ) {
-        self.draw_model_instanced(model, 0..1, camera_bind_group, light_bind_group);
+        self.draw_model_instanced(model, materials, 0..1, camera_bind_group, light_bind_group);
     }
 
     fn draw_model_instanced(
         &mut self,
         model: &'b Model,
+        materials: &'b AssetStore<Material>,
         instances: Range<u32>,
         camera_bind_group: &'b BindGroup,
         light_bind_group: &'b BindGroup,
     ) {
         for mesh in &model.meshes {
-            let material = &model.materials[mesh.material];
+            let material = &materials[model.materials[mesh.material]];
             self.draw_mesh_instanced(mesh, material, instances.clone(), camera_bind_group, light_bind_group);
         }
     }
+
+    fn draw_model_wireframe_instanced(
+        &mut self,
+        model: &'b Model,
+        materials: &'b AssetStore<Material>,
+        instances: Range<u32>,
+        camera_bind_group: &'b BindGroup,
+        light_bind_group: &'b BindGroup,
+    ) {
+        for mesh in &model.meshes {
+            let Some(wireframe_buffer) = &mesh.wireframe_buffer else {
+                continue;
+            };
+
+            self.set_vertex_buffer(0, wireframe_buffer.slice(..));
+            self.set_bind_group(0, &materials[model.materials[mesh.material]].bind_group, &[]);
+            self.set_bind_group(1, camera_bind_group, &[]);
+            self.set_bind_group(2, light_bind_group, &[]);
+            self.draw(0..mesh.num_elements, instances.clone());
+        }
+    }
 }
@@ -0,0 +1,53 @@
+use std::ops::Range;
+
+use wgpu::{BindGroup, IndexFormat, RenderPass};
+
+use crate::models::{Mesh, Model};
+
+pub trait DrawOutline<'a> {
+    fn draw_outline_mesh_instanced(
+        &mut self,
+        mesh: &'a Mesh,
+        instances: Range<u32>,
+        camera_bind_group: &'a BindGroup,
+        outline_bind_group: &'a BindGroup,
+    );
+
+    fn draw_outline_model_instanced(
+        &mut self,
+        model: &'a Model,
+        instances: Range<u32>,
+        camera_bind_group: &'a BindGroup,
+        outline_bind_group: &'a BindGroup,
+    );
+}
+
+impl<'a, 'b> DrawOutline<'b> for RenderPass<'a>
+    where 'b: 'a,
+{
+    fn draw_outline_mesh_instanced(
+        &mut self,
+        mesh: &'b Mesh,
+        instances: Range<u32>,
+        camera_bind_group: &'b BindGroup,
+        outline_bind_group: &'b BindGroup,
+    ) {
+        self.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        self.set_index_buffer(mesh.index_buffer.slice(..), IndexFormat::Uint32);
+        self.set_bind_group(0, camera_bind_group, &[]);
+        self.set_bind_group(1, outline_bind_group, &[]);
+        self.draw_indexed(0..mesh.num_elements, 0, instances);
+    }
+
+    fn draw_outline_model_instanced(
+        &mut self,
+        model: &'b Model,
+        instances: Range<u32>,
+        camera_bind_group: &'b BindGroup,
+        outline_bind_group: &'b BindGroup,
+    ) {
+        for mesh in &model.meshes {
+            self.draw_outline_mesh_instanced(mesh, instances.clone(), camera_bind_group, outline_bind_group);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use wgpu::{BindGroup, RenderPass};
+
+use crate::models::{DynamicVertexBuffer, TextVertex};
+
+pub trait DrawText<'a> {
+    fn draw_text(
+        &mut self,
+        text: &'a DynamicVertexBuffer<TextVertex>,
+        font_bind_group: &'a BindGroup,
+    );
+}
+
+impl<'a, 'b> DrawText<'b> for RenderPass<'a>
+    where 'b: 'a,
+{
+    fn draw_text(
+        &mut self,
+        text: &'b DynamicVertexBuffer<TextVertex>,
+        font_bind_group: &'b BindGroup,
+    ) {
+        if text.len == 0 {
+            return;
+        }
+
+        self.set_vertex_buffer(0, text.buffer.slice(..));
+        self.set_bind_group(0, font_bind_group, &[]);
+        self.draw(0..text.len, 0..1);
+    }
+}
@@ -0,0 +1,51 @@
+use std::marker::PhantomData;
+use std::mem::size_of;
+
+use bytemuck::{cast_slice, Pod};
+use wgpu::{Buffer, BufferAddress, BufferDescriptor, BufferUsages, Device, Queue};
+
+// A vertex buffer for geometry that's rebuilt every frame, it only grows
+pub struct DynamicVertexBuffer<T> {
+    pub buffer: Buffer,
+    pub len: u32,
+    capacity: usize,
+    label: String,
+    vertex: PhantomData<T>,
+}
+
+impl<T: Pod> DynamicVertexBuffer<T> {
+    pub fn new(device: &Device, capacity: usize, label: &str) -> Self {
+        Self {
+            buffer: Self::create_buffer(device, capacity, label),
+            len: 0,
+            capacity,
+            label: label.to_string(),
+            vertex: PhantomData,
+        }
+    }
+
+    pub fn write(&mut self, device: &Device, queue: &Queue, vertices: &[T]) {
+        if vertices.len() > self.capacity {
+            self.capacity = vertices.len().next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.capacity, &self.label);
+        }
+
+        if !vertices.is_empty() {
+            queue.write_buffer(&self.buffer, 0, cast_slice(vertices));
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let len = vertices.len() as u32;
+
+        self.len = len;
+    }
+
+    fn create_buffer(device: &Device, capacity: usize, label: &str) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some(&format!("{label} - vertex buffer")),
+            size: (capacity * size_of::<T>()) as BufferAddress,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
@@ -0,0 +1,32 @@
+use cgmath::{InnerSpace, Matrix, Matrix4, Vector4};
+
+use crate::models::Aabb;
+
+// The six clip planes of a view projection, with normals pointing inwards
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    // Gribb/Hartmann plane extraction, wgpu clip space has z in 0..1 so the near plane is just row 2
+    pub fn from_matrix(view_proj: Matrix4<f32>) -> Self {
+        let (x, y, z, w) = (view_proj.row(0), view_proj.row(1), view_proj.row(2), view_proj.row(3));
+
+        let planes = [w + x, w - x, w + y, w - y, z, w - z]
+            .map(|plane| plane / plane.truncate().magnitude());
+
+        Self { planes }
+    }
+
+    // Conservative, boxes that straddle a corner of the frustum can still count as visible
+    pub fn intersects(&self, bounds: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let x = if plane.x >= 0.0 { bounds.max.x } else { bounds.min.x };
+            let y = if plane.y >= 0.0 { bounds.max.y } else { bounds.min.y };
+            let z = if plane.z >= 0.0 { bounds.max.z } else { bounds.min.z };
+
+            plane.w + plane.z.mul_add(z, plane.x.mul_add(x, plane.y * y)) >= 0.0
+        })
+    }
+}
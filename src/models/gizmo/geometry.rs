@@ -0,0 +1,80 @@
+use std::f32::consts::TAU;
+
+use cgmath::{EuclideanSpace, Point3, Vector3};
+
+use crate::models::gizmo::{Gizmo, GizmoAxis, GizmoMode};
+use crate::models::{Instance, LineVertex};
+
+const RING_SEGMENTS: u16 = 48;
+const ACTIVE_COLOR: [f32; 3] = [1.0, 0.9, 0.1];
+
+impl Gizmo {
+    pub fn lines(&self, instance: &Instance, size: f32) -> Vec<LineVertex> {
+        let center = Point3::from_vec(instance.position);
+        let mut lines = Vec::new();
+
+        for axis in GizmoAxis::ALL {
+            let color = if self.drag.map(|drag| drag.axis) == Some(axis) {
+                ACTIVE_COLOR
+            } else {
+                axis_color(axis)
+            };
+
+            let direction = self.axis_direction(axis, instance.rotation);
+            let (u, v) = axis.plane();
+            let mut line = |from: Point3<f32>, to: Point3<f32>| {
+                lines.push(LineVertex::new(from.into(), color));
+                lines.push(LineVertex::new(to.into(), color));
+            };
+
+            match self.mode {
+                GizmoMode::Translate => {
+                    let tip = center + direction * size;
+                    let base = tip - direction * size * 0.15;
+
+                    line(center, tip);
+
+                    for side in [u, -u, v, -v] {
+                        line(tip, base + side * size * 0.06);
+                    }
+                }
+                GizmoMode::Rotate => {
+                    let point = |segment: u16| {
+                        let angle = f32::from(segment) / f32::from(RING_SEGMENTS) * TAU;
+
+                        center + (u * angle.cos() + v * angle.sin()) * size
+                    };
+
+                    for segment in 0..RING_SEGMENTS {
+                        line(point(segment), point(segment + 1));
+                    }
+                }
+                GizmoMode::Scale => {
+                    let tip = center + direction * size;
+
+                    line(center, tip);
+
+                    // A small cube at the end of the handle, turned with the instance like the handle
+                    let half = size * 0.05;
+                    let corner = |x: f32, y: f32, z: f32| tip + instance.rotation * Vector3::new(x, y, z) * half;
+
+                    for (a, b) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+                        line(corner(-1.0, a, b), corner(1.0, a, b));
+                        line(corner(a, -1.0, b), corner(a, 1.0, b));
+                        line(corner(a, b, -1.0), corner(a, b, 1.0));
+                    }
+                }
+            }
+        }
+
+        lines
+    }
+}
+
+const fn axis_color(axis: GizmoAxis) -> [f32; 3] {
+    match axis {
+        GizmoAxis::X => [0.9, 0.2, 0.2],
+        GizmoAxis::Y => [0.2, 0.9, 0.2],
+        GizmoAxis::Z => [0.2, 0.4, 0.9],
+    }
+}
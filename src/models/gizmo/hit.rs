@@ -0,0 +1,72 @@
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3};
+
+use crate::models::gizmo::{Gizmo, GizmoAxis, GizmoMode};
+use crate::models::{Instance, Ray};
+
+// How close, relative to the gizmo's size, the cursor has to be to grab a handle
+const HANDLE_TOLERANCE: f32 = 0.08;
+
+impl Gizmo {
+    pub fn hit(&self, ray: &Ray, instance: &Instance, size: f32) -> Option<GizmoAxis> {
+        let center = Point3::from_vec(instance.position);
+        let tolerance = size * HANDLE_TOLERANCE;
+
+        GizmoAxis::ALL
+            .into_iter()
+            .filter_map(|axis| {
+                let direction = self.axis_direction(axis, instance.rotation);
+
+                match self.mode {
+                    GizmoMode::Translate | GizmoMode::Scale => {
+                        let (along, distance) = closest_on_axis(ray, center, direction)?;
+
+                        (distance < tolerance && (0.0..=size + tolerance).contains(&along))
+                            .then_some((axis, distance))
+                    }
+                    GizmoMode::Rotate => {
+                        let point = intersect_plane(ray, center, direction)?;
+                        let distance = ((point - center).magnitude() - size).abs();
+
+                        (distance < tolerance).then_some((axis, distance))
+                    }
+                }
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(axis, _)| axis)
+    }
+}
+
+// Closest approach between the ray and the line through center along axis, returns
+// how far along the axis that point lies and how far the ray passes from it
+pub fn closest_on_axis(ray: &Ray, center: Point3<f32>, axis: Vector3<f32>) -> Option<(f32, f32)> {
+    let offset = center - ray.origin;
+    let alignment = axis.dot(ray.direction);
+    let denominator = alignment.mul_add(-alignment, 1.0);
+
+    // The ray runs parallel to the axis
+    if denominator.abs() < 1e-6 {
+        return None;
+    }
+
+    let axis_offset = axis.dot(offset);
+    let ray_offset = ray.direction.dot(offset);
+    let along = alignment.mul_add(ray_offset, -axis_offset) / denominator;
+    let distance_along_ray = alignment.mul_add(-axis_offset, ray_offset) / denominator;
+
+    let on_axis = center + axis * along;
+    let on_ray = ray.origin + ray.direction * distance_along_ray;
+
+    Some((along, (on_axis - on_ray).magnitude()))
+}
+
+pub fn intersect_plane(ray: &Ray, center: Point3<f32>, normal: Vector3<f32>) -> Option<Point3<f32>> {
+    let facing = ray.direction.dot(normal);
+
+    if facing.abs() < 1e-6 {
+        return None;
+    }
+
+    let t = (center - ray.origin).dot(normal) / facing;
+
+    (t > 0.0).then(|| ray.origin + ray.direction * t)
+}
@@ -0,0 +1,319 @@
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Point3, Quaternion, Rad, Rotation3, Vector3};
+
+use crate::models::{Instance, Ray};
+
+mod geometry;
+mod hit;
+
+// Gizmos stay roughly the same size on screen, this is their length per unit of camera distance
+const GIZMO_SCALE: f32 = 0.15;
+const MIN_SCALE: f32 = 0.01;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum GizmoMode {
+    #[default]
+    Translate,
+    Rotate,
+    Scale,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GizmoAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl GizmoAxis {
+    pub const ALL: [Self; 3] = [Self::X, Self::Y, Self::Z];
+
+    pub fn direction(self) -> Vector3<f32> {
+        match self {
+            Self::X => Vector3::unit_x(),
+            Self::Y => Vector3::unit_y(),
+            Self::Z => Vector3::unit_z(),
+        }
+    }
+
+    // Two axes spanning the plane perpendicular to this one, ordered so angles
+    // measured from the first towards the second turn the right-handed way
+    fn plane(self) -> (Vector3<f32>, Vector3<f32>) {
+        match self {
+            Self::X => (Vector3::unit_y(), Vector3::unit_z()),
+            Self::Y => (Vector3::unit_z(), Vector3::unit_x()),
+            Self::Z => (Vector3::unit_x(), Vector3::unit_y()),
+        }
+    }
+
+    const fn index(self) -> usize {
+        match self {
+            Self::X => 0,
+            Self::Y => 1,
+            Self::Z => 2,
+        }
+    }
+}
+
+// The increments edits are snapped to
+#[derive(Clone, Copy, Debug)]
+pub struct GizmoSnap {
+    pub translation: f32,
+    pub rotation: Deg<f32>,
+    pub scale: f32,
+}
+
+impl Default for GizmoSnap {
+    fn default() -> Self {
+        Self {
+            translation: 0.5,
+            rotation: Deg(15.0),
+            scale: 0.25,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GizmoDrag {
+    axis: GizmoAxis,
+    // Where along the axis, or at what angle around it, the drag started
+    start: f32,
+    position: Vector3<f32>,
+    rotation: Quaternion<f32>,
+    scale: Vector3<f32>,
+}
+
+#[derive(Debug, Default)]
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub snap: GizmoSnap,
+    pub snapping: bool,
+    drag: Option<GizmoDrag>,
+}
+
+impl Gizmo {
+    pub fn size(center: Point3<f32>, eye: Point3<f32>) -> f32 {
+        (eye - center).magnitude() * GIZMO_SCALE
+    }
+
+    #[inline]
+    pub const fn dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    pub fn begin_drag(&mut self, ray: &Ray, instance: &Instance, size: f32) -> bool {
+        self.drag = self.hit(ray, instance, size).and_then(|axis| {
+            let start = self.drag_value(axis, ray, Point3::from_vec(instance.position), instance.rotation)?;
+
+            Some(GizmoDrag {
+                axis,
+                start,
+                position: instance.position,
+                rotation: instance.rotation,
+                scale: instance.scale,
+            })
+        });
+
+        self.drag.is_some()
+    }
+
+    pub fn drag(&self, ray: &Ray, instance: &mut Instance) -> bool {
+        let Some(drag) = self.drag else {
+            return false;
+        };
+
+        let center = Point3::from_vec(drag.position);
+
+        let Some(value) = self.drag_value(drag.axis, ray, center, drag.rotation) else {
+            return false;
+        };
+
+        let direction = self.axis_direction(drag.axis, drag.rotation);
+
+        match self.mode {
+            GizmoMode::Translate => {
+                let offset = self.snapped(value - drag.start, self.snap.translation);
+
+                instance.position = drag.position + direction * offset;
+            }
+            GizmoMode::Rotate => {
+                let angle = self.snapped(value - drag.start, Rad::from(self.snap.rotation).0);
+
+                instance.rotation = Quaternion::from_axis_angle(direction, Rad(angle)) * drag.rotation;
+            }
+            GizmoMode::Scale => {
+                if drag.start.abs() < f32::EPSILON {
+                    return false;
+                }
+
+                let index = drag.axis.index();
+                let scale = self.snapped(drag.scale[index] * value / drag.start, self.snap.scale);
+
+                instance.scale[index] = scale.max(MIN_SCALE);
+            }
+        }
+
+        true
+    }
+
+    #[inline]
+    pub const fn end_drag(&mut self) {
+        self.drag = None;
+    }
+
+    fn snapped(&self, value: f32, increment: f32) -> f32 {
+        if self.snapping && increment > 0.0 {
+            (value / increment).round() * increment
+        } else {
+            value
+        }
+    }
+
+    // Scale stretches along the instance's own axes, so its handles turn with the instance,
+    // translate and rotate work along the world axes
+    fn axis_direction(&self, axis: GizmoAxis, rotation: Quaternion<f32>) -> Vector3<f32> {
+        match self.mode {
+            GizmoMode::Scale => rotation * axis.direction(),
+            GizmoMode::Translate | GizmoMode::Rotate => axis.direction(),
+        }
+    }
+
+    // The distance along the axis for translate and scale, the angle around it for rotate
+    fn drag_value(&self, axis: GizmoAxis, ray: &Ray, center: Point3<f32>, rotation: Quaternion<f32>) -> Option<f32> {
+        let direction = self.axis_direction(axis, rotation);
+
+        match self.mode {
+            GizmoMode::Translate | GizmoMode::Scale =>
+                hit::closest_on_axis(ray, center, direction).map(|(along, _)| along),
+            GizmoMode::Rotate => {
+                let point = hit::intersect_plane(ray, center, direction)?;
+                let (u, v) = axis.plane();
+                let offset = point - center;
+
+                Some(offset.dot(v).atan2(offset.dot(u)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{One, Rotation};
+
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn instance(rotation: Quaternion<f32>) -> Instance {
+        Instance {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            rotation,
+            scale: Vector3::new(1.0, 1.0, 1.0),
+            selected: false,
+        }
+    }
+
+    // Straight down at a point in the xy plane, the gizmos below are all a unit in size
+    fn ray_at(x: f32, y: f32) -> Ray {
+        Ray { origin: Point3::new(x, y, 10.0), direction: -Vector3::unit_z() }
+    }
+
+    fn drag(gizmo: &mut Gizmo, instance: &mut Instance, from: (f32, f32), to: (f32, f32)) -> bool {
+        gizmo.begin_drag(&ray_at(from.0, from.1), instance, 1.0) && gizmo.drag(&ray_at(to.0, to.1), instance)
+    }
+
+    #[test]
+    fn translate_follows_the_axis() {
+        let mut gizmo = Gizmo::default();
+        let mut moved = instance(Quaternion::one());
+
+        assert!(drag(&mut gizmo, &mut moved, (0.5, 0.0), (2.5, 0.7)));
+        assert!(close(moved.position.x, 2.0) && close(moved.position.y, 0.0), "{:?}", moved.position);
+
+        // Only the handles are grabbed
+        gizmo.end_drag();
+
+        assert!(!drag(&mut gizmo, &mut moved, (0.5, 0.5), (2.0, 2.0)));
+    }
+
+    #[test]
+    fn translate_snaps() {
+        let mut gizmo = Gizmo { snapping: true, ..Gizmo::default() };
+        let mut moved = instance(Quaternion::one());
+
+        assert!(drag(&mut gizmo, &mut moved, (0.0, 0.5), (0.0, 1.2)));
+        assert!(close(moved.position.y, 0.5), "{:?}", moved.position);
+    }
+
+    #[test]
+    fn rotate_turns_around_the_axis() {
+        let mut gizmo = Gizmo { mode: GizmoMode::Rotate, ..Gizmo::default() };
+        let mut turned = instance(Quaternion::one());
+
+        // Along the z ring a quarter turn, from +x round to +y
+        assert!(drag(&mut gizmo, &mut turned, (1.0, 0.0), (0.0, 1.0)));
+
+        let forward = turned.rotation.rotate_vector(Vector3::unit_x());
+
+        assert!(close(forward.x, 0.0) && close(forward.y, 1.0), "{forward:?}");
+    }
+
+    #[test]
+    fn rotate_snaps() {
+        let mut gizmo = Gizmo { mode: GizmoMode::Rotate, snapping: true, ..Gizmo::default() };
+        let mut turned = instance(Quaternion::one());
+        let (sin, cos) = Rad::from(Deg(50.0_f32)).0.sin_cos();
+
+        assert!(drag(&mut gizmo, &mut turned, (1.0, 0.0), (cos, sin)));
+
+        let forward = turned.rotation.rotate_vector(Vector3::unit_x());
+        let angle = Deg::from(Rad(forward.y.atan2(forward.x)));
+
+        assert!(close(angle.0, 45.0), "{angle:?}");
+    }
+
+    #[test]
+    fn scale_stretches_the_axis_it_was_dragged_along() {
+        let mut gizmo = Gizmo { mode: GizmoMode::Scale, ..Gizmo::default() };
+        let mut stretched = instance(Quaternion::one());
+
+        assert!(drag(&mut gizmo, &mut stretched, (0.5, 0.0), (1.0, 0.3)));
+        assert!(close(stretched.scale.x, 2.0) && close(stretched.scale.y, 1.0), "{:?}", stretched.scale);
+    }
+
+    #[test]
+    fn scale_handles_turn_with_the_instance() {
+        let mut gizmo = Gizmo { mode: GizmoMode::Scale, ..Gizmo::default() };
+        let rotation = Quaternion::from_angle_z(Deg(45.0));
+        let mut stretched = instance(rotation);
+        let local_x = rotation.rotate_vector(Vector3::unit_x());
+
+        // The local x handle lies along the turned axis, not along the world one
+        assert!(!drag(&mut gizmo, &mut stretched, (0.5, 0.0), (1.0, 0.0)));
+        gizmo.end_drag();
+
+        let grab = local_x * 0.5;
+        let pull = local_x * 1.5;
+
+        assert!(drag(&mut gizmo, &mut stretched, (grab.x, grab.y), (pull.x, pull.y)));
+        assert!(close(stretched.scale.x, 3.0) && close(stretched.scale.y, 1.0), "{:?}", stretched.scale);
+
+        // And the handle is drawn where it's grabbed
+        let handle = gizmo.lines(&stretched, 1.0)[1].position;
+
+        assert!(close(handle[0], local_x.x) && close(handle[1], local_x.y), "{handle:?}");
+    }
+
+    #[test]
+    fn scale_snaps_and_stays_positive() {
+        let mut gizmo = Gizmo { mode: GizmoMode::Scale, snapping: true, ..Gizmo::default() };
+        let mut stretched = instance(Quaternion::one());
+
+        assert!(drag(&mut gizmo, &mut stretched, (0.0, 0.8), (0.0, 1.1)));
+        assert!(close(stretched.scale.y, 1.5), "{:?}", stretched.scale);
+
+        assert!(gizmo.drag(&ray_at(0.0, -1.0), &mut stretched));
+        assert!(close(stretched.scale.y, MIN_SCALE), "{:?}", stretched.scale);
+    }
+}
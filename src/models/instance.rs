@@ -1,6 +1,6 @@
 use std::mem::size_of;
 
-use cgmath::{Matrix3, Matrix4, Quaternion, Vector3};
+use cgmath::{Matrix3, Matrix4, Quaternion, SquareMatrix, Vector3};
 use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
 
 use crate::models::Vertex;
@@ -8,6 +8,16 @@ use crate::models::Vertex;
 pub struct Instance {
     pub position: Vector3<f32>,
     pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+    pub selected: bool,
+}
+
+impl Instance {
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.position)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
 }
 
 #[repr(C)]
@@ -15,16 +25,19 @@ pub struct Instance {
 pub struct InstanceRaw {
     model: [[f32; 4]; 4],
     normal: [[f32; 3]; 3],
+    highlight: f32,
 }
 
 impl From<&Instance> for InstanceRaw {
     fn from(src: &Instance) -> Self {
-        let model = Matrix4::from_translation(src.position) * Matrix4::from(src.rotation);
-        let normal = Matrix3::from(src.rotation);
+        // Normals need the inverse scale, or they'd lean towards the stretched axis
+        let normal = Matrix3::from(src.rotation)
+            * Matrix3::from_diagonal(Vector3::new(1.0 / src.scale.x, 1.0 / src.scale.y, 1.0 / src.scale.z));
 
         Self {
-            model: model.into(),
+            model: src.matrix().into(),
             normal: normal.into(),
+            highlight: if src.selected { 1.0 } else { 0.0 },
         }
     }
 }
@@ -77,6 +90,11 @@ impl Vertex for InstanceRaw {
                     offset: size_of::<[f32; 22]>() as BufferAddress,
                     shader_location: 11,
                     format: VertexFormat::Float32x3,
+                },
+                VertexAttribute {
+                    offset: size_of::<[f32; 25]>() as BufferAddress,
+                    shader_location: 12,
+                    format: VertexFormat::Float32,
                 }, ],
         }
     }
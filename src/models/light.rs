@@ -10,8 +10,8 @@ pub struct Light {
 #[derive(Debug, Copy, Clone, Pod, Zeroable)]
 pub struct LightUniform {
     pub position: [f32; 3],
-    // Due to uniforms requiring 16 byte (4 float) spacing, we need to use a padding field here
-    _padding: u32,
+    // Packed into what used to be padding after the position
+    pub ambient_strength: f32,
     pub color: [f32; 3],
     // Due to uniforms requiring 16 byte (4 float) spacing, we need to use a padding field here
     _padding2: u32,
@@ -21,7 +21,8 @@ impl LightUniform {
     pub const fn new(position: [f32; 3], color: [f32; 3]) -> Self {
         Self {
             position,
-            _padding: 0,
+            // We don't need (or want) much ambient light, so 0.1 is fine
+            ambient_strength: 0.1,
             color,
             _padding2: 0,
         }
@@ -0,0 +1,30 @@
+use std::mem::size_of;
+
+use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode};
+
+use crate::models::Vertex;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct LineVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+}
+
+impl LineVertex {
+    pub const fn new(position: [f32; 3], color: [f32; 3]) -> Self {
+        Self { position, color }
+    }
+}
+
+impl Vertex for LineVertex {
+    fn desc<'a>() -> VertexBufferLayout<'a> {
+        const ATTRIBUTES: [VertexAttribute; 2] = vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+        VertexBufferLayout {
+            array_stride: size_of::<Self>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
@@ -1,18 +1,46 @@
-pub use camera::{Camera, CameraController, CameraProjection, CameraUniform};
+pub use bounds::Aabb;
+pub use camera::{
+    Camera, CameraAnimation, CameraController, CameraProjection, CameraTrack, CameraUniform,
+};
 pub use configuration::CameraConfiguration;
-pub use draw::{DrawLight, DrawModel};
+pub use debug_draw::DebugDraw;
+pub use dynamic_buffer::DynamicVertexBuffer;
+pub use draw::{DrawLight, DrawLines, DrawModel, DrawOutline, DrawText};
+pub use frustum::Frustum;
+pub use gizmo::{Gizmo, GizmoMode};
 pub use instance::{Instance, InstanceRaw};
 pub use light::{Light, LightUniform};
-pub use model::{Material, Mesh, Model, ModelVertex};
-pub use texture::Texture;
+pub use lines::LineVertex;
+pub use model::{Material, MaterialUniform, Mesh, Model, ModelVertex};
+pub use outline::{Outline, OutlineUniform};
+pub use picking::{pick, Pick, Ray};
+pub use profiler::{PassProfiler, PassTiming};
+pub use scene::Scene;
+pub use stats::{loading_overlay, FrameStats};
+pub use text::{Text, TextVertex};
+pub use texture::{ColorSpace, SamplerSettings, Texture};
 pub use vertex::Vertex;
+pub use view_mode::ViewMode;
 
+mod bounds;
 mod camera;
 mod configuration;
+mod debug_draw;
 mod draw;
+mod dynamic_buffer;
+mod frustum;
+mod gizmo;
 mod instance;
 mod light;
+mod lines;
 mod model;
+mod outline;
+mod picking;
+mod profiler;
+mod scene;
+mod stats;
+mod text;
 mod texture;
 mod vertex;
+mod view_mode;
 
@@ -1,19 +1,65 @@
 use std::mem::size_of;
+use std::sync::Arc;
 
-use wgpu::{BindGroup, Buffer, BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode};
+use bytemuck::cast_slice;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{
+    BindGroup, Buffer, BufferAddress, BufferUsages, Device, Sampler, VertexAttribute, VertexBufferLayout,
+    VertexStepMode,
+};
 
-use crate::models::{Texture, Vertex};
+use crate::assets::Handle;
+use crate::models::{Aabb, Texture, Vertex};
 
 pub struct Model {
     pub meshes: Vec<Mesh>,
-    pub materials: Vec<Material>,
+    // Live in Assets::materials, meshes index into this
+    pub materials: Vec<Handle<Material>>,
+}
+
+impl Model {
+    pub fn triangles(&self) -> usize {
+        self.meshes.iter().map(|mesh| mesh.num_elements as usize / 3).sum()
+    }
 }
 
 pub struct Material {
     pub name: String,
-    pub diffuse_texture: Texture,
-    pub normal_texture: Texture,
+    // Only held so the textures stay loaded, the bind group is what's drawn with
+    pub diffuse_texture: Handle<Texture>,
+    pub normal_texture: Handle<Texture>,
+    // Per slot rather than the textures' own, materials sharing a texture may sample it differently
+    pub diffuse_sampler: Arc<Sampler>,
+    pub normal_sampler: Arc<Sampler>,
     pub bind_group: BindGroup,
+    pub buffer: Buffer,
+    pub uniform: MaterialUniform,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct MaterialUniform {
+    pub specular_strength: f32,
+    pub shininess: f32,
+    // Uniform buffers need to be at least 16 bytes on WebGL
+    _padding: [f32; 2],
+}
+
+impl MaterialUniform {
+    pub const fn new(specular_strength: f32, shininess: f32) -> Self {
+        Self {
+            specular_strength,
+            shininess,
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+impl Default for MaterialUniform {
+    // The shininess the lighting tutorial settled on, it started out at 32.0
+    fn default() -> Self {
+        Self::new(1.0, 2.0)
+    }
 }
 
 pub struct Mesh {
@@ -22,6 +68,28 @@ pub struct Mesh {
     pub index_buffer: Buffer,
     pub num_elements: u32,
     pub material: usize,
+    // CPU side copy of the geometry, used for picking and debug drawing
+    pub bounds: Aabb,
+    pub vertices: Vec<ModelVertex>,
+    pub indices: Vec<u32>,
+    // Un-indexed copy of the vertices for the barycentric wireframe, only there while
+    // it's shown on a device that can't rasterize polygons as lines
+    pub wireframe_buffer: Option<Buffer>,
+}
+
+impl Mesh {
+    pub fn create_wireframe_buffer(&self, device: &Device) -> Buffer {
+        let corners = self.indices
+            .iter()
+            .map(|index| self.vertices[*index as usize])
+            .collect::<Vec<_>>();
+
+        device.create_buffer_init(&BufferInitDescriptor {
+            label: Some(&format!("{:?} Wireframe Buffer", self.name)),
+            contents: cast_slice(&corners),
+            usage: BufferUsages::VERTEX,
+        })
+    }
 }
 
 #[repr(C)]
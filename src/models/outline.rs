@@ -0,0 +1,29 @@
+use wgpu::{BindGroup, Buffer};
+
+pub struct Outline {
+    pub bind_group: BindGroup,
+    pub buffer: Buffer,
+    pub uniform: OutlineUniform,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct OutlineUniform {
+    pub color: [f32; 4],
+    // The outline's width is in pixels, so the shader needs the size of the surface
+    pub viewport: [f32; 2],
+    pub width: f32,
+    // Due to uniforms requiring 16 byte (4 float) spacing, we need to use a padding field here
+    _padding: u32,
+}
+
+impl OutlineUniform {
+    pub const fn new(color: [f32; 4], width: f32, viewport: [f32; 2]) -> Self {
+        Self {
+            color,
+            viewport,
+            width,
+            _padding: 0,
+        }
+    }
+}
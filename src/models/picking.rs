@@ -0,0 +1,241 @@
+use cgmath::{InnerSpace, Matrix4, Point3, SquareMatrix, Transform, Vector3, Vector4};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+use crate::models::{Aabb, Instance, Model, ModelVertex};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    // Un-projects a cursor position through the inverse view projection matrix
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn from_cursor(
+        cursor: PhysicalPosition<f64>,
+        size: PhysicalSize<u32>,
+        view_proj: Matrix4<f32>,
+    ) -> Option<Self> {
+        let inverse = view_proj.invert()?;
+        let x = 2.0 * cursor.x as f32 / size.width as f32 - 1.0;
+        let y = 1.0 - 2.0 * cursor.y as f32 / size.height as f32;
+
+        // wgpu clip space depth runs from 0.0 at the near plane to 1.0 at the far plane
+        let unproject = |z: f32| {
+            let point = inverse * Vector4::new(x, y, z, 1.0);
+
+            Point3::from_homogeneous(point)
+        };
+
+        let origin = unproject(0.0);
+        let direction = (unproject(1.0) - origin).normalize();
+
+        Some(Self { origin, direction })
+    }
+
+    pub fn transform(&self, matrix: Matrix4<f32>) -> Self {
+        Self {
+            origin: matrix.transform_point(self.origin),
+            direction: matrix.transform_vector(self.direction),
+        }
+    }
+
+    // Möller–Trumbore, returns the distance along the ray to the hit
+    #[allow(clippy::many_single_char_names)]
+    pub fn intersect_triangle(&self, v0: Point3<f32>, v1: Point3<f32>, v2: Point3<f32>) -> Option<f32> {
+        let edge1 = v1 - v0;
+        let edge2 = v2 - v0;
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let s = self.origin - v0;
+        let u = s.dot(p) * inverse;
+
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = s.cross(edge1);
+        let v = self.direction.dot(q) * inverse;
+
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(q) * inverse;
+
+        (t > 0.0).then_some(t)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pick {
+    pub model: usize,
+    pub mesh: usize,
+    pub instance: usize,
+    pub distance: f32,
+    pub position: Point3<f32>,
+}
+
+// Tests every instance's meshes, first against their bounds and then triangle by triangle
+pub fn pick(ray: &Ray, model_index: usize, model: &Model, instances: &[Instance]) -> Option<Pick> {
+    let meshes = model.meshes.iter().map(|mesh| (&mesh.bounds, mesh.vertices.as_slice(), mesh.indices.as_slice()));
+
+    pick_geometry(ray, model_index, &meshes, instances)
+}
+
+// The CPU side of pick, meshes are their bounds, vertices and indices.
+// Option::is_none_or would read better, it needs rust 1.82
+#[allow(clippy::unnecessary_map_or)]
+fn pick_geometry<'a, M>(ray: &Ray, model_index: usize, meshes: &M, instances: &[Instance]) -> Option<Pick>
+    where M: Iterator<Item=(&'a Aabb, &'a [ModelVertex], &'a [u32])> + Clone
+{
+    let mut nearest: Option<Pick> = None;
+
+    for (instance_index, instance) in instances.iter().enumerate() {
+        let Some(to_local) = instance.matrix().invert() else {
+            continue;
+        };
+
+        let local = ray.transform(to_local);
+
+        for (mesh_index, (bounds, vertices, indices)) in meshes.clone().enumerate() {
+            if bounds.intersect(&local).is_none() {
+                continue;
+            }
+
+            for triangle in indices.chunks_exact(3) {
+                let [v0, v1, v2] = [triangle[0], triangle[1], triangle[2]]
+                    .map(|index| Point3::from(vertices[index as usize].position));
+
+                let Some(t) = local.intersect_triangle(v0, v1, v2) else {
+                    continue;
+                };
+
+                let position = instance.matrix().transform_point(local.origin + local.direction * t);
+                let distance = (position - ray.origin).magnitude();
+
+                if nearest.map_or(true, |nearest| distance < nearest.distance) {
+                    nearest = Some(Pick {
+                        model: model_index,
+                        mesh: mesh_index,
+                        instance: instance_index,
+                        distance,
+                        position,
+                    });
+                }
+            }
+        }
+    }
+
+    nearest
+}
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::Zeroable;
+    use cgmath::{Deg, One, Quaternion, Rotation3};
+
+    use super::*;
+    use crate::models::{Camera, CameraProjection};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn ray(origin: [f32; 3], direction: [f32; 3]) -> Ray {
+        Ray { origin: origin.into(), direction: Vector3::from(direction).normalize() }
+    }
+
+    fn instance(position: [f32; 3]) -> Instance {
+        Instance {
+            position: position.into(),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+            selected: false,
+        }
+    }
+
+    // A unit quad in the xy plane, facing +z
+    fn quad() -> (Aabb, Vec<ModelVertex>, Vec<u32>) {
+        let positions = [[-0.5, -0.5, 0.0], [0.5, -0.5, 0.0], [0.5, 0.5, 0.0], [-0.5, 0.5, 0.0]];
+        let vertices = positions.map(|position| ModelVertex { position, ..ModelVertex::zeroed() });
+
+        (Aabb::from_points(positions), vertices.to_vec(), vec![0, 1, 2, 0, 2, 3])
+    }
+
+    fn pick_quads(ray: &Ray, instances: &[Instance]) -> Option<Pick> {
+        let (bounds, vertices, indices) = quad();
+
+        pick_geometry(ray, 0, &std::iter::once((&bounds, vertices.as_slice(), indices.as_slice())), instances)
+    }
+
+    #[test]
+    fn cursor_in_the_middle_looks_ahead() {
+        let size = PhysicalSize::new(800, 600);
+        let camera = Camera::new((0.0, 0.0, 5.0), Deg(-90.0), Deg(0.0));
+        let projection = CameraProjection::new(size.width, size.height, Deg(45.0), 0.1, 100.0);
+        let view_proj = projection.calc_matrix() * camera.calc_matrix();
+
+        let ray = Ray::from_cursor(PhysicalPosition::new(400.0, 300.0), size, view_proj).unwrap();
+
+        assert!(close(ray.origin.x, 0.0) && close(ray.origin.y, 0.0) && close(ray.origin.z, 4.9), "{ray:?}");
+        assert!(close(ray.direction.x, 0.0) && close(ray.direction.y, 0.0) && close(ray.direction.z, -1.0), "{ray:?}");
+
+        // The top left corner leans left, up and still ahead
+        let corner = Ray::from_cursor(PhysicalPosition::new(0.0, 0.0), size, view_proj).unwrap();
+
+        assert!(corner.direction.x < 0.0 && corner.direction.y > 0.0 && corner.direction.z < 0.0, "{corner:?}");
+    }
+
+    #[test]
+    fn triangles_are_hit_in_front_only() {
+        let [v0, v1, v2] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]].map(Point3::from);
+
+        let hit = ray([0.25, 0.25, 2.0], [0.0, 0.0, -1.0]).intersect_triangle(v0, v1, v2);
+
+        assert!(hit.is_some_and(|t| close(t, 2.0)), "{hit:?}");
+        assert_eq!(ray([0.75, 0.75, 2.0], [0.0, 0.0, -1.0]).intersect_triangle(v0, v1, v2), None);
+        assert_eq!(ray([0.25, 0.25, 2.0], [0.0, 0.0, 1.0]).intersect_triangle(v0, v1, v2), None);
+        assert_eq!(ray([0.25, 0.25, 2.0], [1.0, 0.0, 0.0]).intersect_triangle(v0, v1, v2), None);
+    }
+
+    #[test]
+    fn the_nearest_instance_is_picked() {
+        let instances = [instance([0.0, 0.0, -4.0]), instance([0.0, 0.0, -2.0]), instance([3.0, 0.0, -1.0])];
+        let pick = pick_quads(&ray([0.1, 0.1, 0.0], [0.0, 0.0, -1.0]), &instances).unwrap();
+
+        assert_eq!(pick.instance, 1);
+        assert!(close(pick.distance, 2.0), "{pick:?}");
+        assert!(close(pick.position.z, -2.0), "{pick:?}");
+    }
+
+    #[test]
+    fn picks_go_through_the_instance_transform() {
+        let turned = [Instance {
+            rotation: Quaternion::from_angle_y(Deg(90.0)),
+            scale: Vector3::new(4.0, 1.0, 1.0),
+            ..instance([0.0, 0.0, 0.0])
+        }];
+
+        // Turned to face +x, the quad's 4 wide x axis now runs along z
+        let pick = pick_quads(&ray([5.0, 0.0, 1.5], [-1.0, 0.0, 0.0]), &turned).unwrap();
+
+        assert!(close(pick.distance, 5.0) && close(pick.position.z, 1.5), "{pick:?}");
+        assert_eq!(pick_quads(&ray([5.0, 0.0, 2.5], [-1.0, 0.0, 0.0]), &turned), None);
+    }
+
+    #[test]
+    fn misses_pick_nothing() {
+        let instances = [instance([0.0, 0.0, -2.0])];
+
+        assert_eq!(pick_quads(&ray([2.0, 0.0, 0.0], [0.0, 0.0, -1.0]), &instances), None);
+        assert_eq!(pick_quads(&ray([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]), &instances), None);
+    }
+}
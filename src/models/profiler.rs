@@ -0,0 +1,204 @@
+use std::mem::size_of;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use instant::Instant;
+use wgpu::{
+    Buffer, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoder, Device, Features, Maintain,
+    MapMode, QuerySet, QuerySetDescriptor, QueryType, Queue,
+};
+
+// Every pass takes a pair of timestamps, passes past this many aren't timed
+const MAX_PASSES: u32 = 8;
+
+// States of the readback buffer's mapping, written by the map callback
+const UNMAPPED: u8 = 0;
+const MAPPED: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+#[derive(Clone, Copy, Debug)]
+pub struct PassTiming {
+    pub label: &'static str,
+    pub milliseconds: f32,
+}
+
+struct Timestamps {
+    query_set: QuerySet,
+    // Queries resolve straight into it, they only need COPY_DST
+    readback_buffer: Buffer,
+    // Nanoseconds per tick
+    period: f32,
+    mapping: Arc<AtomicU8>,
+    // Labels of the frame whose timestamps are in the readback buffer, empty when it's free
+    in_flight: Vec<&'static str>,
+    copied: bool,
+}
+
+// Times each pass on the gpu when the device supports timestamp queries. Results
+// arrive a frame or more late. Without timestamps it falls back to how long the
+// cpu spent recording each pass, which says nothing about the gpu
+pub struct PassProfiler {
+    timestamps: Option<Timestamps>,
+    passes: Vec<(&'static str, Instant)>,
+    cpu_timings: Vec<PassTiming>,
+    timings: Vec<PassTiming>,
+}
+
+impl PassProfiler {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let timestamps = device.features().contains(Features::TIMESTAMP_QUERY).then(|| {
+            let size = BufferAddress::from(MAX_PASSES * 2) * size_of::<u64>() as BufferAddress;
+
+            Timestamps {
+                query_set: device.create_query_set(&QuerySetDescriptor {
+                    label: Some("pass timestamps"),
+                    ty: QueryType::Timestamp,
+                    count: MAX_PASSES * 2,
+                }),
+                readback_buffer: device.create_buffer(&BufferDescriptor {
+                    label: Some("pass timestamps - readback buffer"),
+                    size,
+                    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                period: queue.get_timestamp_period(),
+                mapping: Arc::new(AtomicU8::new(UNMAPPED)),
+                in_flight: Vec::new(),
+                copied: false,
+            }
+        });
+
+        Self {
+            timestamps,
+            passes: Vec::new(),
+            cpu_timings: Vec::new(),
+            timings: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub const fn gpu_timestamps(&self) -> bool {
+        self.timestamps.is_some()
+    }
+
+    // The latest complete set of timings
+    #[inline]
+    pub fn timings(&self) -> &[PassTiming] {
+        &self.timings
+    }
+
+    // Picks up timestamps from an earlier frame if they're ready
+    pub fn begin_frame(&mut self, device: &Device) {
+        self.passes.clear();
+        self.cpu_timings.clear();
+
+        let Some(timestamps) = self.timestamps.as_mut() else {
+            return;
+        };
+
+        device.poll(Maintain::Poll);
+
+        match timestamps.mapping.swap(UNMAPPED, Ordering::Acquire) {
+            MAPPED => {}
+            // Drop the frame, the buffer is free for the next one
+            MAP_FAILED => {
+                timestamps.in_flight.clear();
+                return;
+            }
+            _ => return,
+        }
+
+        {
+            let data = timestamps.readback_buffer.slice(..).get_mapped_range();
+            let ticks: &[u64] = bytemuck::cast_slice(&data);
+
+            self.timings = timestamps.in_flight
+                .iter()
+                .zip(ticks.chunks_exact(2))
+                .map(|(label, pair)| {
+                    #[allow(clippy::cast_precision_loss)]
+                    let nanoseconds = pair[1].saturating_sub(pair[0]) as f32 * timestamps.period;
+
+                    PassTiming { label, milliseconds: nanoseconds / 1_000_000.0 }
+                })
+                .collect();
+        }
+
+        timestamps.readback_buffer.unmap();
+        timestamps.in_flight.clear();
+
+        report("gpu", &self.timings);
+    }
+
+    pub fn begin_pass(&mut self, encoder: &mut CommandEncoder, label: &'static str) {
+        #[allow(clippy::cast_possible_truncation)]
+        let index = self.passes.len() as u32;
+
+        if let Some(timestamps) = self.timestamps.as_ref().filter(|_| index < MAX_PASSES) {
+            encoder.write_timestamp(&timestamps.query_set, index * 2);
+        }
+
+        self.passes.push((label, Instant::now()));
+    }
+
+    pub fn end_pass(&mut self, encoder: &mut CommandEncoder) {
+        let Some(&(label, start)) = self.passes.last() else {
+            return;
+        };
+
+        #[allow(clippy::cast_possible_truncation)]
+        let index = self.passes.len() as u32 - 1;
+
+        if let Some(timestamps) = self.timestamps.as_ref().filter(|_| index < MAX_PASSES) {
+            encoder.write_timestamp(&timestamps.query_set, index * 2 + 1);
+        }
+
+        self.cpu_timings.push(PassTiming {
+            label,
+            milliseconds: start.elapsed().as_secs_f32() * 1000.0,
+        });
+    }
+
+    // Resolves this frame's timestamps, unless the readback buffer still holds an earlier frame's
+    pub fn resolve(&mut self, encoder: &mut CommandEncoder) {
+        let Some(timestamps) = self.timestamps.as_mut() else {
+            self.timings.clone_from(&self.cpu_timings);
+            report("cpu", &self.timings);
+            return;
+        };
+
+        if !timestamps.in_flight.is_empty() || self.passes.is_empty() {
+            return;
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let queries = self.passes.len().min(MAX_PASSES as usize) as u32 * 2;
+
+        encoder.resolve_query_set(&timestamps.query_set, 0..queries, &timestamps.readback_buffer, 0);
+
+        timestamps.in_flight = self.passes.iter().map(|(label, _)| *label).take(MAX_PASSES as usize).collect();
+        timestamps.copied = true;
+    }
+
+    // Must follow the submit that contains the resolve
+    pub fn after_submit(&mut self) {
+        let Some(timestamps) = self.timestamps.as_mut().filter(|timestamps| timestamps.copied) else {
+            return;
+        };
+
+        let mapping = Arc::clone(&timestamps.mapping);
+
+        timestamps.copied = false;
+        timestamps.readback_buffer.slice(..).map_async(MapMode::Read, move |result| {
+            mapping.store(if result.is_ok() { MAPPED } else { MAP_FAILED }, Ordering::Release);
+        });
+    }
+}
+
+// One span per pass, carrying its time as a field
+fn report(source: &'static str, timings: &[PassTiming]) {
+    for timing in timings {
+        info_span!("pass", label = timing.label, source, milliseconds = timing.milliseconds)
+            .in_scope(|| trace!("{} pass took {:.3} ms", timing.label, timing.milliseconds));
+    }
+}
@@ -0,0 +1,42 @@
+use cgmath::{Deg, Euler, Quaternion, Vector3};
+use serde::Deserialize;
+
+use crate::models::Instance;
+
+// What to draw and where, loaded from a ron file under res
+#[derive(Clone, Debug, Deserialize)]
+pub struct Scene {
+    // The --model option wins over this
+    #[serde(default)]
+    pub model: Option<String>,
+    pub instances: Vec<SceneInstance>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct SceneInstance {
+    pub position: [f32; 3],
+    // Euler angles in degrees
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    #[serde(default = "SceneInstance::unit_scale")]
+    pub scale: [f32; 3],
+}
+
+impl SceneInstance {
+    const fn unit_scale() -> [f32; 3] {
+        [1.0, 1.0, 1.0]
+    }
+}
+
+impl From<&SceneInstance> for Instance {
+    fn from(instance: &SceneInstance) -> Self {
+        let [x, y, z] = instance.rotation;
+
+        Self {
+            position: instance.position.into(),
+            rotation: Quaternion::from(Euler::new(Deg(x), Deg(y), Deg(z))),
+            scale: Vector3::from(instance.scale),
+            selected: false,
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::VecDeque;
+
+use wgpu::AdapterInfo;
+
+use crate::models::{PassTiming, Text};
+
+// How many frames the averages and the graph cover
+const FRAME_HISTORY: usize = 120;
+
+const GRAPH_HEIGHT: f32 = 48.0;
+const GRAPH_BAR_WIDTH: f32 = 2.0;
+// A frame that takes this long fills the graph
+const GRAPH_MAX_SECONDS: f32 = 1.0 / 20.0;
+
+const PANEL: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const GREEN: [f32; 4] = [0.2, 0.9, 0.2, 1.0];
+const YELLOW: [f32; 4] = [0.9, 0.8, 0.1, 1.0];
+const RED: [f32; 4] = [0.9, 0.2, 0.2, 1.0];
+
+// Counters for the stats overlay, the render counts are from the previous frame
+#[derive(Debug, Default)]
+pub struct FrameStats {
+    pub visible_instances: usize,
+    pub culled_instances: usize,
+    pub triangles: usize,
+    pub draw_calls: usize,
+    pub pass_timings: Vec<PassTiming>,
+    // Whether the pass timings are from gpu timestamps or cpu fallback
+    pub gpu_timestamps: bool,
+    frame_times: VecDeque<f32>,
+}
+
+impl FrameStats {
+    pub fn record_frame(&mut self, seconds: f32) {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+
+        self.frame_times.push_back(seconds);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub fn average_frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+    }
+
+    pub fn max_frame_time(&self) -> f32 {
+        self.frame_times.iter().copied().fold(0.0, f32::max)
+    }
+
+    pub fn fps(&self) -> f32 {
+        let average = self.average_frame_time();
+
+        if average > 0.0 { 1.0 / average } else { 0.0 }
+    }
+
+    // Lays the panel out in the top left corner
+    pub fn overlay(&self, text: &mut Text, adapter: &AdapterInfo) {
+        let source = if self.gpu_timestamps { "gpu" } else { "cpu" };
+
+        let passes = self.pass_timings.iter().map(|timing| {
+            format!("{:<11} {:.3} ms {source}", timing.label, timing.milliseconds)
+        });
+
+        let lines = [
+            format!(
+                "{:.0} fps  {:.2} ms  max {:.2} ms",
+                self.fps(),
+                self.average_frame_time() * 1000.0,
+                self.max_frame_time() * 1000.0,
+            ),
+            format!("draw calls  {}", self.draw_calls),
+            format!("triangles   {}", self.triangles),
+            format!("instances   {} visible  {} culled", self.visible_instances, self.culled_instances),
+            adapter.name.clone(),
+            format!("{:?} {:?}", adapter.backend, adapter.device_type),
+        ]
+            .into_iter()
+            .chain(passes)
+            .collect::<Vec<_>>();
+
+        let margin = 8.0;
+        let padding = 6.0;
+
+        #[allow(clippy::cast_precision_loss)]
+        let columns = lines.iter().map(String::len).max().unwrap_or(0).max(FRAME_HISTORY / 5) as f32;
+        #[allow(clippy::cast_precision_loss)]
+        let text_height = lines.len() as f32 * Text::GLYPH_HEIGHT;
+
+        let width = columns.mul_add(Text::GLYPH_WIDTH, padding * 2.0);
+        let height = text_height + GRAPH_HEIGHT + padding * 3.0;
+
+        text.rect(margin, margin, width, height, PANEL);
+        text.text(margin + padding, margin + padding, &lines.join("\n"), WHITE);
+
+        // Newest frame on the right, bars grow up from the bottom of the panel
+        let graph_left = margin + padding;
+        let graph_bottom = margin + height - padding;
+
+        for (index, &seconds) in self.frame_times.iter().enumerate() {
+            let color = if seconds <= 1.0 / 59.0 {
+                GREEN
+            } else if seconds <= 1.0 / 29.0 {
+                YELLOW
+            } else {
+                RED
+            };
+
+            let bar_height = (seconds / GRAPH_MAX_SECONDS).min(1.0) * GRAPH_HEIGHT;
+
+            #[allow(clippy::cast_precision_loss)]
+            let x = (index + FRAME_HISTORY - self.frame_times.len()) as f32 * GRAPH_BAR_WIDTH;
+
+            text.rect(graph_left + x, graph_bottom - bar_height, GRAPH_BAR_WIDTH, bar_height, color);
+        }
+
+        // 60 fps marker
+        let target = (1.0 / 60.0) / GRAPH_MAX_SECONDS * GRAPH_HEIGHT;
+
+        #[allow(clippy::cast_precision_loss)]
+        text.rect(graph_left, graph_bottom - target, FRAME_HISTORY as f32 * GRAPH_BAR_WIDTH, 1.0, [1.0, 1.0, 1.0, 0.4]);
+    }
+}
+
+// A single line panel in the bottom left corner, out of the way of the stats
+pub fn loading_overlay(text: &mut Text, status: &str) {
+    let margin = 8.0;
+    let padding = 6.0;
+
+    #[allow(clippy::cast_precision_loss)]
+    let width = (status.len() as f32).mul_add(Text::GLYPH_WIDTH, padding * 2.0);
+    let height = Text::GLYPH_HEIGHT + padding * 2.0;
+    let top = text.viewport()[1] - margin - height;
+
+    text.rect(margin, top, width, height, PANEL);
+    text.text(margin + padding, top + padding, status, WHITE);
+}
@@ -0,0 +1,140 @@
+use std::mem::size_of;
+
+use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode};
+
+use crate::models::Vertex;
+
+// res/font.png holds printable ascii in a 16 by 6 grid, the last cell (DEL) is solid
+const ATLAS_COLUMNS: u32 = 16;
+const ATLAS_ROWS: u32 = 6;
+const FIRST_GLYPH: u32 = ' ' as u32;
+const SOLID_GLYPH: u32 = 0x7f;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct TextVertex {
+    // Clip space, text is laid out in pixels and converted when it's added
+    pub position: [f32; 2],
+    pub tex_coords: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl Vertex for TextVertex {
+    fn desc<'a>() -> VertexBufferLayout<'a> {
+        const ATTRIBUTES: [VertexAttribute; 3] = vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4];
+
+        VertexBufferLayout {
+            array_stride: size_of::<Self>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+// Collects text and solid rectangles for a single frame, positions are in pixels from the top left
+#[derive(Debug)]
+pub struct Text {
+    vertices: Vec<TextVertex>,
+    viewport: [f32; 2],
+}
+
+impl Text {
+    // The size of a cell in res/font.png, glyphs are drawn one to one
+    pub const GLYPH_WIDTH: f32 = 10.0;
+    pub const GLYPH_HEIGHT: f32 = 20.0;
+
+    pub const fn new(viewport: [f32; 2]) -> Self {
+        Self { vertices: Vec::new(), viewport }
+    }
+
+    #[inline]
+    pub fn vertices(&self) -> &[TextVertex] {
+        &self.vertices
+    }
+
+    #[inline]
+    pub const fn viewport(&self) -> [f32; 2] {
+        self.viewport
+    }
+
+    pub fn clear(&mut self, viewport: [f32; 2]) {
+        self.vertices.clear();
+        self.viewport = viewport;
+    }
+
+    // Anything outside printable ascii is drawn as '?'
+    pub fn text(&mut self, x: f32, y: f32, text: &str, color: [f32; 4]) {
+        for (row, line) in text.lines().enumerate() {
+            for (column, character) in line.chars().enumerate() {
+                let glyph = match character as u32 {
+                    // Spaces only advance
+                    FIRST_GLYPH => continue,
+                    glyph @ FIRST_GLYPH..=0x7e => glyph,
+                    _ => '?' as u32,
+                };
+
+                #[allow(clippy::cast_precision_loss)]
+                self.glyph(
+                    (column as f32).mul_add(Self::GLYPH_WIDTH, x),
+                    (row as f32).mul_add(Self::GLYPH_HEIGHT, y),
+                    glyph,
+                    color,
+                );
+            }
+        }
+    }
+
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) {
+        // Sample the middle of the solid cell so filtering never reaches its neighbours
+        let [u, v] = Self::cell(SOLID_GLYPH);
+        let center = [
+            0.5f32.mul_add(Self::cell_size()[0], u),
+            0.5f32.mul_add(Self::cell_size()[1], v),
+        ];
+
+        self.quad([x, y, x + width, y + height], [center[0], center[1], center[0], center[1]], color);
+    }
+
+    fn glyph(&mut self, x: f32, y: f32, glyph: u32, color: [f32; 4]) {
+        let [u, v] = Self::cell(glyph);
+        let [width, height] = Self::cell_size();
+
+        // Snapped to whole pixels so the atlas is sampled at texel centers
+        let (x, y) = (x.round(), y.round());
+
+        self.quad(
+            [x, y, x + Self::GLYPH_WIDTH, y + Self::GLYPH_HEIGHT],
+            [u, v, u + width, v + height],
+            color,
+        );
+    }
+
+    fn quad(&mut self, [left, top, right, bottom]: [f32; 4], [u0, v0, u1, v1]: [f32; 4], color: [f32; 4]) {
+        let [width, height] = self.viewport;
+        let vertex = |x: f32, y: f32, u: f32, v: f32| TextVertex {
+            position: [(x / width).mul_add(2.0, -1.0), (y / height).mul_add(-2.0, 1.0)],
+            tex_coords: [u, v],
+            color,
+        };
+
+        let top_left = vertex(left, top, u0, v0);
+        let top_right = vertex(right, top, u1, v0);
+        let bottom_left = vertex(left, bottom, u0, v1);
+        let bottom_right = vertex(right, bottom, u1, v1);
+
+        self.vertices.extend([top_left, bottom_left, bottom_right, top_left, bottom_right, top_right]);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn cell(glyph: u32) -> [f32; 2] {
+        let index = glyph - FIRST_GLYPH;
+        let [width, height] = Self::cell_size();
+
+        [(index % ATLAS_COLUMNS) as f32 * width, (index / ATLAS_COLUMNS) as f32 * height]
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn cell_size() -> [f32; 2] {
+        [1.0 / ATLAS_COLUMNS as f32, 1.0 / ATLAS_ROWS as f32]
+    }
+}
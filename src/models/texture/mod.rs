@@ -1,9 +1,86 @@
-use wgpu::{Sampler, TextureView};
+use std::sync::Arc;
+
+use wgpu::{AddressMode, FilterMode, Sampler, TextureFormat, TextureView};
+
+use crate::resources::TextureData;
 
 mod texture_static;
 
 pub struct Texture {
     pub texture: wgpu::Texture,
     pub view: TextureView,
-    pub sampler: Sampler,
+    // Shared with every texture sampled the same way, see SamplerCache
+    pub sampler: Arc<Sampler>,
+    // What textures from res were uploaded from, kept to upload them again after a device loss
+    pub data: Option<TextureData>,
+}
+
+// How an image's values are meant to be read. Colors are stored in srgb and sampled as linear,
+// normal maps and other data are linear already and have to be sampled as they are
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+impl ColorSpace {
+    #[inline]
+    pub const fn rgba8(self) -> TextureFormat {
+        match self {
+            Self::Srgb => TextureFormat::Rgba8UnormSrgb,
+            Self::Linear => TextureFormat::Rgba8Unorm,
+        }
+    }
+}
+
+// How a texture is sampled, for a texture or a material's slot. Textures repeat unless they're told not to,
+// like mtl files assume
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerSettings {
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+    pub mag_filter: FilterMode,
+    pub min_filter: FilterMode,
+    pub mipmap_filter: FilterMode,
+    // 1 is off, anything else is rounded up to a power of two up to 16 and only applies when every filter is linear
+    pub anisotropy: u8,
+    pub lod_min_clamp: f32,
+    pub lod_max_clamp: f32,
+}
+
+impl SamplerSettings {
+    pub const REPEAT: Self = Self {
+        address_mode_u: AddressMode::Repeat,
+        address_mode_v: AddressMode::Repeat,
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Nearest,
+        mipmap_filter: FilterMode::Nearest,
+        anisotropy: 1,
+        lod_min_clamp: 0.0,
+        lod_max_clamp: f32::MAX,
+    };
+
+    // What an mtl file's -clamp on asks for
+    pub const CLAMP: Self = Self {
+        address_mode_u: AddressMode::ClampToEdge,
+        address_mode_v: AddressMode::ClampToEdge,
+        ..Self::REPEAT
+    };
+}
+
+impl Default for SamplerSettings {
+    fn default() -> Self {
+        Self::REPEAT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba8_follows_the_color_space() {
+        assert_eq!(ColorSpace::Srgb.rgba8(), TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(ColorSpace::Linear.rgba8(), TextureFormat::Rgba8Unorm);
+    }
 }
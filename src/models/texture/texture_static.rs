@@ -1,20 +1,24 @@
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroU8};
+use std::sync::Arc;
 
 use image::GenericImageView;
 use wgpu::{
     AddressMode, CompareFunction, Device, Extent3d, FilterMode, ImageCopyTexture, ImageDataLayout,
-    Origin3d, Queue, SamplerDescriptor, SurfaceConfiguration, TextureAspect, TextureDescriptor,
+    Origin3d, Queue, Sampler, SamplerDescriptor, SurfaceConfiguration, TextureAspect, TextureDescriptor,
     TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor,
 };
 
-use crate::models::Texture;
+use crate::compressed::GpuImage;
+use crate::models::{ColorSpace, SamplerSettings, Texture};
 
 impl Texture {
-    pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+    // The stencil is used to outline the selected instance
+    pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
 
     pub fn create_depth_texture(
         device: &Device,
         config: &SurfaceConfiguration,
+        sample_count: u32,
         label: &str,
     ) -> Self {
         let desc = TextureDescriptor {
@@ -25,16 +29,21 @@ impl Texture {
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: TextureDimension::D2,
             format: Self::DEPTH_FORMAT,
-            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            // Multisampled depth is never sampled, and the gl backend can't make it a bindable texture
+            usage: if sample_count > 1 {
+                TextureUsages::RENDER_ATTACHMENT
+            } else {
+                TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING
+            },
         };
 
         let texture = device.create_texture(&desc);
         let view = texture.create_view(&TextureViewDescriptor::default());
 
-        let sampler = device.create_sampler(
+        let sampler = Arc::new(device.create_sampler(
             &SamplerDescriptor {
                 address_mode_u: AddressMode::ClampToEdge,
                 address_mode_v: AddressMode::ClampToEdge,
@@ -47,26 +56,45 @@ impl Texture {
                 lod_max_clamp: 100.0,
                 ..SamplerDescriptor::default()
             }
-        );
+        ));
 
-        Self { texture, view, sampler }
+        Self { texture, view, sampler, data: None }
     }
 
-    pub fn from_bytes(
+    // A color target the size of the surface, for multisampling or rendering without one
+    pub fn create_render_target(
         device: &Device,
-        queue: &Queue,
-        bytes: &[u8],
+        config: &SurfaceConfiguration,
+        sample_count: u32,
+        usage: TextureUsages,
         label: &str,
-    ) -> anyhow::Result<Self> {
-        let img = image::load_from_memory(bytes)?;
+    ) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size: Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format: config.format,
+            usage: TextureUsages::RENDER_ATTACHMENT | usage,
+        });
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let sampler = Arc::new(device.create_sampler(&SamplerDescriptor::default()));
 
-        Ok(Self::from_image(device, queue, &img, Some(label)))
+        Self { texture, view, sampler, data: None }
     }
 
     pub fn from_image(
         device: &Device,
         queue: &Queue,
         img: &image::DynamicImage,
+        color_space: ColorSpace,
+        sampler: Arc<Sampler>,
         label: Option<&str>,
     ) -> Self {
         let rgba = img.to_rgba8();
@@ -85,7 +113,7 @@ impl Texture {
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
-                format: TextureFormat::Rgba8UnormSrgb,
+                format: color_space.rgba8(),
                 usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
             }
         );
@@ -108,18 +136,81 @@ impl Texture {
 
         let view = texture.create_view(&TextureViewDescriptor::default());
 
-        let sampler = device.create_sampler(
+        Self { texture, view, sampler, data: None }
+    }
+
+    // Every level is uploaded as it is, compressed or not, so the device has to be able to sample the format
+    pub fn from_gpu_image(
+        device: &Device,
+        queue: &Queue,
+        image: &GpuImage,
+        sampler: Arc<Sampler>,
+        label: Option<&str>,
+    ) -> Self {
+        let info = image.format.describe();
+        let (block_width, block_height) = info.block_dimensions;
+
+        let texture = device.create_texture(
+            &TextureDescriptor {
+                label,
+                size: image.level_size(0),
+                mip_level_count: u32::try_from(image.levels.len()).unwrap_or(1),
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: image.format,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            }
+        );
+
+        for (level, data) in (0..).zip(&image.levels) {
+            // Copies are in whole blocks, even when the level is smaller than one
+            let size = image.level_size(level).physical_size(image.format);
+
+            queue.write_texture(
+                ImageCopyTexture {
+                    aspect: TextureAspect::All,
+                    texture: &texture,
+                    mip_level: level,
+                    origin: Origin3d::ZERO,
+                },
+                data,
+                ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(size.width / u32::from(block_width) * u32::from(info.block_size)),
+                    rows_per_image: NonZeroU32::new(size.height / u32::from(block_height)),
+                },
+                size,
+            );
+        }
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        Self { texture, view, sampler, data: None }
+    }
+
+    // Filtered images only, the cache in Assets is where these usually come from
+    pub fn create_sampler(device: &Device, settings: &SamplerSettings) -> Sampler {
+        let linear = [settings.mag_filter, settings.min_filter, settings.mipmap_filter]
+            .iter()
+            .all(|&filter| filter == FilterMode::Linear);
+
+        let anisotropy_clamp = (linear && settings.anisotropy > 1)
+            .then(|| NonZeroU8::new(settings.anisotropy.min(16).next_power_of_two()))
+            .flatten();
+
+        device.create_sampler(
             &SamplerDescriptor {
-                address_mode_u: AddressMode::ClampToEdge,
-                address_mode_v: AddressMode::ClampToEdge,
+                address_mode_u: settings.address_mode_u,
+                address_mode_v: settings.address_mode_v,
                 address_mode_w: AddressMode::ClampToEdge,
-                mag_filter: FilterMode::Linear,
-                min_filter: FilterMode::Nearest,
-                mipmap_filter: FilterMode::Nearest,
+                mag_filter: settings.mag_filter,
+                min_filter: settings.min_filter,
+                mipmap_filter: settings.mipmap_filter,
+                lod_min_clamp: settings.lod_min_clamp,
+                lod_max_clamp: settings.lod_max_clamp,
+                anisotropy_clamp,
                 ..SamplerDescriptor::default()
             }
-        );
-
-        Self { texture, view, sampler }
+        )
     }
 }
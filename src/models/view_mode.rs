@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum ViewMode {
+    #[default]
+    Lit,
+    Wireframe,
+    Normals,
+    TangentNormals,
+    Uvs,
+    Albedo,
+    Depth,
+    Overdraw,
+}
+
+impl ViewMode {
+    pub const DEBUG: [Self; 7] = [
+        Self::Wireframe,
+        Self::Normals,
+        Self::TangentNormals,
+        Self::Uvs,
+        Self::Albedo,
+        Self::Depth,
+        Self::Overdraw,
+    ];
+
+    pub const fn fragment_entry_point(self) -> &'static str {
+        match self {
+            Self::Lit => "fs_main",
+            Self::Wireframe => "fs_wireframe",
+            Self::Normals => "fs_normals",
+            Self::TangentNormals => "fs_tangent_normals",
+            Self::Uvs => "fs_uvs",
+            Self::Albedo => "fs_albedo",
+            Self::Depth => "fs_depth",
+            Self::Overdraw => "fs_overdraw",
+        }
+    }
+}
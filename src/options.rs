@@ -0,0 +1,179 @@
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use wgpu::{AdapterInfo, Backend, Backends, PowerPreference, PresentMode};
+
+// Settings the app binary takes from its command line, the web build runs with the defaults
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Options {
+    // An env filter like "info" or "learn_wgpu=debug,wgpu=warn", RUST_LOG is used when it's unset.
+    // The web build only understands a bare level
+    pub log_filter: Option<String>,
+    // Where to write a chrome trace event file, open it in Perfetto or chrome://tracing
+    pub trace_file: Option<PathBuf>,
+    // Files under res, the model falls back to the scene's and then to cube.obj
+    pub model: Option<String>,
+    pub scene: Option<String>,
+    // Directories or zips of res files, searched before res next to the executable, native only
+    pub res_dirs: Vec<PathBuf>,
+    // Physical pixels, the platform picks when it's unset
+    pub window_size: Option<(u32, u32)>,
+    pub fullscreen: bool,
+    // Overrides vsync, modes the surface doesn't support fall back to Fifo
+    pub present_mode: Option<PresentMode>,
+    pub vsync: bool,
+    pub backends: Backends,
+    // Picks the adapter instead of the power preference, native only
+    pub adapter: Option<AdapterSelector>,
+    pub power_preference: PowerPreference,
+    // Asks for the software adapter, for machines without a gpu and for tests
+    pub fallback_adapter: bool,
+    // Applies to the scene pass, the ui is drawn after the resolve
+    pub sample_count: u32,
+    // Renders offscreen without a window, then exits
+    pub headless: Option<Headless>,
+    // Pretends the device was lost before rendering this frame, to exercise State::recover
+    pub lose_device_at: Option<u32>,
+    // Reloads shaders and res files from the source tree when they change, native only
+    pub watch: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            log_filter: None,
+            trace_file: None,
+            model: None,
+            scene: None,
+            res_dirs: Vec::new(),
+            window_size: None,
+            fullscreen: false,
+            present_mode: None,
+            vsync: true,
+            backends: Backends::all(),
+            adapter: None,
+            power_preference: PowerPreference::default(),
+            fallback_adapter: false,
+            sample_count: 1,
+            headless: None,
+            lose_device_at: None,
+            watch: false,
+        }
+    }
+}
+
+impl Options {
+    #[must_use]
+    pub const fn present_mode(&self) -> PresentMode {
+        match self.present_mode {
+            Some(present_mode) => present_mode,
+            None if self.vsync => PresentMode::Fifo,
+            None => PresentMode::AutoNoVsync,
+        }
+    }
+}
+
+// Indices are positions in the --list-adapters listing for the same backends
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdapterSelector {
+    Index(usize),
+    Backend(Backend),
+    // Matched case insensitively against part of the name
+    Name(String),
+}
+
+impl AdapterSelector {
+    #[must_use]
+    pub fn matches(&self, index: usize, info: &AdapterInfo) -> bool {
+        match self {
+            Self::Index(selected) => *selected == index,
+            Self::Backend(backend) => info.backend == *backend,
+            Self::Name(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+        }
+    }
+}
+
+// Anything that isn't an index or a backend is taken as a name
+impl FromStr for AdapterSelector {
+    type Err = Infallible;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        let selector = selector.trim().to_lowercase();
+
+        if let Ok(index) = selector.parse() {
+            return Ok(Self::Index(index));
+        }
+
+        Ok(match selector.as_str() {
+            "vulkan" => Self::Backend(Backend::Vulkan),
+            "metal" => Self::Backend(Backend::Metal),
+            "dx12" => Self::Backend(Backend::Dx12),
+            "dx11" => Self::Backend(Backend::Dx11),
+            "gl" => Self::Backend(Backend::Gl),
+            "webgpu" => Self::Backend(Backend::BrowserWebGpu),
+            _ => Self::Name(selector),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Headless {
+    pub frames: u32,
+    // Frames are written here as numbered pngs, it's created if it's missing
+    pub output: PathBuf,
+    // Batch rendering has no window to size itself after
+    pub size: (u32, u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::DeviceType;
+
+    use super::*;
+
+    fn adapter(name: &str, backend: Backend) -> AdapterInfo {
+        AdapterInfo {
+            name: name.to_string(),
+            vendor: 0,
+            device: 0,
+            device_type: DeviceType::DiscreteGpu,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend,
+        }
+    }
+
+    fn selector(text: &str) -> AdapterSelector {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn selectors_parse_indices_backends_and_names() {
+        assert_eq!(selector("1"), AdapterSelector::Index(1));
+        assert_eq!(selector(" Vulkan "), AdapterSelector::Backend(Backend::Vulkan));
+        assert_eq!(selector("GL"), AdapterSelector::Backend(Backend::Gl));
+        assert_eq!(selector("GeForce RTX"), AdapterSelector::Name("geforce rtx".to_string()));
+    }
+
+    #[test]
+    fn names_match_part_of_the_name_in_any_case() {
+        let info = adapter("NVIDIA GeForce RTX 3080", Backend::Vulkan);
+
+        assert!(selector("geforce").matches(0, &info));
+        assert!(AdapterSelector::Name("NVIDIA".to_string()).matches(0, &info));
+        assert!(AdapterSelector::Name("rtx 3080".to_string()).matches(0, &info));
+        assert!(!AdapterSelector::Name("Radeon".to_string()).matches(0, &info));
+    }
+
+    #[test]
+    fn indices_and_backends_match_only_their_own() {
+        let info = adapter("llvmpipe", Backend::Gl);
+
+        assert!(selector("2").matches(2, &info));
+        assert!(!selector("2").matches(1, &info));
+        assert!(selector("gl").matches(5, &info));
+        assert!(!selector("vulkan").matches(5, &info));
+    }
+}
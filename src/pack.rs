@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use image::{DynamicImage, ImageOutputFormat};
+
+use crate::baked::{self, MESH_EXTENSION, TEXTURE_EXTENSION};
+use crate::compressed;
+use crate::loader::Progress;
+use crate::resources::{read_model_data, TextureImage};
+use crate::sources::DirSource;
+
+#[derive(Clone, Debug)]
+pub struct PackOptions {
+    // Materials and textures are read from the directory each model is in
+    pub inputs: Vec<PathBuf>,
+    // Textures keep the paths they had relative to the model, with a new extension
+    pub output: PathBuf,
+    pub texture_format: PackedTextureFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackedTextureFormat {
+    // Raw pixels, ready to upload without decoding
+    Rgba8,
+    // Smaller, but decoded at load time like any other image
+    Png,
+}
+
+impl PackedTextureFormat {
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::Rgba8 => TEXTURE_EXTENSION,
+            Self::Png => "png",
+        }
+    }
+
+    fn encode(self, image: &DynamicImage) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Rgba8 => Ok(baked::write_texture(image)),
+            Self::Png => {
+                let mut data = Vec::new();
+
+                image.write_to(&mut Cursor::new(&mut data), ImageOutputFormat::Png)?;
+
+                Ok(data)
+            }
+        }
+    }
+}
+
+/// Converts obj models into the packed mesh format, along with the textures their materials use.
+/// Tangents are worked out here so loading a packed model doesn't have to.
+///
+/// Returns the files written and their sizes in bytes, in the order they were written.
+///
+/// # Errors
+///
+/// When a model or one of its textures can't be read, or the packed files can't be written.
+pub fn pack(options: &PackOptions) -> anyhow::Result<Vec<(PathBuf, usize)>> {
+    fs::create_dir_all(&options.output)
+        .with_context(|| format!("couldn't create {}", options.output.display()))?;
+
+    let mut files = Vec::new();
+
+    for input in &options.inputs {
+        pack_model(input, options, &mut files).with_context(|| format!("couldn't pack {}", input.display()))?;
+    }
+
+    Ok(files)
+}
+
+fn pack_model(input: &Path, options: &PackOptions, files: &mut Vec<(PathBuf, usize)>) -> anyhow::Result<()> {
+    let (Some(directory), Some(file_name)) = (input.parent(), input.file_name().and_then(OsStr::to_str)) else {
+        bail!("not a file name");
+    };
+
+    // Already packed models go through as they are, only a new texture format is applied
+    if !Path::new(file_name).extension().is_some_and(|extension| {
+        extension.eq_ignore_ascii_case("obj") || extension.eq_ignore_ascii_case(MESH_EXTENSION)
+    }) {
+        bail!("only obj models and packed .{MESH_EXTENSION} models can be packed");
+    }
+
+    // Compressed textures are left compressed, whatever the adapter the packed files end up used with
+    let source = DirSource::new(directory.to_path_buf());
+    let mut model = pollster::block_on(read_model_data(&source, file_name, compressed::FEATURES, &Progress::default()))?;
+
+    // Materials often share textures, each one is only written once
+    let mut written = HashSet::new();
+
+    for material in &mut model.materials {
+        for texture in [&mut material.diffuse_texture, &mut material.normal_texture] {
+            let original = directory.join(&texture.file_name);
+
+            let data = match &texture.image {
+                // Already as ready for the gpu as they get, they're copied as they are
+                TextureImage::Gpu(_) => None,
+                TextureImage::Decoded(image) => {
+                    texture.file_name = Path::new(&texture.file_name)
+                        .with_extension(options.texture_format.extension())
+                        .to_string_lossy()
+                        .into_owned();
+
+                    Some(image)
+                }
+            };
+
+            if written.insert(texture.file_name.clone()) {
+                let data = match data {
+                    Some(image) => options.texture_format.encode(image)?,
+                    None => fs::read(&original).with_context(|| format!("couldn't read {}", original.display()))?,
+                };
+
+                files.push(write(options.output.join(&texture.file_name), &data)?);
+            }
+        }
+    }
+
+    let output = options.output.join(Path::new(file_name).with_extension(MESH_EXTENSION));
+
+    files.push(write(output, &baked::write_model(&model))?);
+
+    Ok(())
+}
+
+fn write(path: PathBuf, data: &[u8]) -> anyhow::Result<(PathBuf, usize)> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(&path, data).with_context(|| format!("couldn't write {}", path.display()))?;
+
+    Ok((path, data.len()))
+}
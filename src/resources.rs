@@ -1,80 +1,201 @@
-#[cfg(not(target_arch = "wasm32"))]
-use std::fs;
-#[cfg(not(target_arch = "wasm32"))]
-use std::fs::read;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::io::{BufReader, Cursor};
-#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
+use std::sync::Arc;
 
 use bytemuck::cast_slice;
-use tobj::{load_mtl_buf, load_obj_buf_async, LoadOptions, Material as ObjMaterial, Model as ObjModel};
-use wgpu::{BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindingResource, BufferUsages, Device, Queue};
+use anyhow::{anyhow, bail, ensure, Context};
+use image::{DynamicImage, Rgba, RgbaImage};
+use tobj::{load_mtl_buf, load_obj_buf_async, LoadError, LoadOptions, Mesh as ObjMesh};
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindingResource, Buffer, BufferUsages, Device, Features,
+    Queue, Sampler,
+};
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 
-use crate::models::{Material, Mesh, Model, ModelVertex, Texture};
+use crate::assets::{Assets, Handle};
+use crate::baked;
+use crate::compressed::{self, GpuImage};
+use crate::error::InitError;
+use crate::input::{GamepadSettings, InputBindings};
+use crate::loader::Progress;
+use crate::models::{
+    Aabb, CameraTrack, ColorSpace, Material, MaterialUniform, Mesh, Model, ModelVertex, SamplerSettings, Scene,
+    Texture,
+};
+use crate::sources::AssetSource;
+
+// Context on errors from fetching a file, which tells them apart from errors parsing it
+#[derive(Debug)]
+pub struct NotFound(pub String);
+
+impl Display for NotFound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "couldn't read {}", self.0)
+    }
+}
 
-#[cfg(target_arch = "wasm32")]
-fn format_url(file_name: &str) -> reqwest::Url {
-    let window = web_sys::window().unwrap();
-    let location = window.location();
+#[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
+#[instrument(skip(source))]
+pub async fn load_string(source: &dyn AssetSource, file_name: &str) -> anyhow::Result<String> {
+    let data = load_binary(source, file_name).await?;
 
-    let base = reqwest::Url::parse(&format!(
-        "{}/{}/",
-        location.origin().unwrap(),
-        option_env!("RES_PATH").unwrap_or("res"),
-    )).unwrap();
+    String::from_utf8(data).with_context(|| format!("{file_name} isn't utf-8"))
+}
 
-    base.join(file_name).unwrap()
+#[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
+#[instrument(skip(source))]
+pub async fn load_binary(source: &dyn AssetSource, file_name: &str) -> anyhow::Result<Vec<u8>> {
+    let data = match source.read(file_name).await {
+        Ok(Some(data)) => Ok(data),
+        Ok(None) => Err(anyhow!("it isn't in any of the res sources")),
+        Err(err) => Err(err),
+    };
+
+    data.with_context(|| NotFound(file_name.to_owned()))
 }
 
-#[cfg_attr(not(target_arch = "wasm32"), allow(clippy::unused_async))]
 #[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
-pub async fn load_string(file_name: &str) -> anyhow::Result<String> {
-    cfg_if! {
-        if #[cfg(target_arch = "wasm32")] {
-            let url = format_url(file_name);
-            let txt = reqwest::get(url)
-                .await?
-                .text()
-                .await?;
-        } else {
-            let path = Path::new(env!("OUT_DIR"))
-                .join("res")
-                .join(file_name);
-            let txt = fs::read_to_string(path)?;
-        }
-    }
+pub async fn load_camera_track(source: &dyn AssetSource, file_name: &str) -> anyhow::Result<CameraTrack> {
+    let track_text = load_string(source, file_name).await?;
+    let track: CameraTrack = ron::from_str(&track_text)?;
+
+    track.validate().with_context(|| format!("{file_name} isn't a playable track"))?;
 
-    Ok(txt)
+    Ok(track)
 }
 
-#[cfg_attr(not(target_arch = "wasm32"), allow(clippy::unused_async))]
 #[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
-pub async fn load_binary(file_name: &str) -> anyhow::Result<Vec<u8>> {
-    cfg_if! {
-        if #[cfg(target_arch = "wasm32")] {
-            let url = format_url(file_name);
-            let data = reqwest::get(url).await?.bytes().await?.to_vec();
-        } else {
-            let path = Path::new(env!("OUT_DIR"))
-                .join("res")
-                .join(file_name);
-
-            let data = read(path)?;
-        }
+pub async fn load_gamepad_settings(source: &dyn AssetSource, file_name: &str) -> anyhow::Result<GamepadSettings> {
+    let settings_text = load_string(source, file_name).await?;
+
+    Ok(ron::from_str(&settings_text)?)
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
+pub async fn load_input_bindings(source: &dyn AssetSource, file_name: &str) -> anyhow::Result<InputBindings> {
+    let bindings_text = load_string(source, file_name).await?;
+
+    Ok(ron::from_str(&bindings_text)?)
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
+pub async fn load_scene(source: &dyn AssetSource, file_name: &str) -> anyhow::Result<Scene> {
+    let scene_text = load_string(source, file_name).await?;
+
+    Ok(ron::from_str(&scene_text)?)
+}
+
+// What a model file holds once it's read and decoded. Nothing is on the gpu yet,
+// so it can be loaded off the main thread and uploaded by upload_model when it's done
+pub struct ModelData {
+    pub meshes: Vec<MeshData>,
+    pub materials: Vec<MaterialData>,
+}
+
+pub struct MeshData {
+    pub vertices: Vec<ModelVertex>,
+    pub indices: Vec<u32>,
+    pub material: usize,
+    pub bounds: Aabb,
+}
+
+pub struct MaterialData {
+    pub name: String,
+    pub diffuse_texture: TextureData,
+    pub normal_texture: TextureData,
+}
+
+pub struct TextureData {
+    // Registered under it along with the color space, see texture_key
+    pub file_name: String,
+    pub color_space: ColorSpace,
+    // How the material's slot samples it, the texture's own sampler is whatever it was first uploaded with
+    pub sampler: SamplerSettings,
+    pub image: TextureImage,
+}
+
+pub enum TextureImage {
+    Decoded(DynamicImage),
+    // From compressed texture files, or decoded from them when the device can't sample their format
+    Gpu(GpuImage),
+}
+
+// Every file read along the way is counted in the progress, the model's textures included.
+// Files from the pack binary are told apart by their extension, anything else is taken as obj.
+// The features are the device's, they decide which compressed textures have to be decoded
+#[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
+#[instrument(skip(source, features, progress))]
+pub async fn read_model_data(
+    source: &dyn AssetSource,
+    file_name: &str,
+    features: Features,
+    progress: &Progress,
+) -> anyhow::Result<ModelData> {
+    if Path::new(file_name).extension().is_some_and(|extension| extension.eq_ignore_ascii_case(baked::MESH_EXTENSION)) {
+        read_baked_model(source, file_name, features, progress).await
+    } else {
+        read_obj_model(source, file_name, features, progress).await
     }
+}
 
-    Ok(data)
+#[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
+async fn read_baked_model(
+    source: &dyn AssetSource,
+    file_name: &str,
+    features: Features,
+    progress: &Progress,
+) -> anyhow::Result<ModelData> {
+    let data = load_binary(source, file_name).await?;
+
+    progress.add(data.len());
+
+    let baked = baked::read_model(&data).with_context(|| format!("couldn't read {file_name}"))?;
+    let mut materials = Vec::with_capacity(baked.materials.len());
+
+    for material in baked.materials {
+        let diffuse_texture = read_texture_data(
+            source, &material.diffuse_texture, ColorSpace::Srgb, material.diffuse_sampler, features, progress,
+        ).await?;
+
+        let normal_texture = read_texture_data(
+            source, &material.normal_texture, ColorSpace::Linear, material.normal_sampler, features, progress,
+        ).await?;
+
+        materials.push(MaterialData { name: material.name, diffuse_texture, normal_texture });
+    }
+
+    Ok(ModelData { meshes: baked.meshes, materials })
 }
 
 #[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
-pub async fn load_model(
+async fn read_obj_model(
+    source: &dyn AssetSource,
     file_name: &str,
-    device: &Device,
-    queue: &Queue,
-    layout: &BindGroupLayout,
-) -> anyhow::Result<Model> {
-    let obj_text = load_string(file_name).await?;
+    features: Features,
+    progress: &Progress,
+) -> anyhow::Result<ModelData> {
+    let obj_text = load_string(source, file_name).await?;
+
+    progress.add(obj_text.len());
+
+    // Material files are read before parsing, tobj only has a bare error for one that's missing
+    let mut mtl_texts = HashMap::new();
+
+    for line in obj_text.lines() {
+        let mut words = line.split_whitespace();
+
+        if let (Some("mtllib"), Some(mtl_file)) = (words.next(), words.next()) {
+            if !mtl_texts.contains_key(mtl_file) {
+                let mtl_text = load_string(source, mtl_file).await?;
+
+                progress.add(mtl_text.len());
+                mtl_texts.insert(mtl_file.to_owned(), mtl_text);
+            }
+        }
+    }
+
     let obj_cursor = Cursor::new(obj_text);
     let mut obj_reader = BufReader::new(obj_cursor);
 
@@ -85,38 +206,297 @@ pub async fn load_model(
             single_index: true,
             ..LoadOptions::default()
         },
-        |material_path| async move {
-            let mat_text = load_string(&material_path).await.unwrap();
+        |material_path| {
+            let mat_text = mtl_texts.get(&material_path);
 
-            load_mtl_buf(&mut BufReader::new(Cursor::new(mat_text)))
+            async move {
+                load_mtl_buf(&mut BufReader::new(mat_text.ok_or(LoadError::OpenFileFailed)?.as_bytes()))
+            }
         },
     ).await?;
 
-    let mut materials = Vec::new();
+    let obj_materials = obj_materials?;
+
+    let meshes = models
+        .iter()
+        .map(|model| model_to_mesh(&model.mesh))
+        .collect::<Vec<_>>();
+
+    // Drawing indexes materials by mesh, so a bad index would only fail at the first frame.
+    // Checked before the textures are read, which is most of the work
+    ensure!(!meshes.is_empty(), "{file_name} has no meshes");
 
-    for material in obj_materials? {
-        materials.push(model_to_material(device, queue, layout, material).await?);
+    if let Some((mesh, model)) = meshes.iter().zip(&models).find(|(mesh, _)| mesh.material >= obj_materials.len()) {
+        bail!("mesh {} uses material {}, {file_name} has {}", model.name, mesh.material, obj_materials.len());
     }
 
-    let meshes = models
+    let mut materials = Vec::with_capacity(obj_materials.len());
+
+    for material in obj_materials {
+        let (diffuse_file, diffuse_sampler) = texture_options(&material.diffuse_texture);
+        let (normal_file, normal_sampler) = texture_options(&material.normal_texture);
+
+        let diffuse_texture = read_texture_data(
+            source, &diffuse_file, ColorSpace::Srgb, diffuse_sampler, features, progress,
+        ).await?;
+
+        let normal_texture = read_texture_data(
+            source, &normal_file, ColorSpace::Linear, normal_sampler, features, progress,
+        ).await?;
+
+        materials.push(MaterialData { name: material.name, diffuse_texture, normal_texture });
+    }
+
+    Ok(ModelData { meshes, materials })
+}
+
+// tobj leaves the options in front of a map's file name. Only -clamp matters for drawing,
+// the others are skipped along with their values. A value that's missing leaves the file name alone
+fn texture_options(map: &str) -> (String, SamplerSettings) {
+    let mut sampler = SamplerSettings::default();
+    let mut words = map.split_whitespace().peekable();
+
+    while let Some(option) = words.next_if(|word| word.starts_with('-')) {
+        let switch = |word: &&str| *word == "on" || *word == "off";
+        let number = |word: &&str| word.parse::<f32>().is_ok();
+
+        match option {
+            "-clamp" => match words.next_if(switch) {
+                Some("on") => sampler = SamplerSettings::CLAMP,
+                Some(_) => sampler = SamplerSettings::REPEAT,
+                None => warn!("-clamp without on or off in {map:?}"),
+            },
+            // Up to three numbers, the ones left out have defaults
+            "-mm" | "-o" | "-s" | "-t" => {
+                for _ in 0..3 {
+                    words.next_if(number);
+                }
+            }
+            "-blendu" | "-blendv" | "-cc" => {
+                words.next_if(switch);
+            }
+            "-bm" | "-boost" | "-texres" => {
+                words.next_if(number);
+            }
+            "-imfchan" | "-type" => {
+                words.next();
+            }
+            _ => warn!("unknown texture option {option} in {map:?}"),
+        }
+    }
+
+    (words.collect::<Vec<_>>().join(" "), sampler)
+}
+
+// Stands in for models while they load, a grey cube about the size of res/cube.obj
+pub fn placeholder_model() -> ModelData {
+    // The normal and the two axes across each face, in counter clockwise order seen from outside
+    const FACES: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ];
+
+    const CORNERS: [(f32, f32); 4] = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+
+    let mut mesh = ObjMesh::default();
+
+    for (face, (normal, u, v)) in (0_u32..).zip(FACES) {
+        for (s, t) in CORNERS {
+            mesh.positions.extend((0..3).map(|axis| t.mul_add(v[axis], s.mul_add(u[axis], normal[axis]))));
+            mesh.normals.extend(normal);
+            mesh.texcoords.extend([s.mul_add(0.5, 0.5), t.mul_add(-0.5, 0.5)]);
+        }
+
+        mesh.indices.extend([0, 1, 2, 0, 2, 3].map(|index| face * 4 + index));
+    }
+
+    let texture = |file_name: &str, color_space, color| TextureData {
+        file_name: file_name.to_owned(),
+        color_space,
+        sampler: SamplerSettings::default(),
+        image: TextureImage::Decoded(DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba(color)))),
+    };
+
+    ModelData {
+        meshes: vec![model_to_mesh(&mesh)],
+        materials: vec![MaterialData {
+            name: "placeholder".to_owned(),
+            diffuse_texture: texture("placeholder diffuse", ColorSpace::Srgb, [128, 128, 128, 255]),
+            normal_texture: texture("placeholder normal", ColorSpace::Linear, [128, 128, 255, 255]),
+        }],
+    }
+}
+
+// The gpu half of loading a model. Materials are new, their textures are shared through the registry
+pub fn upload_model(
+    file_name: &str,
+    data: ModelData,
+    assets: &mut Assets,
+    device: &Device,
+    queue: &Queue,
+    layout: &BindGroupLayout,
+) -> Model {
+    let meshes = data.meshes
         .into_iter()
-        .map(|model| model_to_mesh(&model, file_name, device))
-        .collect::<Vec<_>>();
+        .map(|mesh| upload_mesh(mesh, file_name, device))
+        .collect();
+
+    let materials = data.materials
+        .into_iter()
+        .map(|material| upload_material(file_name, material, assets, device, queue, layout))
+        .collect();
 
-    Ok(Model { meshes, materials })
+    Model { meshes, materials }
 }
 
-#[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
-async fn model_to_material(
+// Keyed by the model's file as well, names only have to be unique within a file
+fn upload_material(
+    file_name: &str,
+    material: MaterialData,
+    assets: &mut Assets,
     device: &Device,
     queue: &Queue,
     layout: &BindGroupLayout,
-    material: ObjMaterial,
-) -> anyhow::Result<Material> {
-    let diffuse_texture = load_texture(&material.diffuse_texture, device, queue).await?;
-    let normal_texture = load_texture(&material.normal_texture, device, queue).await?;
+) -> Handle<Material> {
+    let diffuse_sampler = assets.samplers.get(device, material.diffuse_texture.sampler);
+    let normal_sampler = assets.samplers.get(device, material.normal_texture.sampler);
+    let diffuse_handle = upload_texture(material.diffuse_texture, assets, device, queue);
+    let normal_handle = upload_texture(material.normal_texture, assets, device, queue);
+
+    let uniform = MaterialUniform::default();
+    let buffer = material_buffer(&material.name, uniform, device);
 
-    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+    let bind_group = material_bind_group(
+        device,
+        layout,
+        &assets.textures[diffuse_handle],
+        &diffuse_sampler,
+        &assets.textures[normal_handle],
+        &normal_sampler,
+        &buffer,
+    );
+
+    assets.materials.insert(&format!("{file_name}#{}", material.name), Material {
+        name: material.name,
+        diffuse_texture: diffuse_handle,
+        normal_texture: normal_handle,
+        diffuse_sampler,
+        normal_sampler,
+        bind_group,
+        buffer,
+        uniform,
+    })
+}
+
+// Material values can be tweaked at runtime
+fn material_buffer(name: &str, uniform: MaterialUniform, device: &Device) -> Buffer {
+    device.create_buffer_init(&BufferInitDescriptor {
+        label: Some(&format!("{name:?} Material Buffer")),
+        contents: cast_slice(&[uniform]),
+        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+    })
+}
+
+// Textures already in the registry win over the freshly decoded copy, reloading them is up to the watcher
+fn upload_texture(texture: TextureData, assets: &mut Assets, device: &Device, queue: &Queue) -> Handle<Texture> {
+    let key = texture_key(&texture.file_name, texture.color_space);
+
+    if let Some(handle) = assets.textures.acquire(&key) {
+        return handle;
+    }
+
+    let sampler = assets.samplers.get(device, texture.sampler);
+
+    assets.textures.insert(&key, upload_image(texture, sampler, device, queue))
+}
+
+// The texture keeps the data, see Texture::data
+pub fn upload_image(data: TextureData, sampler: Arc<Sampler>, device: &Device, queue: &Queue) -> Texture {
+    let label = Some(data.file_name.as_str());
+
+    let texture = match &data.image {
+        TextureImage::Decoded(image) => Texture::from_image(device, queue, image, data.color_space, sampler, label),
+        TextureImage::Gpu(image) => Texture::from_gpu_image(device, queue, image, sampler, label),
+    };
+
+    Texture { data: Some(data), ..texture }
+}
+
+// Everything is uploaded again to a new device from what was kept on the cpu, handles stay as they were.
+// A texture the device can't sample is checked for first, so failing leaves the assets as they were
+pub fn reupload(assets: &mut Assets, device: &Device, queue: &Queue, layout: &BindGroupLayout) -> Result<(), InitError> {
+    for data in assets.textures.iter().filter_map(|texture| texture.data.as_ref()) {
+        if let TextureImage::Gpu(image) = &data.image {
+            if !device.features().contains(image.format.describe().required_features) {
+                let err = anyhow!("it's {:?}, the new device can't sample it", image.format);
+
+                return Err(InitError::asset(&data.file_name, err));
+            }
+        }
+    }
+
+    let old_samplers = std::mem::take(&mut assets.samplers);
+    let samplers = &mut assets.samplers;
+
+    // Materials don't keep their slots' settings, the old samplers still know them
+    let mut resample = |sampler: &Arc<Sampler>| {
+        samplers.get(device, old_samplers.settings(sampler).unwrap_or_default())
+    };
+
+    for texture in assets.textures.iter_mut() {
+        if let Some(data) = texture.data.take() {
+            let sampler = resample(&texture.sampler);
+
+            *texture = upload_image(data, sampler, device, queue);
+        }
+    }
+
+    let textures = &assets.textures;
+
+    for material in assets.materials.iter_mut() {
+        material.diffuse_sampler = resample(&material.diffuse_sampler);
+        material.normal_sampler = resample(&material.normal_sampler);
+        material.buffer = material_buffer(&material.name, material.uniform, device);
+        material.bind_group = material_bind_group(
+            device,
+            layout,
+            &textures[material.diffuse_texture],
+            &material.diffuse_sampler,
+            &textures[material.normal_texture],
+            &material.normal_sampler,
+            &material.buffer,
+        );
+    }
+
+    for mesh in assets.models.iter_mut().flat_map(|model| &mut model.meshes) {
+        let data = MeshData {
+            vertices: std::mem::take(&mut mesh.vertices),
+            indices: std::mem::take(&mut mesh.indices),
+            material: mesh.material,
+            bounds: mesh.bounds,
+        };
+
+        *mesh = upload_mesh(data, &mesh.name, device);
+    }
+
+    Ok(())
+}
+
+// Also rebuilt when either texture is reloaded
+pub fn material_bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    diffuse_texture: &Texture,
+    diffuse_sampler: &Sampler,
+    normal_texture: &Texture,
+    normal_sampler: &Sampler,
+    buffer: &Buffer,
+) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
         layout,
         entries: &[
             BindGroupEntry {
@@ -125,7 +505,7 @@ async fn model_to_material(
             },
             BindGroupEntry {
                 binding: 1,
-                resource: BindingResource::Sampler(&diffuse_texture.sampler),
+                resource: BindingResource::Sampler(diffuse_sampler),
             },
             BindGroupEntry {
                 binding: 2,
@@ -133,33 +513,30 @@ async fn model_to_material(
             },
             BindGroupEntry {
                 binding: 3,
-                resource: BindingResource::Sampler(&normal_texture.sampler),
+                resource: BindingResource::Sampler(normal_sampler),
+            },
+            BindGroupEntry {
+                binding: 4,
+                resource: buffer.as_entire_binding(),
             },
         ],
         label: None,
-    });
-
-    Ok(Material {
-        name: material.name,
-        diffuse_texture,
-        normal_texture,
-        bind_group,
     })
 }
 
-fn model_to_mesh(model: &ObjModel, file_name: &str, device: &Device) -> Mesh {
-    let mut vertices = (0..model.mesh.positions.len() / 3)
+fn model_to_mesh(mesh: &ObjMesh) -> MeshData {
+    let mut vertices = (0..mesh.positions.len() / 3)
         .map(|idx| ModelVertex {
             position: [
-                model.mesh.positions[idx * 3],
-                model.mesh.positions[idx * 3 + 1],
-                model.mesh.positions[idx * 3 + 2],
+                mesh.positions[idx * 3],
+                mesh.positions[idx * 3 + 1],
+                mesh.positions[idx * 3 + 2],
             ],
-            tex_coords: [model.mesh.texcoords[idx * 2], model.mesh.texcoords[idx * 2 + 1]],
+            tex_coords: [mesh.texcoords[idx * 2], mesh.texcoords[idx * 2 + 1]],
             normal: [
-                model.mesh.normals[idx * 3],
-                model.mesh.normals[idx * 3 + 1],
-                model.mesh.normals[idx * 3 + 2],
+                mesh.normals[idx * 3],
+                mesh.normals[idx * 3 + 1],
+                mesh.normals[idx * 3 + 2],
             ],
             // We'll calculate these later
             tangent: [0.0; 3],
@@ -167,7 +544,7 @@ fn model_to_mesh(model: &ObjModel, file_name: &str, device: &Device) -> Mesh {
         })
         .collect::<Vec<_>>();
 
-    let indices = &model.mesh.indices;
+    let indices = &mesh.indices;
     let mut triangles_included = vec![0; vertices.len()];
 
     // Calculate tangents and bitangets. We're going to
@@ -236,15 +613,24 @@ fn model_to_mesh(model: &ObjModel, file_name: &str, device: &Device) -> Mesh {
         v.bi_tangent = (cgmath::Vector3::from(v.bi_tangent) * denom).into();
     }
 
+    MeshData {
+        bounds: Aabb::from_points(vertices.iter().map(|vertex| vertex.position)),
+        vertices,
+        indices: mesh.indices.clone(),
+        material: mesh.material_id.unwrap_or(0),
+    }
+}
+
+fn upload_mesh(mesh: MeshData, file_name: &str, device: &Device) -> Mesh {
     let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
         label: Some(&format!("{:?} Vertex Buffer", file_name)),
-        contents: cast_slice(&vertices),
+        contents: cast_slice(&mesh.vertices),
         usage: BufferUsages::VERTEX,
     });
 
     let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
         label: Some(&format!("{:?} Index Buffer", file_name)),
-        contents: cast_slice(&model.mesh.indices),
+        contents: cast_slice(&mesh.indices),
         usage: BufferUsages::INDEX,
     });
 
@@ -253,19 +639,133 @@ fn model_to_mesh(model: &ObjModel, file_name: &str, device: &Device) -> Mesh {
         name: file_name.to_string(),
         vertex_buffer,
         index_buffer,
-        num_elements: model.mesh.indices.len() as u32,
-        material: model.mesh.material_id.unwrap_or(0),
+        num_elements: mesh.indices.len() as u32,
+        material: mesh.material,
+        bounds: mesh.bounds,
+        vertices: mesh.vertices,
+        indices: mesh.indices,
+        wireframe_buffer: None,
     }
 }
 
+// Registry key for a texture. The same file used as a color and as data is uploaded once for each
+pub fn texture_key(file_name: &str, color_space: ColorSpace) -> String {
+    match color_space {
+        ColorSpace::Srgb => format!("{file_name}#srgb"),
+        ColorSpace::Linear => format!("{file_name}#linear"),
+    }
+}
+
+// Materials that use the same image in the same color space share one upload, with the sampler it was first loaded with
 #[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
+#[instrument(skip(source, assets, device, queue))]
 pub async fn load_texture(
+    source: &dyn AssetSource,
     file_name: &str,
+    color_space: ColorSpace,
+    sampler: SamplerSettings,
+    assets: &mut Assets,
     device: &Device,
     queue: &Queue,
-) -> anyhow::Result<Texture> {
-    let data = load_binary(file_name).await?;
+) -> anyhow::Result<Handle<Texture>> {
+    let key = texture_key(file_name, color_space);
+
+    if let Some(handle) = assets.textures.acquire(&key) {
+        return Ok(handle);
+    }
+
+    let data = read_texture(source, file_name, color_space, sampler, device.features()).await?;
+    let sampler = assets.samplers.get(device, sampler);
+
+    Ok(assets.textures.insert(&key, upload_image(data, sampler, device, queue)))
+}
+
+// A single texture, without counting it in any progress
+#[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
+pub async fn read_texture(
+    source: &dyn AssetSource,
+    file_name: &str,
+    color_space: ColorSpace,
+    sampler: SamplerSettings,
+    features: Features,
+) -> anyhow::Result<TextureData> {
+    read_texture_data(source, file_name, color_space, sampler, features, &Progress::default()).await
+}
+
+
+// Decoded on the loading thread, only the upload is left for upload_model
+#[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
+async fn read_texture_data(
+    source: &dyn AssetSource,
+    file_name: &str,
+    color_space: ColorSpace,
+    sampler: SamplerSettings,
+    features: Features,
+    progress: &Progress,
+) -> anyhow::Result<TextureData> {
+    let data = load_binary(source, file_name).await?;
+
+    progress.add(data.len());
 
-    Texture::from_bytes(device, queue, &data, file_name)
+    let image = decode_texture(&data, color_space, features).with_context(|| format!("couldn't decode {file_name}"))?;
+
+    Ok(TextureData { file_name: file_name.to_owned(), color_space, sampler, image })
 }
 
+// Told apart by what's in them rather than their extension.
+// Baked textures are raw pixels and compressed ones are mostly uploaded as they are, there's nothing to decode.
+// The color space only applies to files that don't say which one they're in
+fn decode_texture(data: &[u8], color_space: ColorSpace, features: Features) -> anyhow::Result<TextureImage> {
+    if compressed::is_compressed(data) {
+        compressed::read_image(data, color_space, features).map(TextureImage::Gpu)
+    } else if baked::is_texture(data) {
+        baked::read_texture(data).map(TextureImage::Decoded)
+    } else {
+        Ok(TextureImage::Decoded(image::load_from_memory(data)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_options(map: &str, file_name: &str, sampler: SamplerSettings) {
+        assert_eq!(texture_options(map), (file_name.to_owned(), sampler), "{map:?}");
+    }
+
+    #[test]
+    fn maps_without_options() {
+        assert_options("cube-diffuse.jpg", "cube-diffuse.jpg", SamplerSettings::REPEAT);
+        assert_options("textures/brick wall.png", "textures/brick wall.png", SamplerSettings::REPEAT);
+    }
+
+    #[test]
+    fn clamp_sets_the_sampler() {
+        assert_options("-clamp on decal.png", "decal.png", SamplerSettings::CLAMP);
+        assert_options("-clamp off tile.png", "tile.png", SamplerSettings::REPEAT);
+        assert_options("-clamp on -clamp off tile.png", "tile.png", SamplerSettings::REPEAT);
+    }
+
+    #[test]
+    fn clamp_without_a_value_keeps_the_file_name() {
+        assert_options("-clamp decal.png", "decal.png", SamplerSettings::REPEAT);
+        assert_options("-clamp", "", SamplerSettings::REPEAT);
+    }
+
+    #[test]
+    fn other_options_are_skipped() {
+        assert_options("-o 0.5 0.5 -s 2 2 1 -clamp on decal.png", "decal.png", SamplerSettings::CLAMP);
+        assert_options("-mm 0 1 -bm 0.5 -imfchan r bump.png", "bump.png", SamplerSettings::REPEAT);
+        assert_options("-blendu off -cc on -type sphere sky.png", "sky.png", SamplerSettings::REPEAT);
+
+        // The file name right after an option that's missing its value isn't taken for it
+        assert_options("-bm 2.png", "2.png", SamplerSettings::REPEAT);
+        assert_options("-blendu 1.png", "1.png", SamplerSettings::REPEAT);
+    }
+
+    #[test]
+    fn texture_keys_tell_color_spaces_apart() {
+        assert_ne!(texture_key("cube.png", ColorSpace::Srgb), texture_key("cube.png", ColorSpace::Linear));
+        assert_ne!(texture_key("a.png", ColorSpace::Srgb), texture_key("b.png", ColorSpace::Srgb));
+    }
+}
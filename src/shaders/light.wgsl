@@ -10,6 +10,7 @@ var<uniform> camera: Camera;
 
 struct Light {
     position: vec3<f32>,
+    ambient_strength: f32,
     color: vec3<f32>,
 }
 
@@ -0,0 +1,97 @@
+// Vertex shader
+
+struct Camera {
+    view_pos: vec4<f32>,
+    view_proj: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct Outline {
+    color: vec4<f32>,
+    viewport: vec2<f32>,
+    width: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> outline: Outline;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+}
+
+struct InstanceInput {
+    @location(5) model_matrix_0: vec4<f32>,
+    @location(6) model_matrix_1: vec4<f32>,
+    @location(7) model_matrix_2: vec4<f32>,
+    @location(8) model_matrix_3: vec4<f32>,
+    @location(9) normal_matrix_0: vec3<f32>,
+    @location(10) normal_matrix_1: vec3<f32>,
+    @location(11) normal_matrix_2: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+}
+
+fn clip_position(model: VertexInput, instance: InstanceInput) -> vec4<f32> {
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+
+    return camera.view_proj * model_matrix * vec4<f32>(model.position, 1.0);
+}
+
+// Fills the stencil buffer with the selected instance's silhouette
+@vertex
+fn vs_mask(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    out.clip_position = clip_position(model, instance);
+
+    return out;
+}
+
+// Pushes vertices out along their screen space normal by the outline's width in pixels
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let normal_matrix = mat3x3<f32>(
+        instance.normal_matrix_0,
+        instance.normal_matrix_1,
+        instance.normal_matrix_2,
+    );
+
+    let position = clip_position(model, instance);
+    let world_normal = normalize(normal_matrix * model.normal);
+    let clip_normal = (camera.view_proj * vec4<f32>(world_normal, 0.0)).xy;
+
+    var offset = vec2<f32>(0.0, 0.0);
+
+    if (length(clip_normal) > 0.0001) {
+        offset = normalize(clip_normal) * outline.width * 2.0 / outline.viewport * position.w;
+    }
+
+    var out: VertexOutput;
+
+    out.clip_position = vec4<f32>(position.xy + offset, position.zw);
+
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return outline.color;
+}
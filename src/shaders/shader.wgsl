@@ -2,6 +2,7 @@
 struct CameraUniform {
     view_pos: vec4<f32>,
     view_proj: mat4x4<f32>,
+    far: f32,
 };
 
 @group(1) @binding(0)
@@ -9,6 +10,7 @@ var<uniform> camera: CameraUniform;
 
 struct Light {
     position: vec3<f32>,
+    ambient_strength: f32,
     color: vec3<f32>,
 }
 
@@ -29,6 +31,11 @@ struct VertexOutput {
     @location(1) tangent_position: vec3<f32>,
     @location(2) tangent_light_position: vec3<f32>,
     @location(3) tangent_view_position: vec3<f32>,
+    @location(4) highlight: f32,
+    // Only used by the debug view modes
+    @location(5) world_position: vec3<f32>,
+    @location(6) world_normal: vec3<f32>,
+    @location(7) barycentric: vec3<f32>,
 }
 
 struct InstanceInput {
@@ -39,10 +46,10 @@ struct InstanceInput {
     @location(9) normal_matrix_0: vec3<f32>,
     @location(10) normal_matrix_1: vec3<f32>,
     @location(11) normal_matrix_2: vec3<f32>,
+    @location(12) highlight: f32,
 };
 
-@vertex
-fn vs_main(
+fn vertex(
     model: VertexInput,
     instance: InstanceInput,
 ) -> VertexOutput {
@@ -80,6 +87,37 @@ fn vs_main(
     out.tangent_position = tangent_matrix * world_position.xyz;
     out.tangent_view_position = tangent_matrix * camera.view_pos.xyz;
     out.tangent_light_position = tangent_matrix * light.position;
+    out.highlight = instance.highlight;
+    out.world_position = world_position.xyz;
+    out.world_normal = world_normal;
+
+    return out;
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    return vertex(model, instance);
+}
+
+// For the wireframe fallback, meshes are drawn un-indexed so every
+// three consecutive vertices make a triangle
+@vertex
+fn vs_barycentric(
+    @builtin(vertex_index) vertex_index: u32,
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out = vertex(model, instance);
+    let corner = vertex_index % 3u;
+
+    out.barycentric = vec3<f32>(
+        f32(corner == 0u),
+        f32(corner == 1u),
+        f32(corner == 2u),
+    );
 
     return out;
 }
@@ -99,14 +137,20 @@ var t_normal: texture_2d<f32>;
 @group(0) @binding(3)
 var s_normal: sampler;
 
+struct Material {
+    specular_strength: f32,
+    shininess: f32,
+}
+
+@group(0) @binding(4)
+var<uniform> material: Material;
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let object_color: vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords);
     let object_normal: vec4<f32> = textureSample(t_normal, s_normal, in.tex_coords);
 
-    // We don't need (or want) much ambient light, so 0.1 is fine
-    let ambient_strength = 0.1;
-    let ambient_color = light.color * ambient_strength;
+    let ambient_color = light.color * light.ambient_strength;
 
     let tangent_normal = object_normal.xyz * 2.0 - 1.0;
     let light_dir = normalize(in.tangent_light_position - in.tangent_position);
@@ -116,12 +160,69 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let diffuse_strength = max(dot(tangent_normal, light_dir), 0.0);
     let diffuse_color = light.color * diffuse_strength;
 
-    let specular_strength = pow(max(dot(tangent_normal, half_dir), 0.0), 2.0); // original value was 32.0
-    let specular_color = specular_strength * light.color;
+    let specular_strength = pow(max(dot(tangent_normal, half_dir), 0.0), material.shininess);
+    let specular_color = specular_strength * material.specular_strength * light.color;
 
-    let result = (ambient_color + diffuse_color + specular_color) * object_color.xyz;
+    let lit = (ambient_color + diffuse_color + specular_color) * object_color.xyz;
+
+    // Tint selected instances
+    let highlight_color = vec3<f32>(1.0, 0.6, 0.1);
+    let result = mix(lit, highlight_color, in.highlight * 0.5);
 
     return vec4<f32>(result, object_color.a);
 }
 
+// Debug view modes
 
+@fragment
+fn fs_wireframe(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.9, 0.9, 0.9, 1.0);
+}
+
+@fragment
+fn fs_barycentric(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Keep edges about a pixel and a half wide regardless of distance
+    let width = fwidth(in.barycentric) * 1.5;
+    let edge = smoothstep(vec3<f32>(0.0), width, in.barycentric);
+    let coverage = 1.0 - min(edge.x, min(edge.y, edge.z));
+
+    if (coverage < 0.1) {
+        discard;
+    }
+
+    return vec4<f32>(vec3<f32>(0.9) * coverage, 1.0);
+}
+
+@fragment
+fn fs_normals(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(normalize(in.world_normal) * 0.5 + 0.5, 1.0);
+}
+
+@fragment
+fn fs_tangent_normals(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(textureSample(t_normal, s_normal, in.tex_coords).xyz, 1.0);
+}
+
+@fragment
+fn fs_uvs(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(fract(in.tex_coords), 0.0, 1.0);
+}
+
+@fragment
+fn fs_albedo(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+}
+
+@fragment
+fn fs_depth(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Distance from the camera, linear up to the far plane
+    let depth = 1.0 - clamp(distance(in.world_position, camera.view_pos.xyz) / camera.far, 0.0, 1.0);
+
+    return vec4<f32>(vec3<f32>(depth), 1.0);
+}
+
+@fragment
+fn fs_overdraw(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Blended additively, so brighter means more fragments were shaded
+    return vec4<f32>(0.1, 0.04, 0.02, 1.0);
+}
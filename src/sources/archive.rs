@@ -0,0 +1,55 @@
+use std::future::ready;
+use std::io::{Cursor, Read};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+use anyhow::Context;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use crate::sources::{AssetSource, ReadFuture};
+
+// A zip of res, paths in the archive are relative to res like file names everywhere else.
+// The whole archive is kept in memory, so the web build can fetch it in one go
+pub struct ZipSource {
+    // Reading an entry moves the archive's cursor
+    archive: Mutex<ZipArchive<Cursor<Vec<u8>>>>,
+}
+
+impl ZipSource {
+    pub fn new(data: Vec<u8>) -> anyhow::Result<Self> {
+        let archive = ZipArchive::new(Cursor::new(data)).context("not a zip archive")?;
+
+        Ok(Self { archive: Mutex::new(archive) })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let data = std::fs::read(path).with_context(|| format!("couldn't read {}", path.display()))?;
+
+        Self::new(data).with_context(|| format!("couldn't open {}", path.display()))
+    }
+
+    fn read_now(&self, file_name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let mut data = Vec::new();
+
+        let read = self.archive
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .by_name(file_name)
+            .and_then(|mut file| Ok(file.read_to_end(&mut data)?));
+
+        match read {
+            Ok(_) => Ok(Some(data)),
+            Err(ZipError::FileNotFound) => Ok(None),
+            Err(err) => Err(err).with_context(|| format!("couldn't unpack {file_name}")),
+        }
+    }
+}
+
+impl AssetSource for ZipSource {
+    fn read<'a>(&'a self, file_name: &'a str) -> ReadFuture<'a> {
+        Box::pin(ready(self.read_now(file_name)))
+    }
+}
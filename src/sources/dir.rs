@@ -0,0 +1,32 @@
+use std::fs;
+use std::future::ready;
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+use crate::sources::{AssetSource, ReadFuture};
+
+pub struct DirSource {
+    root: PathBuf,
+}
+
+impl DirSource {
+    // A root that doesn't exist is just empty, res next to the executable usually isn't there
+    pub const fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+impl AssetSource for DirSource {
+    fn read<'a>(&'a self, file_name: &'a str) -> ReadFuture<'a> {
+        let path = self.root.join(file_name);
+
+        let data = if path.is_file() {
+            fs::read(&path).map(Some).with_context(|| format!("couldn't read {}", path.display()))
+        } else {
+            Ok(None)
+        };
+
+        Box::pin(ready(data))
+    }
+}
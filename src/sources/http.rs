@@ -0,0 +1,36 @@
+use anyhow::{anyhow, Context};
+use reqwest::{StatusCode, Url};
+
+use crate::sources::{AssetSource, ReadFuture};
+
+// Fetches res files from the server the page came from
+pub struct HttpSource {
+    base: Url,
+}
+
+impl HttpSource {
+    // Under RES_PATH, or res when the build didn't set it
+    pub fn new() -> anyhow::Result<Self> {
+        let window = web_sys::window().context("not running in a browser window")?;
+        let origin = window.location().origin().map_err(|_| anyhow!("the page has no origin"))?;
+        let res = option_env!("RES_PATH").unwrap_or("res");
+
+        let base = Url::parse(&format!("{origin}/{res}/")).with_context(|| format!("{origin}/{res}/ isn't a url"))?;
+
+        Ok(Self { base })
+    }
+}
+
+impl AssetSource for HttpSource {
+    fn read<'a>(&'a self, file_name: &'a str) -> ReadFuture<'a> {
+        Box::pin(async move {
+            let response = reqwest::get(self.base.join(file_name)?).await?;
+
+            if response.status() == StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+
+            Ok(Some(response.error_for_status()?.bytes().await?.to_vec()))
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::future::ready;
+
+use crate::sources::{AssetSource, ReadFuture};
+
+// Paths relative to res and their contents, built into the binary with the embed-res feature
+#[cfg(feature = "embed-res")]
+const EMBEDDED: &[(&str, &[u8])] = include!(concat!(env!("OUT_DIR"), "/embedded_res.rs"));
+#[cfg(not(feature = "embed-res"))]
+const EMBEDDED: &[(&str, &[u8])] = &[];
+
+// Files held in memory, either built into the binary or added at runtime
+#[derive(Default)]
+pub struct MemorySource {
+    files: HashMap<String, Cow<'static, [u8]>>,
+}
+
+impl MemorySource {
+    // Empty without the embed-res feature
+    pub fn embedded() -> Self {
+        let mut source = Self::default();
+
+        for &(file_name, data) in EMBEDDED {
+            source.insert(file_name, data);
+        }
+
+        source
+    }
+
+    // Replaces a file that's already there
+    pub fn insert(&mut self, file_name: &str, data: impl Into<Cow<'static, [u8]>>) {
+        self.files.insert(file_name.to_owned(), data.into());
+    }
+}
+
+impl AssetSource for MemorySource {
+    fn read<'a>(&'a self, file_name: &'a str) -> ReadFuture<'a> {
+        Box::pin(ready(Ok(self.files.get(file_name).map(|data| data.to_vec()))))
+    }
+}
@@ -0,0 +1,224 @@
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+
+pub use archive::ZipSource;
+#[cfg(not(target_arch = "wasm32"))]
+pub use dir::DirSource;
+#[cfg(target_arch = "wasm32")]
+pub use http::HttpSource;
+pub use memory::MemorySource;
+
+use crate::Options;
+
+mod archive;
+#[cfg(not(target_arch = "wasm32"))]
+mod dir;
+#[cfg(target_arch = "wasm32")]
+mod http;
+mod memory;
+
+// Natively sources are read from the loader threads, fetching on the web can't be sent anywhere
+#[cfg(not(target_arch = "wasm32"))]
+pub type ReadFuture<'a> = Pin<Box<dyn Future<Output = anyhow::Result<Option<Vec<u8>>>> + Send + 'a>>;
+#[cfg(target_arch = "wasm32")]
+pub type ReadFuture<'a> = Pin<Box<dyn Future<Output = anyhow::Result<Option<Vec<u8>>>> + 'a>>;
+
+// Somewhere res files are read from, file names are relative to res with forward slashes.
+// None means the file isn't there, an error means it is but couldn't be read
+pub trait AssetSource: Send + Sync {
+    fn read<'a>(&'a self, file_name: &'a str) -> ReadFuture<'a>;
+}
+
+// Tries its sources in order, the first one that has the file wins
+#[derive(Default)]
+pub struct LayeredSource {
+    sources: Vec<Box<dyn AssetSource>>,
+}
+
+impl LayeredSource {
+    pub fn push(&mut self, source: impl AssetSource + 'static) {
+        self.sources.push(Box::new(source));
+    }
+}
+
+impl AssetSource for LayeredSource {
+    fn read<'a>(&'a self, file_name: &'a str) -> ReadFuture<'a> {
+        Box::pin(async move {
+            for source in &self.sources {
+                if let Some(data) = source.read(file_name).await? {
+                    return Ok(Some(data));
+                }
+            }
+
+            Ok(None)
+        })
+    }
+}
+
+// Directories and zip packs from the options come first, then res next to the executable, then the copy
+// the build made, which only exists on the machine that built it. Hot reloading reads straight from the source tree.
+// Embedded files are only used when none of them have the file
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(clippy::unused_async)]
+pub async fn default_source(options: &Options) -> anyhow::Result<Arc<dyn AssetSource>> {
+    let source_tree = options.watch.then(|| PathBuf::from(crate::watcher::RES_DIR));
+    let next_to_executable = std::env::current_exe()
+        .ok()
+        .and_then(|executable| executable.parent().map(|directory| directory.join("res")));
+
+    let paths = source_tree
+        .into_iter()
+        .chain(options.res_dirs.iter().cloned())
+        .chain(next_to_executable)
+        .chain([Path::new(env!("OUT_DIR")).join("res")])
+        .collect::<Vec<_>>();
+
+    debug!("looking for res files in {paths:?}, embedded files: {}", cfg!(feature = "embed-res"));
+
+    let mut source = LayeredSource::default();
+
+    for path in paths {
+        if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("zip")) {
+            source.push(ZipSource::open(&path)?);
+        } else {
+            source.push(DirSource::new(path));
+        }
+    }
+
+    source.push(MemorySource::embedded());
+
+    Ok(Arc::new(source))
+}
+
+// Embedded files first since there's no point fetching them, then the pack RES_PACK names if the build set one,
+// which gets everything in a single request, then the files one by one
+#[cfg(target_arch = "wasm32")]
+#[allow(clippy::future_not_send)] // todo: ???
+pub async fn default_source(_options: &Options) -> anyhow::Result<Arc<dyn AssetSource>> {
+    let http = HttpSource::new()?;
+    let mut source = LayeredSource::default();
+
+    source.push(MemorySource::embedded());
+
+    if let Some(pack) = option_env!("RES_PACK") {
+        match http.read(pack).await? {
+            Some(data) => source.push(ZipSource::new(data)?),
+            None => warn!("{pack} isn't there, fetching res files one at a time"),
+        }
+    }
+
+    source.push(http);
+
+    Ok(Arc::new(source))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageOutputFormat, Rgba, RgbaImage};
+    use wgpu::Features;
+
+    use super::*;
+    use crate::loader::Progress;
+    use crate::resources::{read_model_data, ModelData, NotFound, TextureImage};
+
+    const OBJ: &str = "mtllib quad.mtl
+v -1 -1 0
+v 1 -1 0
+v 1 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vn 0 0 1
+usemtl Flat
+f 1/1/1 2/2/1 3/3/1
+";
+
+    const MTL: &str = "newmtl Flat
+map_Kd diffuse.png
+map_Bump normal.png
+";
+
+    fn png(color: [u8; 4]) -> Vec<u8> {
+        let mut data = Cursor::new(Vec::new());
+
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba(color)))
+            .write_to(&mut data, ImageOutputFormat::Png)
+            .unwrap();
+
+        data.into_inner()
+    }
+
+    fn read(source: &dyn AssetSource, progress: &Progress) -> anyhow::Result<ModelData> {
+        pollster::block_on(read_model_data(source, "quad.obj", Features::empty(), progress))
+    }
+
+    fn pixel(image: &TextureImage) -> [u8; 4] {
+        match image {
+            TextureImage::Decoded(image) => image.to_rgba8().get_pixel(0, 0).0,
+            TextureImage::Gpu(_) => panic!("pngs aren't uploaded as they are"),
+        }
+    }
+
+    #[test]
+    fn reads_models_from_memory() {
+        let mut memory = MemorySource::default();
+
+        memory.insert("quad.obj", OBJ.as_bytes());
+        memory.insert("quad.mtl", MTL.as_bytes());
+        memory.insert("diffuse.png", png([255, 0, 0, 255]));
+        memory.insert("normal.png", png([128, 128, 255, 255]));
+
+        let progress = Progress::default();
+        let model = read(&memory, &progress).unwrap();
+
+        assert_eq!(model.meshes.len(), 1);
+        assert_eq!(model.meshes[0].indices.len(), 3);
+        assert_eq!(model.materials[0].name, "Flat");
+        assert_eq!(pixel(&model.materials[0].diffuse_texture.image), [255, 0, 0, 255]);
+        assert_eq!(pixel(&model.materials[0].normal_texture.image), [128, 128, 255, 255]);
+        assert_eq!(progress.items(), 4);
+    }
+
+    #[test]
+    fn first_layer_with_the_file_wins() {
+        let mut model = MemorySource::default();
+        let mut textures = MemorySource::default();
+
+        model.insert("quad.obj", OBJ.as_bytes());
+        model.insert("quad.mtl", MTL.as_bytes());
+        model.insert("diffuse.png", png([255, 0, 0, 255]));
+        textures.insert("diffuse.png", png([0, 0, 255, 255]));
+        textures.insert("normal.png", png([128, 128, 255, 255]));
+
+        let mut layered = LayeredSource::default();
+
+        layered.push(model);
+        layered.push(textures);
+
+        let model = read(&layered, &Progress::default()).unwrap();
+
+        assert_eq!(pixel(&model.materials[0].diffuse_texture.image), [255, 0, 0, 255]);
+        assert_eq!(pixel(&model.materials[0].normal_texture.image), [128, 128, 255, 255]);
+    }
+
+    #[test]
+    fn missing_files_are_not_found() {
+        let mut model = MemorySource::default();
+
+        model.insert("quad.obj", OBJ.as_bytes());
+
+        let mut layered = LayeredSource::default();
+
+        layered.push(model);
+        layered.push(MemorySource::default());
+
+        let err = read(&layered, &Progress::default()).err().unwrap();
+
+        assert_eq!(err.downcast_ref::<NotFound>().map(|NotFound(file_name)| file_name.as_str()), Some("quad.mtl"));
+    }
+}
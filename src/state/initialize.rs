@@ -1,43 +1,176 @@
+use std::collections::HashMap;
+use std::iter::successors;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use bytemuck::cast_slice;
 use cgmath::{Deg, InnerSpace, Quaternion, Rotation3, Vector3, Zero};
 #[allow(clippy::wildcard_imports)]
 use wgpu::*;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu_core::device::DeviceError;
 use winit::dpi::PhysicalSize;
 
-use crate::models::{Instance as MeshInstance, InstanceRaw, Light, LightUniform};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::adapters::select_adapter;
+use crate::compressed;
+use crate::error::InitError;
+use crate::Options;
+use crate::models::{
+    Camera, CameraConfiguration, CameraProjection, DynamicVertexBuffer, Instance as MeshInstance, InstanceRaw, Light,
+    LightUniform, LineVertex, ModelVertex, Outline, OutlineUniform, PassProfiler, TextVertex, Texture, Vertex, ViewMode,
+};
+use crate::state::shaders::Shaders;
+use crate::ui::Ui;
 
 const NUM_INSTANCES_PER_ROW: u32 = 10;
 const SPACE_BETWEEN: f32 = 3.0;
 
+#[instrument(skip_all)]
 pub fn configure_surface(
     adapter: &Adapter,
     device: &Device,
     surface: &Surface,
     size: PhysicalSize<u32>,
-) -> SurfaceConfiguration {
+    present_mode: PresentMode,
+) -> Result<SurfaceConfiguration, InitError> {
+    let Some(&format) = surface.get_supported_formats(adapter).first() else {
+        return Err(InitError::UnsupportedSurface(adapter.get_info().name));
+    };
+
+    // The auto modes are resolved by wgpu, and Fifo is always supported
+    let present_mode = match present_mode {
+        PresentMode::AutoVsync | PresentMode::AutoNoVsync => present_mode,
+        _ if surface.get_supported_present_modes(adapter).contains(&present_mode) => present_mode,
+        _ => {
+            warn!("present mode {present_mode:?} isn't supported, using Fifo");
+            PresentMode::Fifo
+        }
+    };
+
     let configuration = SurfaceConfiguration {
         usage: TextureUsages::RENDER_ATTACHMENT,
-        format: surface.get_supported_formats(adapter)[0],
+        format,
         width: size.width,
         height: size.height,
-        present_mode: PresentMode::Fifo,
+        present_mode,
         alpha_mode: CompositeAlphaMode::Auto,
     };
 
     surface.configure(device, &configuration);
 
-    configuration
+    Ok(configuration)
+}
+
+// Stands in for a surface when rendering headless, frames are copied out of an
+// srgb texture so they can be saved as they are
+pub fn offscreen_configuration(size: PhysicalSize<u32>) -> SurfaceConfiguration {
+    SurfaceConfiguration {
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        format: TextureFormat::Rgba8UnormSrgb,
+        width: size.width,
+        height: size.height,
+        present_mode: PresentMode::Fifo,
+        alpha_mode: CompositeAlphaMode::Auto,
+    }
+}
+
+// Both the color and depth formats have to support it, or the scene isn't multisampled
+pub fn supported_sample_count(adapter: &Adapter, format: TextureFormat, sample_count: u32) -> u32 {
+    let multisample = |format| adapter
+        .get_texture_format_features(format)
+        .flags
+        .contains(TextureFormatFeatureFlags::MULTISAMPLE);
+
+    if sample_count > 1 && !(multisample(format) && multisample(Texture::DEPTH_FORMAT)) {
+        warn!("{sample_count}x msaa isn't supported for {format:?}, it's disabled");
+        return 1;
+    }
+
+    sample_count
+}
+
+// What the scene pass draws into, every pipeline used in it has to agree
+#[derive(Clone, Copy, Debug)]
+pub struct SceneTarget {
+    pub format: TextureFormat,
+    pub sample_count: u32,
+}
+
+// Kept after initialization, so pipelines and bind groups can be rebuilt when what they use changes
+pub struct BindGroupLayouts {
+    pub camera: BindGroupLayout,
+    pub diffuse: BindGroupLayout,
+    pub font: BindGroupLayout,
+    pub light: BindGroupLayout,
+    pub outline: BindGroupLayout,
+}
+
+pub struct PipelineOptions<'a> {
+    pub vertex_entry_point: &'a str,
+    pub fragment_entry_point: &'a str,
+    pub topology: PrimitiveTopology,
+    // Line requires Features::POLYGON_MODE_LINE, Point requires Features::POLYGON_MODE_POINT
+    pub polygon_mode: PolygonMode,
+    pub blend: BlendState,
+    pub color_writes: ColorWrites,
+    pub cull_mode: Option<Face>,
+    pub depth_stencil: Option<DepthStencilState>,
+}
+
+impl PipelineOptions<'_> {
+    pub fn new(depth_format: Option<TextureFormat>) -> Self {
+        Self {
+            vertex_entry_point: "vs_main",
+            fragment_entry_point: "fs_main",
+            topology: PrimitiveTopology::TriangleList,
+            polygon_mode: PolygonMode::Fill,
+            blend: BlendState {
+                alpha: BlendComponent::REPLACE,
+                color: BlendComponent::REPLACE,
+            },
+            color_writes: ColorWrites::ALL,
+            cull_mode: Some(Face::Back),
+            depth_stencil: depth_format.map(|format| DepthStencilState {
+                format,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+        }
+    }
 }
 
 pub fn create_render_pipeline(
     device: &Device,
     bind_group_layouts: &[&BindGroupLayout],
-    format: TextureFormat,
+    target: SceneTarget,
     depth_format: Option<TextureFormat>,
     vertex_layouts: &[VertexBufferLayout],
     shader: ShaderModuleDescriptor,
     label: &str,
+) -> RenderPipeline {
+    create_render_pipeline_with_options(
+        device,
+        bind_group_layouts,
+        target,
+        vertex_layouts,
+        shader,
+        PipelineOptions::new(depth_format),
+        label,
+    )
+}
+
+#[instrument(skip_all, fields(label = label))]
+pub fn create_render_pipeline_with_options(
+    device: &Device,
+    bind_group_layouts: &[&BindGroupLayout],
+    target: SceneTarget,
+    vertex_layouts: &[VertexBufferLayout],
+    shader: ShaderModuleDescriptor,
+    options: PipelineOptions,
+    label: &str,
 ) -> RenderPipeline {
     let shader = device.create_shader_module(shader);
 
@@ -53,42 +186,32 @@ pub fn create_render_pipeline(
         layout: Some(&render_pipeline_layout),
         vertex: VertexState {
             module: &shader,
-            entry_point: "vs_main",
+            entry_point: options.vertex_entry_point,
             buffers: vertex_layouts,
         },
         fragment: Some(FragmentState {
             module: &shader,
-            entry_point: "fs_main",
+            entry_point: options.fragment_entry_point,
             targets: &[Some(ColorTargetState {
-                format,
-                blend: Some(BlendState {
-                    alpha: BlendComponent::REPLACE,
-                    color: BlendComponent::REPLACE,
-                }),
-                write_mask: ColorWrites::ALL,
+                format: target.format,
+                blend: Some(options.blend),
+                write_mask: options.color_writes,
             })],
         }),
         primitive: PrimitiveState {
-            topology: PrimitiveTopology::TriangleList,
+            topology: options.topology,
             strip_index_format: None,
             front_face: FrontFace::Ccw,
-            cull_mode: Some(Face::Back),
-            // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-            polygon_mode: PolygonMode::Fill,
+            cull_mode: options.cull_mode,
+            polygon_mode: options.polygon_mode,
             // Requires Features::DEPTH_CLIP_CONTROL
             unclipped_depth: false,
             // Requires Features::CONSERVATIVE_RASTERIZATION
             conservative: false,
         },
-        depth_stencil: depth_format.map(|format| DepthStencilState {
-            format,
-            depth_write_enabled: true,
-            depth_compare: CompareFunction::Less,
-            stencil: StencilState::default(),
-            bias: DepthBiasState::default(),
-        }),
+        depth_stencil: options.depth_stencil,
         multisample: MultisampleState {
-            count: 1,
+            count: target.sample_count,
             mask: !0,
             alpha_to_coverage_enabled: false,
         },
@@ -96,6 +219,48 @@ pub fn create_render_pipeline(
     })
 }
 
+// The lit pipeline and its debug view modes, everything that uses shader.wgsl
+pub fn create_scene_pipelines(
+    device: &Device,
+    layouts: &BindGroupLayouts,
+    target: SceneTarget,
+    shaders: &Shaders,
+) -> (RenderPipeline, HashMap<ViewMode, RenderPipeline>) {
+    let bind_group_layouts = [&layouts.diffuse, &layouts.camera, &layouts.light];
+    let vertex_layouts = [ModelVertex::desc(), InstanceRaw::desc()];
+
+    let render_pipeline = create_render_pipeline(
+        device,
+        &bind_group_layouts,
+        target,
+        Some(Texture::DEPTH_FORMAT),
+        &vertex_layouts,
+        shaders.get("shader.wgsl"),
+        "shader",
+    );
+
+    let view_pipelines = create_view_mode_pipelines(device, &bind_group_layouts, target, &vertex_layouts, shaders);
+
+    (render_pipeline, view_pipelines)
+}
+
+pub fn create_light_pipeline(
+    device: &Device,
+    layouts: &BindGroupLayouts,
+    target: SceneTarget,
+    shaders: &Shaders,
+) -> RenderPipeline {
+    create_render_pipeline(
+        device,
+        &[&layouts.camera, &layouts.light],
+        target,
+        Some(Texture::DEPTH_FORMAT),
+        &[ModelVertex::desc()],
+        shaders.get("light.wgsl"),
+        "light",
+    )
+}
+
 pub fn diffuse_bind_group_layout(
     device: &Device,
     label: &str,
@@ -136,13 +301,24 @@ pub fn diffuse_bind_group_layout(
                 ty: BindingType::Sampler(SamplerBindingType::Filtering),
                 count: None,
             },
+            BindGroupLayoutEntry {
+                binding: 4,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
         ],
         label: Some(&format!("{label} - bind group layout")),
     })
 }
 
-pub fn get_instances(device: &Device) -> (Vec<MeshInstance>, Buffer) {
-    let instances = (0..NUM_INSTANCES_PER_ROW).flat_map(|z| {
+// A grid of instances, used when there's no scene
+pub fn default_instances() -> Vec<MeshInstance> {
+    (0..NUM_INSTANCES_PER_ROW).flat_map(|z| {
         #[allow(clippy::cast_precision_loss)]
         (0..NUM_INSTANCES_PER_ROW).map(move |x| {
             let x = SPACE_BETWEEN * (x as f32 - NUM_INSTANCES_PER_ROW as f32 / 2.0);
@@ -158,20 +334,22 @@ pub fn get_instances(device: &Device) -> (Vec<MeshInstance>, Buffer) {
 
             // let rotation = Quaternion::from_axis_angle((0.0, 1.0, 0.0).into(), Deg(180.0));
 
-            MeshInstance { position, rotation }
+            MeshInstance { position, rotation, scale: Vector3::new(1.0, 1.0, 1.0), selected: false }
         })
-    }).collect::<Vec<_>>();
+    }).collect()
+}
 
+pub fn create_instance_buffer(device: &Device, instances: &[MeshInstance]) -> Buffer {
     let instance_data = instances.iter().map(Into::into).collect::<Vec<InstanceRaw>>();
-    let instance_buffer = device.create_buffer_init(
+
+    device.create_buffer_init(
         &BufferInitDescriptor {
             label: Some("instance buffer"),
             contents: cast_slice(&instance_data),
-            usage: BufferUsages::VERTEX,
+            // Selection and edits rewrite instances in place
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
         }
-    );
-
-    (instances, instance_buffer)
+    )
 }
 
 pub fn initialize_light(device: &Device) -> (Light, BindGroupLayout) {
@@ -219,29 +397,396 @@ pub fn initialize_light(device: &Device) -> (Light, BindGroupLayout) {
     (light, light_bind_group_layout)
 }
 
-pub async fn request_adapter(instance: &Instance, surface: &Surface) -> Adapter {
+pub fn initialize_outline(device: &Device, configuration: &SurfaceConfiguration) -> (Outline, BindGroupLayout) {
+    #[allow(clippy::cast_precision_loss)]
+    let outline_uniform = OutlineUniform::new(
+        [1.0, 0.6, 0.1, 1.0],
+        3.0,
+        [configuration.width as f32, configuration.height as f32],
+    );
+
+    // The color, width and viewport can all change while running
+    let outline_buffer = device.create_buffer_init(
+        &BufferInitDescriptor {
+            label: Some("outline buffer"),
+            contents: cast_slice(&[outline_uniform]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        }
+    );
+
+    let outline_bind_group_layout =
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("outline - bind group layout"),
+        });
+
+    let outline_bind_group = device.create_bind_group(&BindGroupDescriptor {
+        layout: &outline_bind_group_layout,
+        entries: &[BindGroupEntry {
+            binding: 0,
+            resource: outline_buffer.as_entire_binding(),
+        }],
+        label: Some("outline - bind group"),
+    });
+
+    let outline = Outline {
+        bind_group: outline_bind_group,
+        buffer: outline_buffer,
+        uniform: outline_uniform,
+    };
+
+    (outline, outline_bind_group_layout)
+}
+
+// The mask pass marks the selected instance in the stencil buffer, the outline
+// pass then draws an inflated copy of it everywhere the mask wasn't written.
+// Both ignore depth so the outline shows through anything in front of it.
+pub fn create_outline_pipelines(
+    device: &Device,
+    layouts: &BindGroupLayouts,
+    target: SceneTarget,
+    shaders: &Shaders,
+) -> (RenderPipeline, RenderPipeline) {
+    let bind_group_layouts = [&layouts.camera, &layouts.outline];
+    let vertex_layouts = [ModelVertex::desc(), InstanceRaw::desc()];
+
+    let depth_stencil = |compare, pass_op, write_mask| DepthStencilState {
+        format: Texture::DEPTH_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: CompareFunction::Always,
+        stencil: StencilState {
+            front: StencilFaceState { compare, fail_op: StencilOperation::Keep, depth_fail_op: StencilOperation::Keep, pass_op },
+            back: StencilFaceState { compare, fail_op: StencilOperation::Keep, depth_fail_op: StencilOperation::Keep, pass_op },
+            read_mask: 0xff,
+            write_mask,
+        },
+        bias: DepthBiasState::default(),
+    };
+
+    let mask_pipeline = create_render_pipeline_with_options(
+        device,
+        &bind_group_layouts,
+        target,
+        &vertex_layouts,
+        shaders.get("outline.wgsl"),
+        PipelineOptions {
+            vertex_entry_point: "vs_mask",
+            color_writes: ColorWrites::empty(),
+            cull_mode: None,
+            depth_stencil: Some(depth_stencil(CompareFunction::Always, StencilOperation::Replace, 0xff)),
+            ..PipelineOptions::new(None)
+        },
+        "outline mask",
+    );
+
+    let outline_pipeline = create_render_pipeline_with_options(
+        device,
+        &bind_group_layouts,
+        target,
+        &vertex_layouts,
+        shaders.get("outline.wgsl"),
+        PipelineOptions {
+            cull_mode: None,
+            depth_stencil: Some(depth_stencil(CompareFunction::NotEqual, StencilOperation::Keep, 0x00)),
+            ..PipelineOptions::new(None)
+        },
+        "outline",
+    );
+
+    (mask_pipeline, outline_pipeline)
+}
+
+// A variant of the main pipeline for every debug view mode, they all share shader.wgsl
+fn create_view_mode_pipelines(
+    device: &Device,
+    bind_group_layouts: &[&BindGroupLayout],
+    target: SceneTarget,
+    vertex_layouts: &[VertexBufferLayout],
+    shaders: &Shaders,
+) -> HashMap<ViewMode, RenderPipeline> {
+    let polygon_mode_line = device.features().contains(Features::POLYGON_MODE_LINE);
+
+    ViewMode::DEBUG
+        .into_iter()
+        .map(|view_mode| {
+            let mut options = PipelineOptions::new(Some(Texture::DEPTH_FORMAT));
+
+            options.fragment_entry_point = view_mode.fragment_entry_point();
+
+            match view_mode {
+                ViewMode::Wireframe => {
+                    options.cull_mode = None;
+
+                    if polygon_mode_line {
+                        options.polygon_mode = PolygonMode::Line;
+                    } else {
+                        // Meshes are drawn from their un-indexed wireframe buffers instead
+                        options.vertex_entry_point = "vs_barycentric";
+                        options.fragment_entry_point = "fs_barycentric";
+                    }
+                }
+                ViewMode::Overdraw => {
+                    let additive = BlendComponent {
+                        src_factor: BlendFactor::One,
+                        dst_factor: BlendFactor::One,
+                        operation: BlendOperation::Add,
+                    };
+
+                    options.blend = BlendState { color: additive, alpha: additive };
+
+                    if let Some(depth_stencil) = options.depth_stencil.as_mut() {
+                        depth_stencil.depth_write_enabled = false;
+                        depth_stencil.depth_compare = CompareFunction::Always;
+                    }
+                }
+                _ => {}
+            }
+
+            let pipeline = create_render_pipeline_with_options(
+                device,
+                bind_group_layouts,
+                target,
+                vertex_layouts,
+                shaders.get("shader.wgsl"),
+                options,
+                &format!("{view_mode:?}"),
+            );
+
+            (view_mode, pipeline)
+        })
+        .collect()
+}
+
+// Overlays, like gizmos, skip the depth test so they're drawn on top of the scene
+pub fn create_line_pipeline(
+    device: &Device,
+    layouts: &BindGroupLayouts,
+    target: SceneTarget,
+    overlay: bool,
+    shaders: &Shaders,
+    label: &str,
+) -> RenderPipeline {
+    let mut options = PipelineOptions::new(Some(Texture::DEPTH_FORMAT));
+
+    options.topology = PrimitiveTopology::LineList;
+    options.cull_mode = None;
+
+    if let Some(depth_stencil) = options.depth_stencil.as_mut() {
+        depth_stencil.depth_write_enabled = false;
+
+        if overlay {
+            depth_stencil.depth_compare = CompareFunction::Always;
+        }
+    }
+
+    create_render_pipeline_with_options(
+        device,
+        &[&layouts.camera],
+        target,
+        &[LineVertex::desc()],
+        shaders.get("line.wgsl"),
+        options,
+        label,
+    )
+}
+
+pub fn font_bind_group_layout(device: &Device) -> BindGroupLayout {
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: TextureViewDimension::D2,
+                    sample_type: TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+        label: Some("font - bind group layout"),
+    })
+}
+
+pub fn create_font_bind_group(device: &Device, layout: &BindGroupLayout, texture: &Texture) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
+        layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&texture.view),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::Sampler(&texture.sampler),
+            },
+        ],
+        label: Some("font - bind group"),
+    })
+}
+
+// Screen space and alpha blended, drawn last over everything else
+pub fn create_text_pipeline(
+    device: &Device,
+    layouts: &BindGroupLayouts,
+    target: SceneTarget,
+    shaders: &Shaders,
+) -> RenderPipeline {
+    let mut options = PipelineOptions::new(Some(Texture::DEPTH_FORMAT));
+
+    options.blend = BlendState::ALPHA_BLENDING;
+    options.cull_mode = None;
+
+    if let Some(depth_stencil) = options.depth_stencil.as_mut() {
+        depth_stencil.depth_write_enabled = false;
+        depth_stencil.depth_compare = CompareFunction::Always;
+    }
+
+    create_render_pipeline_with_options(
+        device,
+        &[&layouts.font],
+        target,
+        &[TextVertex::desc()],
+        shaders.get("text.wgsl"),
+        options,
+        "text",
+    )
+}
+
+// Everything on the gpu besides the assets, which State::recover builds again for a new device
+pub struct GpuResources {
+    pub camera_configuration: CameraConfiguration,
+    pub debug_lines: DynamicVertexBuffer<LineVertex>,
+    pub debug_pipeline: RenderPipeline,
+    pub depth_texture: Texture,
+    pub gizmo_lines: DynamicVertexBuffer<LineVertex>,
+    pub gizmo_pipeline: RenderPipeline,
+    pub layouts: BindGroupLayouts,
+    pub light: Light,
+    pub light_pipeline: RenderPipeline,
+    pub multisampled_framebuffer: Option<Texture>,
+    pub outline: Outline,
+    pub outline_mask_pipeline: RenderPipeline,
+    pub outline_pipeline: RenderPipeline,
+    pub profiler: PassProfiler,
+    pub render_pipeline: RenderPipeline,
+    pub sample_count: u32,
+    pub text_buffer: DynamicVertexBuffer<TextVertex>,
+    pub text_pipeline: RenderPipeline,
+    pub ui: Ui,
+    pub view_pipelines: HashMap<ViewMode, RenderPipeline>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_gpu_resources(
+    adapter: &Adapter,
+    device: &Device,
+    queue: &Queue,
+    surface_configuration: &SurfaceConfiguration,
+    camera: &Camera,
+    camera_projection: &CameraProjection,
+    shaders: &Shaders,
+    pixels_per_point: f32,
+    options: &Options,
+) -> GpuResources {
+    let sample_count = supported_sample_count(adapter, surface_configuration.format, options.sample_count);
+    let target = SceneTarget { format: surface_configuration.format, sample_count };
+
+    let (camera_configuration, camera_bind_group_layout) =
+        CameraConfiguration::new(device, camera, camera_projection, "main");
+    let (light, light_bind_group_layout) = initialize_light(device);
+    let (outline, outline_bind_group_layout) = initialize_outline(device, surface_configuration);
+
+    let layouts = BindGroupLayouts {
+        camera: camera_bind_group_layout,
+        diffuse: diffuse_bind_group_layout(device, "diffuse-texture"),
+        font: font_bind_group_layout(device),
+        light: light_bind_group_layout,
+        outline: outline_bind_group_layout,
+    };
+
+    let (render_pipeline, view_pipelines) = create_scene_pipelines(device, &layouts, target, shaders);
+    let light_pipeline = create_light_pipeline(device, &layouts, target, shaders);
+    let (outline_mask_pipeline, outline_pipeline) = create_outline_pipelines(device, &layouts, target, shaders);
+    let gizmo_pipeline = create_line_pipeline(device, &layouts, target, true, shaders, "gizmo");
+    let debug_pipeline = create_line_pipeline(device, &layouts, target, false, shaders, "debug draw");
+    let text_pipeline = create_text_pipeline(device, &layouts, target, shaders);
+
+    let depth_texture = Texture::create_depth_texture(device, surface_configuration, sample_count, "depth texture");
+
+    // Resolved into the surface at the end of the scene pass
+    let multisampled_framebuffer = (sample_count > 1).then(|| Texture::create_render_target(
+        device,
+        surface_configuration,
+        sample_count,
+        TextureUsages::empty(),
+        "multisampled framebuffer",
+    ));
+
+    GpuResources {
+        camera_configuration,
+        debug_lines: DynamicVertexBuffer::new(device, 4096, "debug draw"),
+        debug_pipeline,
+        depth_texture,
+        gizmo_lines: DynamicVertexBuffer::new(device, 256, "gizmo"),
+        gizmo_pipeline,
+        layouts,
+        light,
+        light_pipeline,
+        multisampled_framebuffer,
+        outline,
+        outline_mask_pipeline,
+        outline_pipeline,
+        profiler: PassProfiler::new(device, queue),
+        render_pipeline,
+        sample_count,
+        text_buffer: DynamicVertexBuffer::new(device, 4096, "text"),
+        text_pipeline,
+        ui: Ui::new(device, surface_configuration.format, pixels_per_point),
+        view_pipelines,
+    }
+}
+
+pub async fn request_adapter(
+    instance: &Instance,
+    surface: Option<&Surface>,
+    options: &Options,
+) -> Result<Adapter, InitError> {
+    // Adapters can only be enumerated natively, the web gets what it's given
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(selector) = options.adapter.as_ref() {
+        return select_adapter(instance, options.backends, surface, selector);
+    }
+
     instance.request_adapter(
         &RequestAdapterOptions {
-            power_preference: PowerPreference::default(),
-            compatible_surface: Some(surface),
-            force_fallback_adapter: false,
+            power_preference: options.power_preference,
+            compatible_surface: surface,
+            force_fallback_adapter: options.fallback_adapter,
         },
-    ).await.unwrap()
-
-    // let adapter = instance
-    //     .enumerate_adapters(Backends::all())
-    //     .filter(|adapter| {
-    //         // Check if this adapter supports our surface
-    //         surface.get_preferred_format(&adapter).is_some()
-    //     })
-    //     .next()
-    //     .unwrap();
+    ).await.ok_or_else(|| InitError::NoAdapter(format!("no adapter found for {:?}", options.backends)))
 }
 
-pub async fn request_device(adapter: &Adapter) -> (Device, Queue) {
+pub async fn request_device(adapter: &Adapter) -> Result<(Device, Queue), InitError> {
     adapter.request_device(
         &DeviceDescriptor {
-            features: Features::empty(),
+            // Optional features are only requested when the adapter has them
+            features: adapter.features() & (Features::POLYGON_MODE_LINE | Features::TIMESTAMP_QUERY | compressed::FEATURES),
             // WebGL doesn't support all of wgpu's features, so if
             // we're building for the web we'll have to disable some.
             limits: if cfg!(target_arch = "wasm32") {
@@ -252,5 +797,75 @@ pub async fn request_device(adapter: &Adapter) -> (Device, Queue) {
             label: None,
         },
         None, // Trace path
-    ).await.unwrap()
+    ).await.map_err(InitError::RequestDevice)
+}
+
+// wgpu reports a lost device as an uncaptured error with the loss among its sources, that raises the flag.
+// Anything else is a bug and panics, like wgpu's own handler
+pub fn watch_device_loss(device: &Device, lost: &Arc<AtomicBool>) {
+    let lost = Arc::clone(lost);
+
+    device.on_uncaptured_error(move |err| {
+        assert!(is_device_loss(&err), "wgpu error: {err}");
+
+        error!("device lost: {err}");
+        lost.store(true, Ordering::Release);
+    });
+}
+
+// wgpu doesn't have an error of its own for it, the sources are wgpu-core's
+fn is_device_loss(err: &Error) -> bool {
+    successors(Some(err as &(dyn std::error::Error + 'static)), |err| err.source())
+        .any(|err| matches!(err.downcast_ref::<DeviceError>(), Some(DeviceError::Lost)))
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use wgpu_core::resource::CreateBufferError;
+
+    use super::*;
+
+    fn fallback_device() -> Option<Device> {
+        let instance = Instance::new(Backends::all());
+        let options = RequestAdapterOptions { force_fallback_adapter: true, ..RequestAdapterOptions::default() };
+        let adapter = pollster::block_on(instance.request_adapter(&options))?;
+
+        Some(pollster::block_on(request_device(&adapter)).expect("couldn't get a device").0)
+    }
+
+    #[test]
+    fn losses_are_found_among_the_sources() {
+        let lost = Error::OutOfMemory { source: Box::new(DeviceError::Lost) };
+        let invalid = Error::Validation { source: Box::new(DeviceError::Invalid), description: String::new() };
+
+        assert!(is_device_loss(&lost));
+        assert!(!is_device_loss(&invalid));
+    }
+
+    // Fails when wgpu uses another wgpu-core than the one it's downcast with
+    #[test]
+    fn errors_come_from_the_same_wgpu_core() {
+        let Some(device) = fallback_device() else {
+            eprintln!("skipped, there's no software adapter");
+            return;
+        };
+
+        device.push_error_scope(ErrorFilter::Validation);
+
+        // Mapping for writes only goes with copying from
+        device.create_buffer(&BufferDescriptor {
+            label: None,
+            size: 4,
+            usage: BufferUsages::MAP_WRITE | BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+
+        let err = pollster::block_on(device.pop_error_scope()).expect("the buffer was valid");
+
+        assert!(
+            successors(Some(&err as &(dyn std::error::Error + 'static)), |err| err.source())
+                .any(|err| err.downcast_ref::<CreateBufferError>().is_some()),
+            "{err:?}",
+        );
+    }
 }
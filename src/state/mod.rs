@@ -1,29 +1,117 @@
-use wgpu::{Buffer, Device, Queue, RenderPipeline, Surface, SurfaceConfiguration};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
+use cgmath::Matrix4;
+use wgpu::{AdapterInfo, BindGroup, Buffer, Device, Instance as GpuInstance, Queue, RenderPipeline, Surface, SurfaceConfiguration};
+use winit::dpi::PhysicalPosition;
+
+use crate::assets::{Assets, Handle};
+use crate::error::InitError;
+use crate::input::{Gamepad, InputBindings};
 use crate::models::{
-    Camera, CameraConfiguration, CameraController, CameraProjection,
-    Instance, Light, Model, Texture,
+    Camera, CameraAnimation, CameraConfiguration, CameraController, CameraProjection, DebugDraw,
+    DynamicVertexBuffer, FrameStats, Gizmo, Instance, Light, LineVertex, Model, Outline,
+    PassProfiler, Pick, Text, TextVertex, Texture, ViewMode,
 };
+use crate::loader::Loader;
+use crate::Options;
+use crate::state::initialize::BindGroupLayouts;
+use crate::state::shaders::Shaders;
+use crate::sources::AssetSource;
+use crate::ui::Ui;
 
 mod state_static;
 mod state_impl;
+mod state_loading;
+#[cfg(not(target_arch = "wasm32"))]
+mod state_headless;
+mod state_recover;
+#[cfg(not(target_arch = "wasm32"))]
+mod state_reload;
+mod state_ui;
 mod initialize;
+mod shaders;
+
+#[derive(Clone, Copy, Debug)]
+pub enum StateEvent {
+    Selected(Pick),
+    SelectionCleared,
+}
+
+// Where frames end up, headless runs render into a texture that's read back.
+// The surface is shared so a state rebuilt after a device loss can take it over
+enum RenderTarget {
+    Surface(Arc<Surface>),
+    Offscreen(Texture),
+}
 
+#[allow(clippy::struct_excessive_bools)]
 pub struct State {
+    // Shown in the stats overlay
+    adapter_info: AdapterInfo,
+    assets: Assets,
+    bindings: InputBindings,
     camera: Camera,
+    camera_animation: Option<CameraAnimation>,
     camera_configuration: CameraConfiguration,
     camera_controller: CameraController,
     camera_projection: CameraProjection,
+    cursor_position: PhysicalPosition<f64>,
+    debug_draw: DebugDraw,
+    // The camera's view projection when debug drawing was enabled, its frustum is drawn
+    debug_frustum: Option<Matrix4<f32>>,
+    debug_lines: DynamicVertexBuffer<LineVertex>,
+    debug_pipeline: RenderPipeline,
     depth_texture: Texture,
     device: Device,
+    // Raised by the uncaptured error handler, see State::recover
+    device_lost: Arc<AtomicBool>,
+    events: VecDeque<StateEvent>,
+    exit_requested: bool,
+    font_bind_group: BindGroup,
+    font_texture: Handle<Texture>,
+    frame_stats: FrameStats,
+    gamepad: Gamepad,
+    gizmo: Gizmo,
+    gizmo_lines: DynamicVertexBuffer<LineVertex>,
+    gizmo_pipeline: RenderPipeline,
+    // The model failing to load while the placeholder is drawn, see State::take_init_error
+    init_error: Option<InitError>,
+    // Kept to find an adapter again after a device loss
+    instance: Arc<GpuInstance>,
     instances: Vec<Instance>,
     instance_buffer: Buffer,
+    layouts: BindGroupLayouts,
     light: Light,
     light_pipeline: RenderPipeline,
+    loader: Loader,
     mouse_pressed: bool,
-    obj_model: Model,
+    // Only there with msaa, the scene is drawn into it and resolved into the target
+    multisampled_framebuffer: Option<Texture>,
+    // A placeholder until the model file has loaded, see State::finish_loads
+    obj_model: Handle<Model>,
+    options: Options,
+    outline: Outline,
+    outline_mask_pipeline: RenderPipeline,
+    outline_pipeline: RenderPipeline,
+    // Until the model has loaded for the first time
+    placeholder: bool,
+    profiler: PassProfiler,
     queue: Queue,
     render_pipeline: RenderPipeline,
-    surface: Surface,
+    render_target: RenderTarget,
+    sample_count: u32,
+    selection: Option<Pick>,
+    shaders: Shaders,
+    show_stats: bool,
+    // Where res files are read from, kept for reloading and for recovering from a device loss
+    source: Arc<dyn AssetSource>,
     surface_configuration: SurfaceConfiguration,
+    text: Text,
+    text_buffer: DynamicVertexBuffer<TextVertex>,
+    text_pipeline: RenderPipeline,
+    ui: Ui,
+    view_mode: ViewMode,
+    view_pipelines: HashMap<ViewMode, RenderPipeline>,
 }
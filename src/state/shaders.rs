@@ -0,0 +1,48 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use wgpu::{ShaderModuleDescriptor, ShaderSource};
+
+const BUILT_IN: [(&str, &str); 5] = [
+    ("light.wgsl", include_str!("../shaders/light.wgsl")),
+    ("line.wgsl", include_str!("../shaders/line.wgsl")),
+    ("outline.wgsl", include_str!("../shaders/outline.wgsl")),
+    ("shader.wgsl", include_str!("../shaders/shader.wgsl")),
+    ("text.wgsl", include_str!("../shaders/text.wgsl")),
+];
+
+// Wgsl sources by file name, built in and swapped for what's on disk by hot reloading
+#[derive(Clone)]
+pub struct Shaders {
+    sources: HashMap<&'static str, Cow<'static, str>>,
+}
+
+impl Default for Shaders {
+    fn default() -> Self {
+        Self {
+            sources: BUILT_IN.into_iter().map(|(file_name, source)| (file_name, Cow::Borrowed(source))).collect(),
+        }
+    }
+}
+
+impl Shaders {
+    // Only the built in file names exist
+    pub fn get(&self, file_name: &str) -> ShaderModuleDescriptor<'_> {
+        let (file_name, source) = self.sources
+            .get_key_value(file_name)
+            .unwrap_or_else(|| panic!("there's no shader called {file_name}"));
+
+        ShaderModuleDescriptor {
+            label: Some(file_name),
+            source: ShaderSource::Wgsl(Cow::Borrowed(source)),
+        }
+    }
+
+    // Gives back the previous source so it can be put back, nothing happens for unknown files
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn replace(&mut self, file_name: &str, source: Cow<'static, str>) -> Option<Cow<'static, str>> {
+        self.sources
+            .get_mut(file_name)
+            .map(|current| std::mem::replace(current, source))
+    }
+}
@@ -0,0 +1,70 @@
+use std::num::NonZeroU32;
+use std::sync::mpsc::channel;
+
+use anyhow::{anyhow, bail};
+use image::RgbaImage;
+use wgpu::{
+    BufferAddress, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d, ImageCopyBuffer,
+    ImageDataLayout, Maintain, MapMode, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use crate::state::{RenderTarget, State};
+
+impl State {
+    // Reads back the last frame rendered by a headless state, it waits for the gpu
+    #[instrument(skip_all)]
+    pub fn capture(&self) -> anyhow::Result<RgbaImage> {
+        let RenderTarget::Offscreen(texture) = &self.render_target else {
+            bail!("only headless frames can be captured");
+        };
+
+        let (width, height) = (self.surface_configuration.width, self.surface_configuration.height);
+        // Rows in the buffer are padded out to the copy alignment
+        let row_bytes = 4 * width;
+        let padded_row_bytes = row_bytes.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = self.device.create_buffer(&BufferDescriptor {
+            label: Some("capture buffer"),
+            size: BufferAddress::from(padded_row_bytes * height),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("capture encoder"),
+        });
+
+        encoder.copy_texture_to_buffer(
+            texture.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_row_bytes),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+
+        self.queue.submit(Some(encoder.finish()));
+
+        let (sender, receiver) = channel();
+        let slice = buffer.slice(..);
+
+        slice.map_async(MapMode::Read, move |result| drop(sender.send(result)));
+        self.device.poll(Maintain::Wait);
+        receiver.recv()??;
+
+        let pixels = slice
+            .get_mapped_range()
+            .chunks_exact(padded_row_bytes as usize)
+            .flat_map(|row| &row[..row_bytes as usize])
+            .copied()
+            .collect();
+
+        buffer.unmap();
+
+        RgbaImage::from_raw(width, height, pixels).ok_or_else(|| anyhow!("capture buffer is too small"))
+    }
+}
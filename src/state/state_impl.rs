@@ -1,19 +1,30 @@
 use std::iter::once;
 
-use cgmath::{Deg, Quaternion, Rotation3, Vector3};
+use cgmath::{Deg, EuclideanSpace, Matrix4, Point3, Quaternion, Rotation3, SquareMatrix, Vector3};
 #[allow(clippy::wildcard_imports)]
 use wgpu::*;
 use wgpu::LoadOp::Clear;
 use winit::dpi::PhysicalSize;
-use winit::event::{ElementState, KeyboardInput, MouseButton, WindowEvent};
+use winit::event::{ElementState, KeyboardInput, WindowEvent};
 
-use crate::models::{DrawLight, DrawModel, Texture};
-use crate::state::State;
+use crate::input::{Action, Binding};
+use crate::models::{
+    DrawLight, DrawLines, DrawModel, DrawOutline, DrawText, Frustum, Gizmo, GizmoMode, InstanceRaw,
+    loading_overlay, OutlineUniform, pick, Ray, Texture, ViewMode,
+};
+use crate::state::{RenderTarget, State, StateEvent};
 
 impl State {
-    #[inline]
+    #[instrument(skip_all)]
     pub fn reconfigure_surface(&mut self) {
-        self.surface.configure(&self.device, &self.surface_configuration);
+        if let RenderTarget::Surface(surface) = &self.render_target {
+            surface.configure(&self.device, &self.surface_configuration);
+        }
+    }
+
+    #[inline]
+    pub const fn exit_requested(&self) -> bool {
+        self.exit_requested
     }
 
     pub fn input(&mut self, event: &WindowEvent) -> bool {
@@ -26,18 +37,23 @@ impl State {
                     ..
                 },
                 ..
-            } => self.camera_controller.process_keyboard(*key, *state),
-            WindowEvent::MouseWheel { delta, .. } => {
+            } => self.process_binding(Binding::Key(*key), *state),
+            WindowEvent::MouseWheel { delta, .. }
+            if self.bindings.action(Binding::Scroll) == Some(Action::Zoom) => {
                 self.camera_controller.process_scroll(delta);
                 true
             }
-            WindowEvent::MouseInput {
-                button: MouseButton::Left,
-                state,
-                ..
-            } => {
-                self.mouse_pressed = *state == ElementState::Pressed;
-                true
+            WindowEvent::MouseInput { button, state, .. } =>
+                self.process_binding(Binding::Mouse(*button), *state),
+            // Tracked for picking, but left for others to handle too
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = *position;
+
+                if self.gizmo.dragging() {
+                    self.drag_gizmo();
+                }
+
+                false
             }
             _ => false,
         }
@@ -53,20 +69,231 @@ impl State {
         self.camera_controller.process_mouse(x, y);
     }
 
+    fn process_binding(&mut self, binding: Binding, state: ElementState) -> bool {
+        let Some(action) = self.bindings.action(binding) else {
+            return false;
+        };
+
+        let pressed = state == ElementState::Pressed;
+
+        match action {
+            Action::Look => self.mouse_pressed = pressed,
+            Action::ToggleFlythrough => match self.camera_animation.as_mut() {
+                Some(animation) if pressed => animation.toggle(),
+                Some(_) => {}
+                None => return false,
+            },
+            Action::ToggleDebugDraw => if pressed {
+                self.debug_draw.enabled = !self.debug_draw.enabled;
+                self.debug_frustum = self.debug_draw.enabled.then(|| self.view_proj());
+            },
+            Action::ToggleStats => if pressed {
+                self.show_stats = !self.show_stats;
+            },
+            Action::ToggleUi => if pressed {
+                self.ui.visible = !self.ui.visible;
+            },
+            Action::ViewMode(view_mode) => if pressed {
+                self.view_mode = view_mode;
+            },
+            Action::Exit => self.exit_requested |= pressed,
+            Action::Select => if !pressed {
+                self.gizmo.end_drag();
+            } else if !self.begin_gizmo_drag() {
+                self.select_at_cursor();
+            },
+            Action::TranslateGizmo | Action::RotateGizmo | Action::ScaleGizmo => {
+                if pressed && !self.gizmo.dragging() {
+                    self.gizmo.mode = match action {
+                        Action::RotateGizmo => GizmoMode::Rotate,
+                        Action::ScaleGizmo => GizmoMode::Scale,
+                        _ => GizmoMode::Translate,
+                    };
+                }
+            }
+            Action::Snap => self.gizmo.snapping = pressed,
+            _ => return self.camera_controller.process_action(action, state),
+        }
+
+        true
+    }
+
+    #[inline]
+    pub fn next_event(&mut self) -> Option<StateEvent> {
+        self.events.pop_front()
+    }
+
+    pub(super) fn view_proj(&self) -> Matrix4<f32> {
+        self.camera_projection.calc_matrix() * self.camera.calc_matrix()
+    }
+
+    fn cursor_ray(&self) -> Option<Ray> {
+        let size = PhysicalSize::new(self.surface_configuration.width, self.surface_configuration.height);
+
+        Ray::from_cursor(self.cursor_position, size, self.view_proj())
+    }
+
+    fn build_debug_draw(&mut self) {
+        self.debug_draw.clear();
+
+        if !self.debug_draw.enabled {
+            return;
+        }
+
+        self.debug_draw.axes(Matrix4::identity(), 1.0);
+        self.debug_draw.sphere(self.light.uniform.position.into(), 0.5, self.light.uniform.color);
+
+        for instance in &self.instances {
+            for mesh in &self.assets.models[self.obj_model].meshes {
+                self.debug_draw.aabb(&mesh.bounds, instance.matrix(), [0.5, 0.5, 0.5]);
+            }
+        }
+
+        if let Some(selection) = self.selection {
+            let transform = self.instances[selection.instance].matrix();
+
+            self.debug_draw.axes(transform, 1.5);
+            self.debug_draw.tangent_frames(&self.assets.models[self.obj_model].meshes[selection.mesh], transform, 0.2);
+        }
+
+        if let Some(view_proj) = self.debug_frustum {
+            self.debug_draw.frustum(view_proj, [1.0, 0.9, 0.1]);
+        }
+    }
+
+    // Only counted for the overlay, every instance is still drawn
+    fn count_visible_instances(&mut self) {
+        let frustum = Frustum::from_matrix(self.view_proj());
+
+        let visible = self.instances
+            .iter()
+            .filter(|instance| {
+                let transform = instance.matrix();
+
+                self.assets.models[self.obj_model].meshes
+                    .iter()
+                    .any(|mesh| frustum.intersects(&mesh.bounds.transform(transform)))
+            })
+            .count();
+
+        self.frame_stats.visible_instances = visible;
+        self.frame_stats.culled_instances = self.instances.len() - visible;
+    }
+
+    fn build_overlay(&mut self) {
+        #[allow(clippy::cast_precision_loss)]
+        self.text.clear([self.surface_configuration.width as f32, self.surface_configuration.height as f32]);
+
+        if self.show_stats {
+            self.frame_stats.overlay(&mut self.text, &self.adapter_info);
+        }
+
+        // Shown with the stats hidden too, there's only a placeholder to look at until it's done
+        if let Some(status) = self.loading_status() {
+            loading_overlay(&mut self.text, &status);
+        }
+    }
+
+    fn begin_gizmo_drag(&mut self) -> bool {
+        let (Some(selection), Some(ray)) = (self.selection, self.cursor_ray()) else {
+            return false;
+        };
+
+        let instance = &self.instances[selection.instance];
+        let size = Gizmo::size(Point3::from_vec(instance.position), self.camera.position);
+
+        self.gizmo.begin_drag(&ray, instance, size)
+    }
+
+    fn drag_gizmo(&mut self) {
+        let (Some(selection), Some(ray)) = (self.selection, self.cursor_ray()) else {
+            return;
+        };
+
+        // Edits go straight to the instance buffer
+        if self.gizmo.drag(&ray, &mut self.instances[selection.instance]) {
+            self.write_instances();
+        }
+    }
+
+    fn select_at_cursor(&mut self) {
+        let selection = self.cursor_ray()
+            .and_then(|ray| pick(&ray, 0, &self.assets.models[self.obj_model], &self.instances));
+
+        if let Some(previous) = self.selection {
+            self.instances[previous.instance].selected = false;
+        }
+
+        if let Some(selected) = selection {
+            self.instances[selected.instance].selected = true;
+        }
+
+        self.selection = selection;
+        self.write_instances();
+        self.events.push_back(selection.map_or(StateEvent::SelectionCleared, StateEvent::Selected));
+    }
+
+    pub fn set_outline(&mut self, color: [f32; 4], width: f32, viewport: [f32; 2]) {
+        self.outline.uniform = OutlineUniform::new(color, width, viewport);
+        self.queue.write_buffer(&self.outline.buffer, 0, bytemuck::cast_slice(&[self.outline.uniform]));
+    }
+
+    // The barycentric wireframe draws from un-indexed copies of the meshes, they're
+    // only kept while it's shown
+    fn update_wireframe_buffers(&mut self) {
+        let shown = self.view_mode == ViewMode::Wireframe
+            && !self.device.features().contains(Features::POLYGON_MODE_LINE);
+
+        for mesh in self.assets.models.iter_mut().flat_map(|model| &mut model.meshes) {
+            match (&mesh.wireframe_buffer, shown) {
+                (None, true) => mesh.wireframe_buffer = Some(mesh.create_wireframe_buffer(&self.device)),
+                (Some(_), false) => mesh.wireframe_buffer = None,
+                _ => {}
+            }
+        }
+    }
+
+    pub(super) fn write_instances(&self) {
+        let instance_data = self.instances.iter().map(Into::into).collect::<Vec<InstanceRaw>>();
+
+        self.queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instance_data));
+    }
+
+    // Headless targets have nothing to present
+    fn acquire_target(&self) -> Result<(Option<SurfaceTexture>, TextureView), SurfaceError> {
+        match &self.render_target {
+            RenderTarget::Surface(surface) => {
+                let output = surface.get_current_texture()?;
+                let view = output.texture.create_view(&TextureViewDescriptor::default());
+
+                Ok((Some(output), view))
+            }
+            RenderTarget::Offscreen(texture) => Ok((None, texture.texture.create_view(&TextureViewDescriptor::default()))),
+        }
+    }
+
+    #[instrument(skip_all)]
     pub fn render(&mut self) -> Result<(), SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output.texture.create_view(&TextureViewDescriptor::default());
+        let (output, view) = self.acquire_target()?;
+
+        // With msaa the scene is resolved into the target, the ui then draws over the result
+        let (scene_view, resolve_target) = self.multisampled_framebuffer
+            .as_ref()
+            .map_or((&view, None), |framebuffer| (&framebuffer.view, Some(&view)));
 
         let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
             label: Some("render encoder"),
         });
 
+        self.profiler.begin_frame(&self.device);
+        self.profiler.begin_pass(&mut encoder, "main");
+
         {
             let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: Some("render pass"),
                 color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
+                    view: scene_view,
+                    resolve_target,
                     ops: Operations {
                         load: Clear(Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 }),
                         store: true,
@@ -75,54 +302,153 @@ impl State {
                 depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
                     view: &self.depth_texture.view,
                     depth_ops: Some(Operations { load: Clear(1.0), store: true }),
-                    stencil_ops: None,
+                    stencil_ops: Some(Operations { load: Clear(0), store: true }),
                 }),
             });
 
+            let model = &self.assets.models[self.obj_model];
+            let meshes = model.meshes.len();
+            let mesh_triangles = model.triangles();
+
+            let mut draw_calls = meshes;
+            let mut triangles = mesh_triangles;
+
             render_pass.set_pipeline(&self.light_pipeline); // NEW!
             render_pass.draw_light_model(
-                &self.obj_model,
+                model,
                 &self.camera_configuration.bind_group,
                 &self.light.bind_group,
             );
-            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_pipeline(self.view_pipelines.get(&self.view_mode).unwrap_or(&self.render_pipeline));
             render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
 
             #[allow(clippy::cast_possible_truncation)]
-            render_pass.draw_model_instanced(
-                &self.obj_model,
-                0..self.instances.len() as u32,
-                &self.camera_configuration.bind_group,
-                &self.light.bind_group,
-            );
+            let instances = 0..self.instances.len() as u32;
+
+            if self.view_mode == ViewMode::Wireframe && !self.device.features().contains(Features::POLYGON_MODE_LINE) {
+                render_pass.draw_model_wireframe_instanced(
+                    model,
+                    &self.assets.materials,
+                    instances,
+                    &self.camera_configuration.bind_group,
+                    &self.light.bind_group,
+                );
+            } else {
+                render_pass.draw_model_instanced(
+                    model,
+                    &self.assets.materials,
+                    instances,
+                    &self.camera_configuration.bind_group,
+                    &self.light.bind_group,
+                );
+            }
+
+            draw_calls += meshes;
+            triangles += mesh_triangles * self.instances.len();
+
+            // Debug lines are depth tested against the scene, so they go after it
+            render_pass.set_pipeline(&self.debug_pipeline);
+            render_pass.draw_lines(&self.debug_lines, &self.camera_configuration.bind_group);
+            draw_calls += usize::from(self.debug_lines.len > 0);
+
+            if let Some(selection) = self.selection {
+                #[allow(clippy::cast_possible_truncation)]
+                let instance = selection.instance as u32;
+
+                render_pass.set_stencil_reference(1);
+
+                for pipeline in [&self.outline_mask_pipeline, &self.outline_pipeline] {
+                    render_pass.set_pipeline(pipeline);
+                    render_pass.draw_outline_model_instanced(
+                        model,
+                        instance..instance + 1,
+                        &self.camera_configuration.bind_group,
+                        &self.outline.bind_group,
+                    );
+                }
+
+                render_pass.set_pipeline(&self.gizmo_pipeline);
+                render_pass.draw_lines(&self.gizmo_lines, &self.camera_configuration.bind_group);
+
+                draw_calls += 2 * meshes + usize::from(self.gizmo_lines.len > 0);
+                triangles += 2 * mesh_triangles;
+            }
+
+            // The overlay goes over everything, gizmos included
+            render_pass.set_pipeline(&self.text_pipeline);
+            render_pass.draw_text(&self.text_buffer, &self.font_bind_group);
+            draw_calls += usize::from(self.text_buffer.len > 0);
+            triangles += self.text_buffer.len as usize / 3;
+
+            self.frame_stats.draw_calls = draw_calls;
+            self.frame_stats.triangles = triangles;
         }
 
+        self.profiler.end_pass(&mut encoder);
+
+        let ui_commands = self.render_ui(&mut encoder, &view);
+
+        self.profiler.resolve(&mut encoder);
+
         // submit will accept anything that implements IntoIter
-        self.queue.submit(once(encoder.finish()));
+        self.queue.submit(ui_commands.into_iter().chain(once(encoder.finish())));
+        self.ui.free_textures();
+        self.profiler.after_submit();
 
-        output.present();
+        self.frame_stats.pass_timings = self.profiler.timings().to_vec();
+        self.frame_stats.gpu_timestamps = self.profiler.gpu_timestamps();
+
+        if let Some(output) = output {
+            output.present();
+        }
 
         Ok(())
     }
 
+    #[instrument(skip_all)]
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             // self.resize(new_size);
             self.surface_configuration.width = new_size.width;
             self.surface_configuration.height = new_size.height;
             self.camera_projection.resize(new_size.width, new_size.height);
-            self.surface.configure(&self.device, &self.surface_configuration);
+            #[allow(clippy::cast_precision_loss)]
+            self.set_outline(
+                self.outline.uniform.color,
+                self.outline.uniform.width,
+                [new_size.width as f32, new_size.height as f32],
+            );
+            self.reconfigure_surface();
 
             self.depth_texture = Texture::create_depth_texture(
                 &self.device,
                 &self.surface_configuration,
+                self.sample_count,
                 "depth_texture",
             );
+
+            if self.multisampled_framebuffer.is_some() {
+                self.multisampled_framebuffer = Some(Texture::create_render_target(
+                    &self.device,
+                    &self.surface_configuration,
+                    self.sample_count,
+                    TextureUsages::empty(),
+                    "multisampled framebuffer",
+                ));
+            }
         }
     }
 
+    #[instrument(skip_all)]
     pub fn update(&mut self, dt: instant::Duration) {
-        self.camera_controller.update_camera(&mut self.camera, dt);
+        self.finish_loads();
+        self.gamepad.update(&mut self.camera_controller);
+
+        match self.camera_animation.as_mut() {
+            Some(animation) if animation.playing() => animation.update_camera(&mut self.camera, dt),
+            _ => self.camera_controller.update_camera(&mut self.camera, dt),
+        }
+
         self.camera_configuration.uniform.update_view_proj(&self.camera, &self.camera_projection);
         self.queue.write_buffer(&self.camera_configuration.buffer, 0, bytemuck::cast_slice(&[self.camera_configuration.uniform]));
 
@@ -133,5 +459,23 @@ impl State {
             (Quaternion::from_axis_angle((0.0, 1.0, 0.0).into(), Deg(60.0 * dt.as_secs_f32())) * old_position).into(); // UPDATED!
 
         self.queue.write_buffer(&self.light.buffer, 0, bytemuck::cast_slice(&[self.light.uniform]));
+
+        // Gizmos are sized by their distance to the camera, so they're rebuilt every frame
+        let gizmo_lines = self.selection.map_or_else(Vec::new, |selection| {
+            let instance = &self.instances[selection.instance];
+
+            self.gizmo.lines(instance, Gizmo::size(Point3::from_vec(instance.position), self.camera.position))
+        });
+
+        self.gizmo_lines.write(&self.device, &self.queue, &gizmo_lines);
+
+        self.update_wireframe_buffers();
+        self.build_debug_draw();
+        self.debug_lines.write(&self.device, &self.queue, self.debug_draw.vertices());
+
+        self.frame_stats.record_frame(dt.as_secs_f32());
+        self.count_visible_instances();
+        self.build_overlay();
+        self.text_buffer.write(&self.device, &self.queue, self.text.vertices());
     }
 }
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use crate::error::InitError;
+use crate::loader::Loaded;
+use crate::resources::upload_model;
+use crate::state::{State, StateEvent};
+
+impl State {
+    // Models that failed to load are logged and what was drawn in their place stays,
+    // unless it's the placeholder. Not getting the model at all is failing to start, see State::take_init_error
+    pub(super) fn finish_loads(&mut self) {
+        for loaded in self.loader.finished() {
+            if let Err(err) = self.model_loaded(loaded) {
+                self.load_failed(err);
+            }
+        }
+    }
+
+    // Headless runs render what they were asked for, not the placeholder
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn wait_for_loads(&mut self) -> Result<(), InitError> {
+        for loaded in self.loader.wait() {
+            if let Err(err) = self.model_loaded(loaded) {
+                self.load_failed(err);
+            }
+        }
+
+        self.take_init_error().map_or(Ok(()), Err)
+    }
+
+    // Set once the model failed to load in place of the placeholder, the app can't go on without it
+    #[inline]
+    pub const fn take_init_error(&mut self) -> Option<InitError> {
+        self.init_error.take()
+    }
+
+    fn load_failed(&mut self, err: InitError) {
+        if self.placeholder {
+            self.init_error.get_or_insert(err);
+        } else {
+            error!("{:#}", anyhow::Error::new(err));
+        }
+    }
+
+    #[instrument(skip_all, fields(file_name = loaded.file_name))]
+    fn model_loaded(&mut self, loaded: Loaded) -> Result<(), InitError> {
+        let Loaded { file_name, result } = loaded;
+
+        let data = result.map_err(|err| InitError::asset(&file_name, err))?;
+
+        // Released while it was loading
+        let Some(handle) = self.assets.models.handle(&file_name) else {
+            return Ok(());
+        };
+
+        // Takes the key over, the old model is released below along with what only it used
+        let model = upload_model(&file_name, data, &mut self.assets, &self.device, &self.queue, &self.layouts.diffuse);
+        let loaded = self.assets.models.insert(&file_name, model);
+
+        // Edited material values carry over by name from what was drawn until now
+        let edited = self.assets.models[handle].materials
+            .iter()
+            .map(|&material| (self.assets.materials[material].name.clone(), self.assets.materials[material].uniform))
+            .collect::<HashMap<_, _>>();
+
+        for &material in &self.assets.models[loaded].materials {
+            let material = &mut self.assets.materials[material];
+
+            if let Some(&uniform) = edited.get(&material.name) {
+                material.uniform = uniform;
+                self.queue.write_buffer(&material.buffer, 0, bytemuck::cast_slice(&[material.uniform]));
+            }
+        }
+
+        self.assets.release_model(handle);
+
+        if self.obj_model == handle {
+            self.obj_model = loaded;
+            self.placeholder = false;
+        }
+
+        // Picks refer to meshes by index, the new model may not have the same ones
+        if let Some(selection) = self.selection.take() {
+            self.instances[selection.instance].selected = false;
+            self.write_instances();
+            self.events.push_back(StateEvent::SelectionCleared);
+        }
+
+        info!("loaded {file_name}");
+
+        Ok(())
+    }
+
+    // For the overlay, nothing while there's nothing loading
+    pub(super) fn loading_status(&self) -> Option<String> {
+        let pending = self.loader.pending();
+        let progress = self.loader.progress();
+
+        #[allow(clippy::cast_precision_loss)]
+        let kilobytes = progress.bytes() as f32 / 1024.0;
+
+        (!pending.is_empty()).then(|| format!(
+            "loading {}  {} files  {kilobytes:.0} KiB",
+            pending.join(", "),
+            progress.items(),
+        ))
+    }
+}
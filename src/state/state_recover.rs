@@ -0,0 +1,186 @@
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use wgpu::TextureUsages;
+
+use crate::error::InitError;
+use crate::models::{Light, Texture};
+use crate::resources::reupload;
+use crate::state::{RenderTarget, State};
+use crate::state::initialize::{
+    configure_surface, create_font_bind_group, create_gpu_resources, create_instance_buffer, request_adapter,
+    request_device, watch_device_loss,
+};
+
+impl State {
+    #[inline]
+    pub fn device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Acquire)
+    }
+
+    // wgpu can't destroy a device on purpose, so this goes down the same path a real loss would
+    pub fn simulate_device_loss(&self) {
+        warn!("simulating a device loss");
+        self.device_lost.store(true, Ordering::Release);
+    }
+
+    // Everything on the gpu is created again for a new device. Assets are uploaded from the copies kept on the cpu
+    // and nothing is read from the source, what lives on the cpu (camera, instances, selection, edited uniforms...)
+    // stays. Failing leaves the state as it was, so it can be tried again
+    #[allow(clippy::future_not_send)] // todo: the gamepad source is not send
+    #[instrument(skip_all)]
+    pub async fn recover(&mut self) -> Result<(), InitError> {
+        let surface = match &self.render_target {
+            RenderTarget::Surface(surface) => Some(Arc::clone(surface)),
+            RenderTarget::Offscreen(_) => None,
+        };
+
+        let adapter = request_adapter(&self.instance, surface.as_deref(), &self.options).await?;
+        let (device, queue) = request_device(&adapter).await?;
+
+        let (render_target, surface_configuration) = if let Some(surface) = surface {
+            let size = (self.surface_configuration.width, self.surface_configuration.height).into();
+            let configuration = configure_surface(&adapter, &device, &surface, size, self.surface_configuration.present_mode)?;
+
+            (RenderTarget::Surface(surface), configuration)
+        } else {
+            let texture = Texture::create_render_target(
+                &device,
+                &self.surface_configuration,
+                1,
+                TextureUsages::COPY_SRC,
+                "offscreen target",
+            );
+
+            (RenderTarget::Offscreen(texture), self.surface_configuration.clone())
+        };
+
+        // Hot reloaded sources compiled before, they're what was drawn with
+        let resources = create_gpu_resources(
+            &adapter,
+            &device,
+            &queue,
+            &surface_configuration,
+            &self.camera,
+            &self.camera_projection,
+            &self.shaders,
+            self.ui.context().pixels_per_point(),
+            &self.options,
+        );
+
+        reupload(&mut self.assets, &device, &queue, &resources.layouts.diffuse)?;
+
+        self.adapter_info = adapter.get_info();
+        self.device_lost.store(false, Ordering::Release);
+        watch_device_loss(&device, &self.device_lost);
+
+        let font_texture = &self.assets.textures[self.font_texture];
+
+        self.font_bind_group = create_font_bind_group(&device, &resources.layouts.font, font_texture);
+        self.instance_buffer = create_instance_buffer(&device, &self.instances);
+
+        self.camera_configuration = resources.camera_configuration;
+        self.debug_lines = resources.debug_lines;
+        self.debug_pipeline = resources.debug_pipeline;
+        self.depth_texture = resources.depth_texture;
+        self.gizmo_lines = resources.gizmo_lines;
+        self.gizmo_pipeline = resources.gizmo_pipeline;
+        self.layouts = resources.layouts;
+        self.light = Light { uniform: self.light.uniform, ..resources.light };
+        self.light_pipeline = resources.light_pipeline;
+        self.multisampled_framebuffer = resources.multisampled_framebuffer;
+        self.outline_mask_pipeline = resources.outline_mask_pipeline;
+        self.outline_pipeline = resources.outline_pipeline;
+        self.profiler = resources.profiler;
+        self.render_pipeline = resources.render_pipeline;
+        self.sample_count = resources.sample_count;
+        self.text_buffer = resources.text_buffer;
+        self.text_pipeline = resources.text_pipeline;
+        self.ui.visible = std::mem::replace(&mut self.ui, resources.ui).visible;
+        self.view_pipelines = resources.view_pipelines;
+
+        self.device = device;
+        self.queue = queue;
+        self.render_target = render_target;
+        self.surface_configuration = surface_configuration;
+
+        let uniform = self.outline.uniform;
+
+        self.outline = resources.outline;
+        self.set_outline(uniform.color, uniform.width, uniform.viewport);
+
+        info!("recovered from a device loss");
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::borrow::Cow;
+    use std::sync::Arc;
+
+    use cgmath::Point3;
+    use instant::Duration;
+    use wgpu::ShaderSource;
+    use winit::dpi::PhysicalSize;
+
+    use crate::error::InitError;
+    use crate::Options;
+    use crate::sources::MemorySource;
+    use crate::state::State;
+
+    // Nothing moves when no time passes, so frames before and after can be compared
+    fn frame(state: &mut State) -> image::RgbaImage {
+        state.update(Duration::ZERO);
+        state.render().expect("couldn't render");
+        state.capture().expect("couldn't capture")
+    }
+
+    #[test]
+    fn recovers_from_device_loss() {
+        let options = Options { fallback_adapter: true, ..Options::default() };
+
+        let mut state = match pollster::block_on(State::new_headless(&options, PhysicalSize::new(64, 64))) {
+            Ok(state) => state,
+            Err(InitError::NoAdapter(reason)) => {
+                eprintln!("skipped, there's no software adapter: {reason}");
+                return;
+            }
+            Err(err) => panic!("{err}"),
+        };
+
+        state.wait_for_loads().expect("couldn't load the model");
+        // The stats overlay changes from frame to frame
+        state.show_stats = false;
+        state.camera.position = Point3::new(1.0, 4.0, 8.0);
+
+        // Stands in for a hot reload, what was edited is what's drawn with after recovering
+        let edited = format!("{}\n// edited\n", include_str!("../shaders/light.wgsl"));
+
+        state.shaders.replace("light.wgsl", Cow::Owned(edited.clone()));
+
+        // Like the materials panel, an edited uniform has to survive too
+        let material = state.assets.models[state.obj_model].materials[0];
+        let edited_material = &mut state.assets.materials[material];
+
+        edited_material.uniform.shininess = 64.0;
+        state.queue.write_buffer(&edited_material.buffer, 0, bytemuck::cast_slice(&[edited_material.uniform]));
+
+        let before = frame(&mut state);
+
+        state.simulate_device_loss();
+        assert!(state.device_lost());
+
+        // Everything comes back from what's kept on the cpu, none of it is read again
+        state.source = Arc::new(MemorySource::default());
+
+        pollster::block_on(state.recover()).expect("couldn't recover");
+
+        assert!(!state.device_lost());
+        assert_eq!(state.camera.position, Point3::new(1.0, 4.0, 8.0));
+        assert!((state.assets.materials[material].uniform.shininess - 64.0).abs() < f32::EPSILON);
+        assert!(matches!(state.shaders.get("light.wgsl").source, ShaderSource::Wgsl(source) if source == edited));
+        assert!(frame(&mut state) == before, "the frame after recovering is different");
+    }
+}
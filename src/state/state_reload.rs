@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use wgpu::{ErrorFilter, RenderPipeline};
+
+use crate::assets::Handle;
+use crate::models::{ColorSpace, Texture, ViewMode};
+use crate::resources::{material_bind_group, read_texture, texture_key, upload_image};
+use crate::state::State;
+use crate::state::initialize::{
+    create_font_bind_group, create_light_pipeline, create_line_pipeline, create_outline_pipelines,
+    create_scene_pipelines, create_text_pipeline, SceneTarget,
+};
+use crate::watcher::Change;
+
+// What's built from each shader file
+enum Pipelines {
+    Scene(RenderPipeline, HashMap<ViewMode, RenderPipeline>),
+    Light(RenderPipeline),
+    Outline(RenderPipeline, RenderPipeline),
+    Lines(RenderPipeline, RenderPipeline),
+    Text(RenderPipeline),
+}
+
+impl State {
+    // Anything that fails to load or compile is logged, and what was there before stays
+    pub fn reload(&mut self, change: Change) {
+        match change {
+            Change::Shader { file_name, source } => self.reload_shader(&file_name, source),
+            Change::Resource { file_name } => self.reload_resource(&file_name),
+        }
+    }
+
+    #[instrument(skip(self, source))]
+    fn reload_shader(&mut self, file_name: &str, source: String) {
+        let Some(previous) = self.shaders.replace(file_name, Cow::Owned(source)) else {
+            debug!("{file_name} isn't used by any pipeline");
+            return;
+        };
+
+        // Compile errors are validation errors, caught here so the last good pipelines can stay
+        self.device.push_error_scope(ErrorFilter::Validation);
+
+        let pipelines = self.create_pipelines(file_name);
+
+        if let Some(err) = pollster::block_on(self.device.pop_error_scope()) {
+            error!("{file_name} didn't compile, keeping the last good version: {err}");
+            self.shaders.replace(file_name, previous);
+            return;
+        }
+
+        match pipelines {
+            Some(Pipelines::Scene(render_pipeline, view_pipelines)) => {
+                self.render_pipeline = render_pipeline;
+                self.view_pipelines = view_pipelines;
+            }
+            Some(Pipelines::Light(light_pipeline)) => self.light_pipeline = light_pipeline,
+            Some(Pipelines::Outline(outline_mask_pipeline, outline_pipeline)) => {
+                self.outline_mask_pipeline = outline_mask_pipeline;
+                self.outline_pipeline = outline_pipeline;
+            }
+            Some(Pipelines::Lines(gizmo_pipeline, debug_pipeline)) => {
+                self.gizmo_pipeline = gizmo_pipeline;
+                self.debug_pipeline = debug_pipeline;
+            }
+            Some(Pipelines::Text(text_pipeline)) => self.text_pipeline = text_pipeline,
+            None => return,
+        }
+
+        info!("reloaded {file_name}");
+    }
+
+    fn create_pipelines(&self, file_name: &str) -> Option<Pipelines> {
+        let (device, layouts, shaders) = (&self.device, &self.layouts, &self.shaders);
+        let target = SceneTarget { format: self.surface_configuration.format, sample_count: self.sample_count };
+
+        let pipelines = match file_name {
+            "shader.wgsl" => {
+                let (render_pipeline, view_pipelines) = create_scene_pipelines(device, layouts, target, shaders);
+
+                Pipelines::Scene(render_pipeline, view_pipelines)
+            }
+            "light.wgsl" => Pipelines::Light(create_light_pipeline(device, layouts, target, shaders)),
+            "outline.wgsl" => {
+                let (outline_mask_pipeline, outline_pipeline) = create_outline_pipelines(device, layouts, target, shaders);
+
+                Pipelines::Outline(outline_mask_pipeline, outline_pipeline)
+            }
+            "line.wgsl" => Pipelines::Lines(
+                create_line_pipeline(device, layouts, target, true, shaders, "gizmo"),
+                create_line_pipeline(device, layouts, target, false, shaders, "debug draw"),
+            ),
+            "text.wgsl" => Pipelines::Text(create_text_pipeline(device, layouts, target, shaders)),
+            _ => return None,
+        };
+
+        Some(pipelines)
+    }
+
+    #[instrument(skip(self))]
+    fn reload_resource(&mut self, file_name: &str) {
+        // Read again in each color space it's used in
+        let textures = [ColorSpace::Srgb, ColorSpace::Linear]
+            .into_iter()
+            .filter_map(|color_space| {
+                let handle = self.assets.textures.handle(&texture_key(file_name, color_space))?;
+
+                Some((color_space, handle))
+            })
+            .collect::<Vec<_>>();
+
+        // Models don't keep track of the material files they used, so all of them are read again
+        let models = if Path::new(file_name).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("mtl")) {
+            self.assets.models.keys().map(str::to_owned).collect()
+        } else {
+            self.assets.models.handle(file_name).map(|_| file_name.to_owned()).into_iter().collect::<Vec<_>>()
+        };
+
+        if textures.is_empty() && models.is_empty() {
+            debug!("{file_name} isn't loaded");
+            return;
+        }
+
+        for (color_space, handle) in textures {
+            self.reload_texture(file_name, color_space, handle);
+        }
+
+        for model in models {
+            self.reload_model(&model);
+        }
+    }
+
+    fn reload_texture(&mut self, file_name: &str, color_space: ColorSpace, handle: Handle<Texture>) {
+        let sampler = Arc::clone(&self.assets.textures[handle].sampler);
+        let settings = self.assets.samplers.settings(&sampler).unwrap_or_default();
+        let read = read_texture(self.source.as_ref(), file_name, color_space, settings, self.device.features());
+
+        let data = match pollster::block_on(read) {
+            Ok(data) => data,
+            Err(err) => {
+                error!("couldn't reload {file_name}, keeping the last good version: {err:#}");
+                return;
+            }
+        };
+
+        self.assets.textures[handle] = upload_image(data, sampler, &self.device, &self.queue);
+
+        // Bind groups still refer to the old texture
+        let textures = &self.assets.textures;
+
+        for material in self.assets.materials.iter_mut() {
+            if material.diffuse_texture == handle || material.normal_texture == handle {
+                material.bind_group = material_bind_group(
+                    &self.device,
+                    &self.layouts.diffuse,
+                    &textures[material.diffuse_texture],
+                    &material.diffuse_sampler,
+                    &textures[material.normal_texture],
+                    &material.normal_sampler,
+                    &material.buffer,
+                );
+            }
+        }
+
+        if handle == self.font_texture {
+            self.font_bind_group = create_font_bind_group(&self.device, &self.layouts.font, &textures[handle]);
+        }
+
+        info!("reloaded {file_name}");
+    }
+
+    // Swapped in by State::finish_loads once it's read, the last good version is drawn until then
+    fn reload_model(&mut self, file_name: &str) {
+        if self.assets.models.handle(file_name).is_some() {
+            self.loader.load_model(file_name);
+        }
+    }
+}
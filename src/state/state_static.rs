@@ -1,89 +1,281 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
 use cgmath::Deg;
-use wgpu::{Backends, Instance};
+use wgpu::{Adapter, Device, Instance, Queue, SurfaceConfiguration, TextureUsages};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::window::Window;
 
-use crate::models::{Camera, CameraConfiguration, CameraController, CameraProjection, InstanceRaw, ModelVertex, Texture, Vertex};
-use crate::resources::load_model;
-use crate::State;
+use crate::models::{
+    Camera, CameraAnimation, CameraController, CameraProjection, ColorSpace, DebugDraw, FrameStats, Gizmo,
+    SamplerSettings, Text, Texture, ViewMode,
+};
+use crate::assets::Assets;
+use crate::input::{default_gamepad_source, Gamepad, GamepadSettings, InputBindings};
+use crate::error::InitError;
+use crate::Options;
+use crate::loader::Loader;
+use crate::resources::{
+    load_camera_track, load_gamepad_settings, load_input_bindings, load_scene, load_texture, placeholder_model,
+    upload_model,
+};
+use crate::state::{RenderTarget, State};
 use crate::state::initialize::{
-    configure_surface, create_render_pipeline, diffuse_bind_group_layout,
-    get_instances, initialize_light, request_adapter, request_device,
+    configure_surface, create_font_bind_group, create_gpu_resources, create_instance_buffer, default_instances,
+    offscreen_configuration, request_adapter, request_device, watch_device_loss, GpuResources,
 };
+use crate::state::shaders::Shaders;
+use crate::sources::{default_source, AssetSource};
 
 impl State {
     // Creating some of the wgpu types requires async code
     #[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: winit window is not send
-    pub async fn new(window: &Window) -> Self {
-        let size = window.inner_size();
+    #[instrument(name = "initialize", skip_all)]
+    pub async fn new(window: &Window, options: &Options) -> Result<Self, InitError> {
         // The instance is a handle to our GPU
         // Backends::all => Vulkan + Metal + DX12 + Browser WebGPU
-        let instance = Instance::new(Backends::all());
+        let source = default_source(options).await.map_err(|err| InitError::asset("res", err))?;
+        let instance = Arc::new(Instance::new(options.backends));
         let surface = unsafe { instance.create_surface(window) };
-        let adapter = request_adapter(&instance, &surface).await;
-        let (device, queue) = request_device(&adapter).await;
-        let surface_configuration = configure_surface(&adapter, &device, &surface, size);
-        let (instances, instance_buffer) = get_instances(&device);
-        let camera = Camera::new((0.0, 5.0, 10.0), Deg(-90.0), Deg(-20.0));
-        let camera_controller = CameraController::new(4.0, 0.4);
-        let camera_projection = CameraProjection::new(surface_configuration.width, surface_configuration.height, Deg(45.0), 0.1, 100.0);
-        let (camera_configuration, camera_bind_group_layout) = CameraConfiguration::new(&device, &camera, &camera_projection, "main");
-        let (light, light_bind_group_layout) = initialize_light(&device);
-        let diffuse_bind_group_layout = diffuse_bind_group_layout(&device, "diffuse-texture");
-
-        let render_pipeline = create_render_pipeline(
+        let adapter = request_adapter(&instance, Some(&surface), options).await?;
+        let (device, queue) = request_device(&adapter).await?;
+        let surface_configuration = configure_surface(
+            &adapter,
             &device,
-            &[
-                &diffuse_bind_group_layout,
-                &camera_bind_group_layout,
-                &light_bind_group_layout,
-            ],
-            surface_configuration.format,
-            Some(Texture::DEPTH_FORMAT),
-            &[ModelVertex::desc(), InstanceRaw::desc()],
-            include_wgsl!("../shaders/shader.wgsl"),
-            "shader",
-        );
+            &surface,
+            window.inner_size(),
+            options.present_mode(),
+        )?;
 
-        let light_pipeline = create_render_pipeline(
-            &device,
-            &[&camera_bind_group_layout, &light_bind_group_layout],
-            surface_configuration.format,
-            Some(Texture::DEPTH_FORMAT),
-            &[ModelVertex::desc()],
-            include_wgsl!("../shaders/light.wgsl"),
-            "light",
-        );
+        #[allow(clippy::cast_possible_truncation)]
+        let pixels_per_point = window.scale_factor() as f32;
+
+        Self::with_target(
+            instance,
+            source,
+            &adapter,
+            device,
+            queue,
+            RenderTarget::Surface(Arc::new(surface)),
+            surface_configuration,
+            pixels_per_point,
+            Shaders::default(),
+            options,
+        ).await
+    }
 
-        let depth_texture = Texture::create_depth_texture(
+    // Renders into a texture instead of a window, see State::capture
+    #[cfg(not(target_arch = "wasm32"))]
+    #[instrument(name = "initialize", skip_all)]
+    pub async fn new_headless(options: &Options, size: PhysicalSize<u32>) -> Result<Self, InitError> {
+        let source = default_source(options).await.map_err(|err| InitError::asset("res", err))?;
+        let instance = Arc::new(Instance::new(options.backends));
+        let adapter = request_adapter(&instance, None, options).await?;
+        let (device, queue) = request_device(&adapter).await?;
+        let surface_configuration = offscreen_configuration(size);
+        let texture = Texture::create_render_target(
             &device,
             &surface_configuration,
-            "depth texture",
+            1,
+            TextureUsages::COPY_SRC,
+            "offscreen target",
         );
 
-        let obj_model = load_model(
-            "cube.obj",
+        let mut state = Self::with_target(
+            instance,
+            source,
+            &adapter,
+            device,
+            queue,
+            RenderTarget::Offscreen(texture),
+            surface_configuration,
+            1.0,
+            Shaders::default(),
+            options,
+        ).await?;
+
+        // Nothing can interact with it
+        state.ui.visible = false;
+
+        Ok(state)
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(clippy::future_not_send))] // todo: ???
+    #[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+    pub(super) async fn with_target(
+        instance: Arc<Instance>,
+        source: Arc<dyn AssetSource>,
+        adapter: &Adapter,
+        device: Device,
+        queue: Queue,
+        render_target: RenderTarget,
+        surface_configuration: SurfaceConfiguration,
+        pixels_per_point: f32,
+        shaders: Shaders,
+        options: &Options,
+    ) -> Result<Self, InitError> {
+        let adapter_info = adapter.get_info();
+
+        info!("using {} on {:?}", adapter_info.name, adapter_info.backend);
+
+        let device_lost = Arc::new(AtomicBool::new(false));
+
+        watch_device_loss(&device, &device_lost);
+
+        // An explicitly requested scene has to load, like the model
+        let scene = match options.scene.as_deref() {
+            Some(file_name) => Some(load_scene(source.as_ref(), file_name).await.map_err(|err| InitError::asset(file_name, err))?),
+            None => None,
+        };
+
+        let model_file = options.model.as_deref()
+            .or_else(|| scene.as_ref().and_then(|scene| scene.model.as_deref()))
+            .unwrap_or("cube.obj");
+
+        let instances = match scene.as_ref() {
+            Some(scene) if !scene.instances.is_empty() => scene.instances.iter().map(Into::into).collect(),
+            Some(_) => {
+                warn!("scene has no instances, using the default grid");
+                default_instances()
+            }
+            None => default_instances(),
+        };
+
+        let instance_buffer = create_instance_buffer(&device, &instances);
+        let camera = Camera::new((0.0, 5.0, 10.0), Deg(-90.0), Deg(-20.0));
+        let camera_controller = CameraController::new(4.0, 0.4);
+        let camera_projection = CameraProjection::new(surface_configuration.width, surface_configuration.height, Deg(45.0), 0.1, 100.0);
+
+        let GpuResources {
+            camera_configuration,
+            debug_lines,
+            debug_pipeline,
+            depth_texture,
+            gizmo_lines,
+            gizmo_pipeline,
+            layouts,
+            light,
+            light_pipeline,
+            multisampled_framebuffer,
+            outline,
+            outline_mask_pipeline,
+            outline_pipeline,
+            profiler,
+            render_pipeline,
+            sample_count,
+            text_buffer,
+            text_pipeline,
+            ui,
+            view_pipelines,
+        } = create_gpu_resources(
+            adapter,
             &device,
             &queue,
-            &diffuse_bind_group_layout,
-        ).await.unwrap();
+            &surface_configuration,
+            &camera,
+            &camera_projection,
+            &shaders,
+            pixels_per_point,
+            options,
+        );
+
+        // The atlas is loaded like any other resource so the overlay works on the web too
+        let mut assets = Assets::default();
+        // Glyphs at the edge of the atlas would pick up the other side if it repeated
+        let font_texture = load_texture(
+            source.as_ref(), "font.png", ColorSpace::Srgb, SamplerSettings::CLAMP, &mut assets, &device, &queue,
+        ).await.map_err(|err| InitError::asset("font.png", err))?;
+        let font_bind_group = create_font_bind_group(&device, &layouts.font, &assets.textures[font_texture]);
+
+        #[allow(clippy::cast_precision_loss)]
+        let text = Text::new([surface_configuration.width as f32, surface_configuration.height as f32]);
+
+        // The first frames draw a placeholder, the model swaps in once it's read in the background
+        let mut loader = Loader::new(&source, device.features());
+        let placeholder = upload_model(model_file, placeholder_model(), &mut assets, &device, &queue, &layouts.diffuse);
+        let obj_model = assets.models.insert(model_file, placeholder);
+
+        loader.load_model(model_file);
+
+        // A missing flythrough isn't fatal, it just disables playback
+        let camera_animation = load_camera_track(source.as_ref(), "flythrough.ron").await
+            .map_err(|err| warn!("camera track not loaded: {err}"))
+            .ok()
+            .map(CameraAnimation::new);
+
+        let bindings = load_input_bindings(source.as_ref(), "bindings.ron").await
+            .unwrap_or_else(|err| {
+                warn!("input bindings not loaded, using defaults: {err}");
+                InputBindings::default()
+            });
+
+        let gamepad_settings = load_gamepad_settings(source.as_ref(), "gamepad.ron").await
+            .unwrap_or_else(|err| {
+                warn!("gamepad settings not loaded, using defaults: {err}");
+                GamepadSettings::default()
+            });
+
+        let gamepad = Gamepad::new(gamepad_settings, default_gamepad_source());
 
-        Self {
+        Ok(Self {
+            adapter_info,
+            assets,
+            bindings,
             camera,
+            camera_animation,
             camera_configuration,
             camera_controller,
             camera_projection,
+            cursor_position: PhysicalPosition::new(0.0, 0.0),
+            debug_draw: DebugDraw::default(),
+            debug_frustum: None,
+            debug_lines,
+            debug_pipeline,
             depth_texture,
             device,
+            device_lost,
+            events: VecDeque::new(),
+            exit_requested: false,
+            font_bind_group,
+            font_texture,
+            frame_stats: FrameStats::default(),
+            gamepad,
+            gizmo: Gizmo::default(),
+            gizmo_lines,
+            gizmo_pipeline,
+            init_error: None,
+            instance,
             instances,
             instance_buffer,
+            layouts,
             light,
             light_pipeline,
+            loader,
             mouse_pressed: false,
+            multisampled_framebuffer,
             obj_model,
+            options: options.clone(),
+            outline,
+            outline_mask_pipeline,
+            outline_pipeline,
+            placeholder: true,
+            profiler,
             queue,
             render_pipeline,
-            surface,
+            render_target,
+            sample_count,
+            selection: None,
+            shaders,
+            source,
+            show_stats: true,
             surface_configuration,
-        }
+            text,
+            text_buffer,
+            text_pipeline,
+            ui,
+            view_mode: ViewMode::default(),
+            view_pipelines,
+        })
     }
 }
@@ -0,0 +1,172 @@
+use std::iter::once;
+
+use cgmath::{Deg, Rad};
+use egui::{Align2, CollapsingHeader, ComboBox, Context, DragValue, Slider, Ui as Panel};
+use wgpu::{
+    CommandBuffer, CommandEncoder, LoadOp, Operations, RenderPassColorAttachment, RenderPassDescriptor,
+    TextureView,
+};
+use winit::event::WindowEvent;
+use winit::window::Window;
+
+use crate::models::ViewMode;
+use crate::state::State;
+
+impl State {
+    #[inline]
+    pub fn ui_input(&mut self, event: &WindowEvent) -> bool {
+        self.ui.on_event(event)
+    }
+
+    #[instrument(skip_all)]
+    pub fn update_ui(&mut self, window: &Window) {
+        if !self.ui.visible {
+            self.ui.clear();
+            return;
+        }
+
+        let context = self.ui.context();
+        let input = self.ui.take_input(window);
+        let output = context.run(input, |context| self.build_ui(context));
+
+        self.ui.finish(window, output);
+    }
+
+    // The ui gets a pass of its own, the last one, without depth
+    pub(super) fn render_ui(&mut self, encoder: &mut CommandEncoder, view: &TextureView) -> Vec<CommandBuffer> {
+        let size_in_pixels = [self.surface_configuration.width, self.surface_configuration.height];
+        let commands = self.ui.prepare(&self.device, &self.queue, encoder, size_in_pixels);
+
+        if self.ui.has_paint_jobs() {
+            self.profiler.begin_pass(encoder, "ui");
+
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("ui render pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations { load: LoadOp::Load, store: true },
+                })],
+                depth_stencil_attachment: None,
+            });
+
+            self.ui.render(&mut render_pass, size_in_pixels);
+            drop(render_pass);
+
+            self.profiler.end_pass(encoder);
+        }
+
+        commands
+    }
+
+    fn build_ui(&mut self, context: &Context) {
+        egui::Window::new("Tweaks")
+            .anchor(Align2::RIGHT_TOP, [-8.0, 8.0])
+            .resizable(false)
+            .show(context, |panel| {
+                CollapsingHeader::new("Light")
+                    .default_open(true)
+                    .show(panel, |panel| self.light_panel(panel));
+                CollapsingHeader::new("Camera")
+                    .show(panel, |panel| self.camera_panel(panel));
+                CollapsingHeader::new("Materials")
+                    .show(panel, |panel| self.materials_panel(panel));
+                CollapsingHeader::new("Render")
+                    .show(panel, |panel| self.render_panel(panel));
+            });
+    }
+
+    // The light uniform is written every update, so edits don't need to be
+    fn light_panel(&mut self, panel: &mut Panel) {
+        let light = &mut self.light.uniform;
+
+        panel.horizontal(|panel| {
+            panel.label("color");
+            panel.color_edit_button_rgb(&mut light.color);
+        });
+
+        panel.horizontal(|panel| {
+            panel.label("position");
+            for value in &mut light.position {
+                panel.add(DragValue::new(value).speed(0.1));
+            }
+        });
+
+        panel.add(Slider::new(&mut light.ambient_strength, 0.0..=1.0).text("ambient"));
+    }
+
+    fn camera_panel(&mut self, panel: &mut Panel) {
+        panel.add(Slider::new(&mut self.camera_controller.speed, 0.5..=20.0).text("speed"));
+        panel.add(Slider::new(&mut self.camera_controller.sensitivity, 0.05..=2.0).text("sensitivity"));
+
+        let mut fovy = Deg::from(self.camera_projection.fovy).0;
+
+        if panel.add(Slider::new(&mut fovy, 20.0..=120.0).text("fov")).changed() {
+            self.camera_projection.fovy = Rad::from(Deg(fovy));
+        }
+
+        panel.add(Slider::new(&mut self.camera_projection.znear, 0.01..=1.0).logarithmic(true).text("near"));
+        panel.add(Slider::new(&mut self.camera_projection.zfar, 10.0..=1000.0).logarithmic(true).text("far"));
+
+        let position = self.camera.position;
+
+        panel.label(format!(
+            "position {:.2}, {:.2}, {:.2}\nyaw {:.1}, pitch {:.1}",
+            position.x,
+            position.y,
+            position.z,
+            Deg::from(self.camera.yaw).0,
+            Deg::from(self.camera.pitch).0,
+        ));
+    }
+
+    fn materials_panel(&mut self, panel: &mut Panel) {
+        for &handle in &self.assets.models[self.obj_model].materials {
+            let material = &mut self.assets.materials[handle];
+
+            panel.label(&material.name);
+
+            let strength = panel.add(Slider::new(&mut material.uniform.specular_strength, 0.0..=4.0).text("specular"));
+            let shininess = panel.add(Slider::new(&mut material.uniform.shininess, 1.0..=256.0).logarithmic(true).text("shininess"));
+
+            if strength.changed() || shininess.changed() {
+                self.queue.write_buffer(&material.buffer, 0, bytemuck::cast_slice(&[material.uniform]));
+            }
+        }
+    }
+
+    fn render_panel(&mut self, panel: &mut Panel) {
+        ComboBox::from_label("view mode")
+            .selected_text(format!("{:?}", self.view_mode))
+            .show_ui(panel, |panel| {
+                for view_mode in once(ViewMode::Lit).chain(ViewMode::DEBUG) {
+                    panel.selectable_value(&mut self.view_mode, view_mode, format!("{view_mode:?}"));
+                }
+            });
+
+        panel.checkbox(&mut self.show_stats, "stats overlay");
+
+        if panel.checkbox(&mut self.debug_draw.enabled, "debug draw").changed() {
+            self.debug_frustum = self.debug_draw.enabled.then(|| self.view_proj());
+        }
+
+        let mut color = self.outline.uniform.color;
+        let mut width = self.outline.uniform.width;
+
+        let color_changed = panel.horizontal(|panel| {
+            panel.label("outline");
+            panel.color_edit_button_rgba_unmultiplied(&mut color).changed()
+        }).inner;
+
+        let width_changed = panel.add(Slider::new(&mut width, 0.0..=10.0).text("outline width")).changed();
+
+        if color_changed || width_changed {
+            self.set_outline(color, width, self.outline.uniform.viewport);
+        }
+
+        panel.checkbox(&mut self.gizmo.snapping, "snap gizmo");
+        panel.add(Slider::new(&mut self.gizmo.snap.translation, 0.05..=5.0).text("translation snap"));
+        panel.add(Slider::new(&mut self.gizmo.snap.rotation.0, 1.0..=90.0).text("rotation snap"));
+        panel.add(Slider::new(&mut self.gizmo.snap.scale, 0.05..=1.0).text("scale snap"));
+    }
+}
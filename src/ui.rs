@@ -0,0 +1,116 @@
+use egui::{ClippedPrimitive, Context, FullOutput, RawInput, TexturesDelta};
+use egui_wgpu::renderer::{Renderer, ScreenDescriptor};
+use wgpu::{CommandBuffer, CommandEncoder, Device, Queue, RenderPass, TextureFormat};
+use winit::event::{ElementState, KeyboardInput, WindowEvent};
+use winit::window::Window;
+
+// egui glue, the panels themselves are built by State::update_ui.
+// egui expects a gamma space target, so on srgb surfaces its colors come out a little light
+pub struct Ui {
+    pub visible: bool,
+    context: Context,
+    input: egui_winit::State,
+    renderer: Renderer,
+    paint_jobs: Vec<ClippedPrimitive>,
+    textures_delta: TexturesDelta,
+}
+
+impl Ui {
+    pub fn new(device: &Device, format: TextureFormat, pixels_per_point: f32) -> Self {
+        // The wayland display is only used for the clipboard, which isn't enabled
+        let mut input = egui_winit::State::new_with_wayland_display(None);
+
+        input.set_max_texture_side(device.limits().max_texture_dimension_2d as usize);
+        input.set_pixels_per_point(pixels_per_point);
+
+        Self {
+            visible: true,
+            context: Context::default(),
+            input,
+            renderer: Renderer::new(device, format, None, 1),
+            paint_jobs: Vec::new(),
+            textures_delta: TexturesDelta::default(),
+        }
+    }
+
+    // True when the ui used the event and nothing else should handle it.
+    // Releases are never consumed, so a button pressed over the scene can't get stuck
+    pub fn on_event(&mut self, event: &WindowEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        let consumed = self.input.on_event(&self.context, event).consumed;
+
+        let released = matches!(
+            event,
+            WindowEvent::MouseInput { state: ElementState::Released, .. }
+                | WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Released, .. }, .. }
+        );
+
+        consumed && !released
+    }
+
+    // Contexts are cheap to clone, a clone lets the caller borrow itself while building panels
+    #[inline]
+    pub fn context(&self) -> Context {
+        self.context.clone()
+    }
+
+    #[inline]
+    pub fn take_input(&mut self, window: &Window) -> RawInput {
+        self.input.take_egui_input(window)
+    }
+
+    pub fn finish(&mut self, window: &Window, output: FullOutput) {
+        self.input.handle_platform_output(window, &self.context, output.platform_output);
+        self.paint_jobs = self.context.tessellate(output.shapes);
+        self.textures_delta.append(output.textures_delta);
+    }
+
+    pub fn clear(&mut self) {
+        self.paint_jobs.clear();
+    }
+
+    // Uploads textures and geometry, returns any command buffers egui's callbacks recorded
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        size_in_pixels: [u32; 2],
+    ) -> Vec<CommandBuffer> {
+        for (id, delta) in self.textures_delta.set.drain(..) {
+            self.renderer.update_texture(device, queue, id, &delta);
+        }
+
+        let screen = self.screen_descriptor(size_in_pixels);
+
+        self.renderer.update_buffers(device, queue, encoder, &self.paint_jobs, &screen)
+    }
+
+    pub fn render<'a>(&'a self, render_pass: &mut RenderPass<'a>, size_in_pixels: [u32; 2]) {
+        self.renderer.render(render_pass, &self.paint_jobs, &self.screen_descriptor(size_in_pixels));
+    }
+
+    // Textures can only be freed once the frame that last used them was submitted
+    pub fn free_textures(&mut self) {
+        for id in self.textures_delta.free.drain(..) {
+            self.renderer.free_texture(&id);
+        }
+    }
+
+    // Vec::is_empty is only const from rust 1.87 on
+    #[inline]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn has_paint_jobs(&self) -> bool {
+        !self.paint_jobs.is_empty()
+    }
+
+    fn screen_descriptor(&self, size_in_pixels: [u32; 2]) -> ScreenDescriptor {
+        ScreenDescriptor {
+            size_in_pixels,
+            pixels_per_point: self.context.pixels_per_point(),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver};
+
+use notify::{recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+// The source tree the binary was built from, so edits there show up while it runs
+const SHADER_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders");
+pub const RES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/res");
+
+pub enum Change {
+    Shader { file_name: String, source: String },
+    // Relative to res, like the loaders expect
+    Resource { file_name: String },
+}
+
+pub struct Watcher {
+    // Watching stops when it's dropped
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl Watcher {
+    pub fn new() -> notify::Result<Self> {
+        let (sender, events) = channel();
+        let mut watcher = recommended_watcher(sender)?;
+
+        watcher.watch(Path::new(SHADER_DIR), RecursiveMode::NonRecursive)?;
+        watcher.watch(Path::new(RES_DIR), RecursiveMode::Recursive)?;
+
+        info!("watching {SHADER_DIR} and {RES_DIR} for changes");
+
+        Ok(Self { _watcher: watcher, events })
+    }
+
+    // What changed since the last call, a file only shows up once however many events saving it caused
+    pub fn changes(&self) -> Vec<Change> {
+        let paths = self.events
+            .try_iter()
+            .filter_map(|event| event.map_err(|err| warn!("file watcher: {err}")).ok())
+            .filter(|event| matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)))
+            .flat_map(|event| event.paths)
+            .filter(|path| path.is_file())
+            .collect::<BTreeSet<_>>();
+
+        paths.iter().filter_map(|path| change(path)).collect()
+    }
+}
+
+fn change(path: &Path) -> Option<Change> {
+    if let Ok(file_name) = path.strip_prefix(SHADER_DIR) {
+        return fs::read_to_string(path)
+            .map_err(|err| warn!("couldn't read {}: {err}", path.display()))
+            .ok()
+            .map(|source| Change::Shader { file_name: file_name.to_string_lossy().into_owned(), source });
+    }
+
+    // Res files are read from the source tree while watching, see sources::default_source
+    let file_name = path.strip_prefix(RES_DIR).ok()?;
+
+    Some(Change::Resource { file_name: file_name.to_string_lossy().replace('\\', "/") })
+}